@@ -1,16 +1,94 @@
 use chrono::{NaiveDate, Datelike};
-use std::io::{self, Write};
-use std::path::Path;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::cmp::Ordering;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 use thiserror::Error;
 
-use crate::cli::{AddArgs, ListArgs, SummaryArgs, CategoryArgs, CategoryCommands};
-use crate::cli::helpers::{parse_date, validate_category, validate_amount, default_description, parse_date_range};
-use crate::models::category::CategoryRegistry;
-use crate::models::expense::Expense;
-use crate::repository::{ExpenseRepository, RepositoryError};
-use crate::config::Config;
+use crate::cli::{AddArgs, QuickArgs, EditArgs, DeleteArgs, ListArgs, ListFormat, TailArgs, SummaryArgs, SummaryFormat, CategorySort, SortOrder, TotalArgs, StatsArgs, AlertsArgs, HistoryArgs, BackupArgs, BackupFormat, ExportArgs, RestoreArgs, ImportArgs, ImportFormat, BulkEditArgs, TrashArgs, TrashCommands, ConfigArgs, ConfigCommands, ConfigFormat, RollupArgs, RollupCommands, CategoryArgs, CategoryCommands, BudgetArgs, BudgetCommands, RecurringArgs, RecurringCommands};
+use crate::cli::helpers::{CliError, parse_date, parse_compare_range, parse_month, parse_month_range, parse_year_range, parse_amount_expr, parse_quick_input, parse_category_filter, validate_category, validate_amount, validate_precision, round_to_nearest, normalize_whitespace, default_description, parse_date_range};
+use crate::backup::{self, BackupError};
+use crate::export::{self, MonthlyTotals, SummaryReport};
+use crate::import::{self, ColumnMapping, ImportError};
+use crate::models::category::{Category, CategoryError, CategoryRegistry};
+use crate::models::expense::{Expense, ExpenseError};
+use crate::models::recurring::RecurringExpense;
+use crate::models::stats::LifetimeStats;
+use crate::repository::{ExpenseRepository, MonthlyCategoryTotals, RepositoryError};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{self, Config};
+use crate::table::Table;
+
+/// Exit code for failures without a more specific code, such as a failed `alerts` check
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for invalid arguments or input data, or a command refused in read-only mode
+pub const EXIT_INVALID_INPUT: i32 = 2;
+
+/// Exit code for a category or expense that doesn't exist
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// Exit code for database failures, including a locked database or a conflicting edit,
+/// which may succeed when retried
+pub const EXIT_DATABASE: i32 = 4;
+
+/// Exit code for a config file that can't be read, parsed or written
+pub const EXIT_CONFIG: i32 = 5;
+
+/// Expenses matched by `list`, `total` or `tail`, with their total
+#[derive(Debug, Clone)]
+pub struct ExpenseListing {
+    pub expenses: Vec<Expense>,
+    /// Sum of the amounts
+    pub total: Decimal,
+    /// The page shown and the number of pages, for a listing split into pages
+    pub page: Option<(usize, usize)>,
+}
+
+impl ExpenseListing {
+    pub fn new(expenses: Vec<Expense>) -> Self {
+        let total = expenses.iter().map(Expense::amount).sum();
+        Self { expenses, total, page: None }
+    }
+    
+    pub fn count(&self) -> usize {
+        self.expenses.len()
+    }
+}
+
+/// Spending so far this month in a budgeted category
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetStatus {
+    pub category: String,
+    pub spent: Decimal,
+    /// The full monthly budget
+    pub budget: Decimal,
+    /// The budget scaled by the days elapsed over the days in the month, when prorating
+    pub prorated: Option<Decimal>,
+}
+
+impl BudgetStatus {
+    /// The budget spending is held against, prorated if it was
+    pub fn target(&self) -> Decimal {
+        self.prorated.unwrap_or(self.budget)
+    }
+}
+
+/// The validated contents of an import file
+struct PreparedImport {
+    /// Expenses ready to be saved
+    expenses: Vec<Expense>,
+    /// The 1-based record number and error of every rejected record
+    errors: Vec<(usize, String)>,
+    /// The 1-based record numbers of valid records already recorded
+    duplicates: Vec<usize>,
+    /// Unknown categories to register first, with `--create-missing-categories`
+    new_categories: Vec<Category>,
+}
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum AppError {
     #[error("Repository error: {0}")]
     RepositoryError(#[from] RepositoryError),
@@ -24,14 +102,75 @@ pub enum AppError {
     #[error("Config error: {0}")]
     ConfigError(#[from] crate::config::ConfigError),
     
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+    
+    #[error("Backup error: {0}")]
+    BackupError(#[from] BackupError),
+    
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    
+    #[error("Import error: {0}")]
+    ImportError(#[from] ImportError),
+    
+    #[error("Expense error: {0}")]
+    ExpenseError(#[from] ExpenseError),
+    
+    #[error("Not allowed in read-only mode: {0}")]
+    ReadOnly(String),
+    
     #[error("{0}")]
     Other(String),
+    
+    /// Wraps another error with a description of what was being attempted
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<AppError>,
+    },
+}
+
+impl AppError {
+    /// Wrap this error with a description of what was being attempted
+    pub fn context(self, context: impl Into<String>) -> Self {
+        AppError::Context { context: context.into(), source: Box::new(self) }
+    }
+    
+    /// Process exit code for this error, one of the `EXIT_*` constants
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::RepositoryError(error) => match error {
+                RepositoryError::NotFound(_) => EXIT_NOT_FOUND,
+                RepositoryError::InvalidOperation(_) => EXIT_INVALID_INPUT,
+                RepositoryError::DatabaseError(_)
+                | RepositoryError::Conflict(_)
+                | RepositoryError::IoError(_)
+                | RepositoryError::Other(_) => EXIT_DATABASE,
+            },
+            AppError::CliError(CliError::CategoryNotFound(_)) => EXIT_NOT_FOUND,
+            AppError::CliError(_) | AppError::ImportError(_) | AppError::ExpenseError(_) | AppError::ReadOnly(_) => EXIT_INVALID_INPUT,
+            AppError::ConfigError(_) => EXIT_CONFIG,
+            AppError::IoError(_)
+            | AppError::CsvError(_)
+            | AppError::BackupError(_)
+            | AppError::JsonError(_)
+            | AppError::Other(_) => EXIT_FAILURE,
+            AppError::Context { source, .. } => source.exit_code(),
+        }
+    }
 }
 
 pub struct App<R: ExpenseRepository> {
     repository: R,
     category_registry: CategoryRegistry,
     config: Config,
+    config_path: PathBuf,
+    config_format: Option<ConfigFormat>,
+    assume_yes: bool,
+    read_only: bool,
+    clock: Box<dyn Clock>,
 }
 
 impl<R: ExpenseRepository> App<R> {
@@ -43,14 +182,166 @@ impl<R: ExpenseRepository> App<R> {
             repository,
             category_registry,
             config,
+            config_path: PathBuf::from("expense_log.yaml"),
+            config_format: None,
+            assume_yes: false,
+            read_only: false,
+            clock: Box::new(SystemClock),
+        }
+    }
+    
+    /// Use the given clock to determine today's date
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+    
+    /// Save config changes, such as added categories, to the given file
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = path.into();
+        self
+    }
+    
+    /// Write the config file in this format instead of the one its extension suggests
+    pub fn with_config_format(mut self, format: Option<ConfigFormat>) -> Self {
+        self.config_format = format;
+        self
+    }
+    
+    fn config_format(&self) -> ConfigFormat {
+        self.config_format.unwrap_or_else(|| config::detect_format(&self.config_path))
+    }
+    
+    /// Skip confirmation prompts, answering yes to all of them
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+    
+    /// Reject every command that would change expenses, categories or the config
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    
+    /// Fail with `AppError::ReadOnly` in read-only mode, before anything is written
+    fn ensure_writable(&self, action: &str) -> Result<(), AppError> {
+        if self.read_only {
+            return Err(AppError::ReadOnly(action.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Ask a yes/no question on stdin, defaulting to no
+    fn confirm(&self, prompt: &str) -> Result<bool, AppError> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        
+        print!("{} (y/N): ", prompt);
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        
+        Ok(input.trim().eq_ignore_ascii_case("y"))
+    }
+    
+    /// Guard against fat-finger entries above `large_expense_warning_threshold`. Interactive
+    /// sessions must confirm; without a terminal a warning is printed and the expense is kept.
+    fn confirm_large_amount(&self, amount: Decimal) -> Result<(), AppError> {
+        let Some(threshold) = self.config.large_expense_warning_threshold else {
+            return Ok(());
+        };
+        
+        if amount <= threshold {
+            return Ok(());
+        }
+        
+        if !self.assume_yes && !io::stdin().is_terminal() {
+            println!("Warning: {} is above the large expense threshold of {}",
+                self.config.format_money(amount), self.config.format_money(threshold));
+            return Ok(());
+        }
+        
+        let prompt = format!("{} is a large expense, confirm?", self.config.format_money(amount));
+        if !self.confirm(&prompt)? {
+            return Err(AppError::Other("Operation cancelled.".to_string()));
         }
+        
+        Ok(())
     }
     
     pub fn add_expense(&self, args: AddArgs) -> Result<(), AppError> {
+        let entered = args.round.map(|_| args.amount.clone());
+        let expense = self.try_add_expense(args)?;
+        
+        if expense.id().is_none() {
+            println!("Skipped: {} for {} on {} is already recorded",
+                self.config.format_money_in(expense.amount(), expense.currency().unwrap_or(&self.config.currency_symbol)),
+                expense.description(),
+                expense.date());
+            return Ok(());
+        }
+        
+        if let Some(entered) = entered {
+            println!("Rounded {} to {}", entered, self.config.format_money(expense.amount()));
+        }
+        
+        println!("Expense added: {} for {} on {}", 
+            self.config.format_money_in(expense.amount(), expense.currency().unwrap_or(&self.config.currency_symbol)), 
+            expense.description(),
+            expense.date());
+        
+        self.warn_over_budget(&expense)?;
+        
+        Ok(())
+    }
+    
+    /// Latest date an expense may be recorded on: today, or any date with `allow_future_dates`
+    fn latest_expense_date(&self) -> NaiveDate {
+        if self.config.allow_future_dates {
+            NaiveDate::MAX
+        } else {
+            self.clock.today()
+        }
+    }
+    
+    /// Warn when a new expense takes its category over the monthly budget
+    fn warn_over_budget(&self, expense: &Expense) -> Result<(), AppError> {
+        let month = expense.date().format("%Y-%m").to_string();
+        let (from_date, to_date) = parse_month_range(&month)?;
+        
+        for (category, spent, budget) in self.check_budgets(from_date, to_date)? {
+            if category == expense.category().name() && spent - expense.amount() <= budget {
+                println!("Warning: {} is over its monthly budget in {}: {} spent of {}",
+                    category, month, self.config.format_money(spent), self.config.format_money(budget));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Validate and save a new expense, returning it with its assigned id. With `if_not_exists`,
+    /// an expense identical to one already recorded is returned unsaved, without an id
+    pub fn try_add_expense(&self, mut args: AddArgs) -> Result<Expense, AppError> {
+        self.ensure_writable("adding expenses")?;
+        
+        if self.config.normalize_input {
+            args.category = args.category.trim().to_string();
+            args.secondary = args.secondary.map(|secondary| secondary.trim().to_string());
+        }
+        
         // Validate inputs
-        validate_amount(args.amount)?;
+        let mut amount = parse_amount_expr(&args.amount)?;
+        if let Some(step) = args.round {
+            amount = round_to_nearest(amount, step)?;
+        }
+        validate_amount(amount)?;
+        validate_precision(amount, self.config.decimal_places)?;
         validate_category(&args.category, &self.category_registry)?;
-        let date = parse_date(args.date)?;
+        let date = parse_date(args.date, self.clock.today())?;
         let description = default_description(args.description, &args.category);
         
         // Get the category from registry
@@ -59,86 +350,392 @@ impl<R: ExpenseRepository> App<R> {
         
         // Create expense
         let mut expense = Expense::new(
-            args.amount,
+            amount,
             category.clone(),
             date,
             description,
         );
         
+        // An explicit currency wins over the category's default; no currency means the base currency
+        let currency = args.currency.or_else(|| category.currency().map(String::from));
+        expense.set_currency(currency);
+        
+        if let Some(secondary) = args.secondary {
+            validate_category(&secondary, &self.category_registry)?;
+            let secondary = self.category_registry.get_category(&secondary)
+                .map_or(secondary.clone(), |c| c.name().to_string());
+            expense.set_secondary_category(Some(secondary));
+        }
+        
+        expense.set_account(args.account);
+        
+        self.normalize_expense(&mut expense);
+        
+        if args.if_not_exists && self.repository.exists(
+            expense.amount(), expense.category().name(), *expense.date(), expense.description(),
+        )? {
+            return Ok(expense);
+        }
+        
+        self.confirm_large_amount(expense.amount())?;
+        
         // Save to repository
         self.repository.save(&mut expense)?;
         
-        println!("Expense added: {} {} for {} on {}", 
-            self.config.currency_symbol, 
-            expense.amount(), 
+        Ok(expense)
+    }
+    
+    /// Change the given fields of an existing expense, leaving the others as they are
+    pub fn edit_expense(&self, args: EditArgs) -> Result<(), AppError> {
+        self.ensure_writable("editing expenses")?;
+        
+        let mut expense = self.repository.get_by_id_required(args.id)?;
+        let today = self.clock.today();
+        let amount_changed = args.amount.is_some();
+        
+        if let Some(amount) = &args.amount {
+            let amount = parse_amount_expr(amount)?;
+            validate_amount(amount)?;
+            validate_precision(amount, self.config.decimal_places)?;
+            expense.set_amount(amount)?;
+        }
+        
+        if let Some(category) = &args.category {
+            let category = if self.config.normalize_input { category.trim() } else { category.as_str() };
+            validate_category(category, &self.category_registry)?;
+            if let Some(category) = self.category_registry.get_category(category) {
+                expense.set_category(category.clone());
+            }
+        }
+        
+        if let Some(date) = args.date {
+            expense.set_date_as_of(parse_date(Some(date), today)?, self.latest_expense_date())?;
+        }
+        
+        if let Some(description) = args.description {
+            expense.set_description(description);
+        }
+        
+        self.normalize_expense(&mut expense);
+        if amount_changed {
+            self.confirm_large_amount(expense.amount())?;
+        }
+        self.repository.save(&mut expense)?;
+        
+        println!("Expense {} updated: {} for {} on {} ({})",
+            args.id,
+            self.config.format_money_in(expense.amount(), expense.currency().unwrap_or(&self.config.currency_symbol)),
             expense.description(),
-            expense.date());
+            expense.date(),
+            expense.category().name());
         
         Ok(())
     }
     
-    pub fn list_expenses(&self, args: ListArgs) -> Result<(), AppError> {
-        let expenses = if let Some(category) = args.category {
-            validate_category(&category, &self.category_registry)?;
-            self.repository.get_by_category(&category)?
-        } else if args.from.is_some() || args.to.is_some() {
-            let (from_date, to_date) = parse_date_range(args.from, args.to)?;
-            self.repository.get_by_date_range(from_date, to_date)?
+    /// Move an expense to the trash, after showing it and asking for confirmation unless `--yes` is given
+    pub fn delete_expense(&self, args: DeleteArgs) -> Result<(), AppError> {
+        self.ensure_writable("deleting expenses")?;
+        
+        let expense = self.repository.get_by_id_required(args.id)?;
+        
+        if !args.yes {
+            println!("#{} {} {} {} {}",
+                args.id,
+                expense.date(),
+                expense.category().name(),
+                self.config.format_money_in(expense.amount(), expense.currency().unwrap_or(&self.config.currency_symbol)),
+                expense.description());
+            
+            let prompt = if args.permanent { "Permanently delete this expense? It cannot be restored." } else { "Delete this expense?" };
+            if !self.confirm(prompt)? {
+                println!("Operation cancelled.");
+                return Ok(());
+            }
+        }
+        
+        if args.permanent {
+            self.repository.purge(args.id)?;
+            println!("Permanently deleted expense {}", args.id);
         } else {
-            self.repository.get_all()?
+            self.repository.delete_required(args.id)?;
+            println!("Deleted expense {}; `trash restore {}` brings it back", args.id, args.id);
+        }
+        
+        Ok(())
+    }
+    
+    /// With `normalize_input` set, tidy the whitespace in the description and switch
+    /// category names to their registered spelling so near-duplicates group together
+    fn normalize_expense(&self, expense: &mut Expense) {
+        if !self.config.normalize_input {
+            return;
+        }
+        
+        expense.set_description(normalize_whitespace(expense.description()));
+        
+        if let Some(category) = self.category_registry.get_category(expense.category().name().trim())
+            && category.name() != expense.category().name() {
+            expense.set_category(category.clone());
+        }
+        
+        if let Some(secondary) = expense.secondary_category().map(str::trim) {
+            let secondary = self.category_registry.get_category(secondary)
+                .map_or(secondary.to_string(), |c| c.name().to_string());
+            expense.set_secondary_category(Some(secondary));
+        }
+    }
+    
+    pub fn quick_add(&self, args: QuickArgs) -> Result<(), AppError> {
+        let (amount, category, description) = parse_quick_input(&args.input)?;
+        
+        self.add_expense(AddArgs {
+            amount: amount.to_string(),
+            category,
+            date: None,
+            description,
+            currency: None,
+            secondary: None,
+            round: None,
+            account: None,
+            if_not_exists: false,
+        })
+    }
+    
+    /// Print the expenses matching the list filters. With `--watch`, keeps printing
+    /// new expenses and only returns on an error
+    pub fn list_expenses(&self, mut args: ListArgs) -> Result<(), AppError> {
+        args.from = args.from.or(args.since.take());
+        args.to = args.to.or(args.until.take());
+        let watch_interval = args.watch.then_some(args.interval);
+        let format = args.format;
+        
+        let listing = self.find_listing(args)?;
+        
+        match format {
+            ListFormat::Table if listing.expenses.is_empty() => println!("No expenses found matching the criteria."),
+            ListFormat::Table => self.print_expense_table(&listing),
+            ListFormat::Json => println!("{}", serde_json::to_string_pretty(&listing.expenses)?),
+            ListFormat::Csv => export::csv::write_expenses(io::stdout(), &listing.expenses)?,
+        }
+        
+        if let Some(interval) = watch_interval {
+            self.watch_expenses(interval)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// The expenses matching the list filters with their total, or the requested page of them
+    pub fn find_listing(&self, args: ListArgs) -> Result<ExpenseListing, AppError> {
+        match args.page {
+            Some(page) => self.find_expense_page(args, page),
+            None => Ok(ExpenseListing::new(self.find_expenses(args)?)),
+        }
+    }
+    
+    /// Expenses matching the list filters, newest first
+    pub(crate) fn find_expenses(&self, args: ListArgs) -> Result<Vec<Expense>, AppError> {
+        let expenses = match (self.find_filtered(&args)?, args.limit) {
+            (Some(expenses), Some(limit)) => expenses.into_iter().take(limit).collect(),
+            (Some(expenses), None) => expenses,
+            // Without filters the limit is applied by the database
+            (None, Some(limit)) => self.repository.get_paged(limit, 0)?,
+            (None, None) => self.repository.get_all()?,
         };
         
-        // Apply limit if provided
-        let expenses = if let Some(limit) = args.limit {
-            expenses.into_iter().take(limit).collect()
-        } else {
+        Ok(expenses)
+    }
+    
+    /// One page of the expenses matching the list filters, newest first
+    fn find_expense_page(&self, args: ListArgs, page: usize) -> Result<ExpenseListing, AppError> {
+        let page_size = args.page_size;
+        let offset = (page - 1).saturating_mul(page_size);
+        
+        let (expenses, count) = match self.find_filtered(&args)? {
+            Some(expenses) => {
+                let count = expenses.len();
+                (expenses.into_iter().skip(offset).take(page_size).collect(), count)
+            },
+            None => (self.repository.get_paged(page_size, offset)?, self.repository.count()?),
+        };
+        
+        let pages = count.div_ceil(page_size).max(1);
+        Ok(ExpenseListing { page: Some((page, pages)), ..ExpenseListing::new(expenses) })
+    }
+    
+    /// Every expense matching the list filters, newest first, or `None` when no filter is given
+    fn find_filtered(&self, args: &ListArgs) -> Result<Option<Vec<Expense>>, AppError> {
+        let categories = args.category.as_deref().and_then(parse_category_filter);
+        
+        let expenses = if let Some(account) = &args.account {
+            self.repository.get_by_account(account)?
+        } else if let Some(pattern) = &args.description_glob {
+            self.repository.get_by_description_pattern(pattern)?
+        } else if let Some(categories) = categories {
+            // Resolve each name to its registered spelling, as stored with the expenses
+            let mut names = Vec::new();
+            for category in &categories {
+                validate_category(category, &self.category_registry)?;
+                if let Some(registered) = self.category_registry.get_category(category) {
+                    names.push(registered.name().to_string());
+                }
+            }
+            
+            // A month or date range narrows the categories down further
+            let range = if let Some(month) = &args.month {
+                Some(parse_month_range(month)?)
+            } else if args.from.is_some() || args.to.is_some() {
+                Some(parse_date_range(args.from.clone(), args.to.clone(), self.clock.today())?)
+            } else {
+                None
+            };
+            
+            let mut expenses = self.repository.get_by_categories(&names)?;
+            if let Some((from_date, to_date)) = range {
+                expenses.retain(|expense| (from_date..=to_date).contains(expense.date()));
+            }
             expenses
+        } else if let Some(month) = &args.month {
+            let (year, month) = parse_month(month)?;
+            self.repository.get_by_month(year, month)?
+        } else if args.from.is_some() || args.to.is_some() {
+            let (from_date, to_date) = parse_date_range(args.from.clone(), args.to.clone(), self.clock.today())?;
+            self.repository.get_by_date_range(from_date, to_date)?
+        } else {
+            return Ok(None);
         };
         
-        if expenses.is_empty() {
-            println!("No expenses found matching the criteria.");
+        Ok(Some(expenses))
+    }
+    
+    pub fn tail(&self, args: TailArgs) -> Result<(), AppError> {
+        let listing = ExpenseListing::new(self.repository.get_recent(args.count)?);
+        
+        if listing.expenses.is_empty() {
+            println!("No expenses recorded yet.");
             return Ok(());
         }
         
-        // Print header
-        println!("{:<5} {:<10} {:<15} {:<10} {:<30}", "ID", "Date", "Category", "Amount", "Description");
-        println!("{}", "-".repeat(75));
+        self.print_expense_table(&listing);
         
-        // Print each expense
-        let mut total = 0.0;
-        for expense in &expenses {
-            println!("{:<5} {:<10} {:<15} {:<10.2} {:<30}",
-                expense.id().unwrap_or(0),
-                expense.date(),
-                expense.category().name(),
-                expense.amount(),
-                expense.description()
-            );
-            total += expense.amount();
+        Ok(())
+    }
+    
+    /// Print expenses as a table followed by their total
+    fn print_expense_table(&self, listing: &ExpenseListing) {
+        let mut table = Table::new(&Expense::table_header());
+        
+        for expense in &listing.expenses {
+            table.add_row(expense.to_table_row(&self.config));
         }
         
+        println!("{}", table.render(self.config.table_style));
+        
         // Print footer with total
-        println!("{}", "-".repeat(75));
-        println!("Total: {} {:.2} ({} items)", self.config.currency_symbol, total, expenses.len());
+        println!("Total: {} ({} items)", self.config.format_money(listing.total), listing.count());
         
-        Ok(())
+        if let Some((page, pages)) = listing.page {
+            println!("Page {} of {}", page, pages);
+        }
+    }
+    
+    /// Poll for newly added expenses and print each one as it appears. New expenses are
+    /// shown regardless of the list filters. Runs until the process is interrupted.
+    fn watch_expenses(&self, interval: u64) -> Result<(), AppError> {
+        let mut last_id = self.repository.get_all()?
+            .iter()
+            .filter_map(Expense::id)
+            .max()
+            .unwrap_or(0);
+        
+        println!("Watching for new expenses every {}s (Ctrl-C to stop)...", interval);
+        
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(interval));
+            
+            for expense in self.repository.get_added_after(last_id)? {
+                let id = expense.id().unwrap_or(last_id);
+                println!("[{}] #{} {} {} {} {}",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    id,
+                    expense.date(),
+                    expense.category().name(),
+                    self.config.format_money_in(expense.amount(), expense.currency().unwrap_or(&self.config.currency_symbol)),
+                    expense.description());
+                last_id = last_id.max(id);
+            }
+        }
     }
     
-    pub fn generate_summary(&self, args: SummaryArgs) -> Result<(), AppError> {
-        let (from_date, to_date) = parse_date_range(args.from, args.to)?;
+    pub fn generate_summary(&self, mut args: SummaryArgs) -> Result<(), AppError> {
+        args.from = args.from.or(args.since.take());
+        args.to = args.to.or(args.until.take());
+        
+        let (from_date, to_date) = if let Some(month) = &args.month {
+            parse_month_range(month)?
+        } else if let Some(year) = &args.year {
+            parse_year_range(year, 1)?
+        } else if let Some(year) = &args.fiscal_year {
+            parse_year_range(year, self.config.fiscal_year_start_month)?
+        } else {
+            parse_date_range(args.from.clone(), args.to.clone(), self.clock.today())?
+        };
+        
+        if args.matrix {
+            return self.summary_matrix(from_date, to_date, args.format);
+        }
+        
+        match args.format {
+            SummaryFormat::Csv => return self.summary_csv(from_date, to_date),
+            SummaryFormat::Tsv => return self.summary_tsv(from_date, to_date),
+            SummaryFormat::Html => {
+                let report = self.summary_report(from_date, to_date, args.last)?;
+                print!("{}", export::html::render(&report));
+                return Ok(());
+            },
+            SummaryFormat::Text => {},
+        }
+        
+        if args.total_only {
+            return self.summary_total_only(from_date, to_date, args.by_category);
+        }
+        
+        let footer = !args.no_total;
         
         println!("Expense Summary ({} to {})", from_date, to_date);
+        println!("Total spent: {}", self.config.format_money(self.repository.get_total(from_date, to_date)?));
         println!("{}", "-".repeat(50));
         
-        if args.by_category {
-            self.summary_by_category(from_date, to_date)?;
+        if let Some(compare) = &args.compare {
+            let (compare_from, compare_to) = parse_compare_range(compare, from_date, to_date)?;
+            self.summary_compare((from_date, to_date), (compare_from, compare_to))?;
+        } else if args.by_category {
+            self.summary_by_category(from_date, to_date, &args, footer)?;
         } else if args.by_month {
-            self.summary_by_month(from_date, to_date)?;
+            self.summary_by_month(from_date, to_date, args.last, footer)?;
+        } else if args.by_description {
+            self.summary_by_description(from_date, to_date, args.normalize)?;
+        } else if args.by_secondary {
+            self.summary_by_secondary(from_date, to_date)?;
+        } else if args.by_account {
+            self.summary_by_account(from_date, to_date)?;
+        } else if args.by_day_of_month {
+            self.summary_by_day_of_month(from_date, to_date)?;
         } else {
             // Default summary shows both
-            self.summary_by_category(from_date, to_date)?;
+            self.summary_by_category(from_date, to_date, &args, footer)?;
+            println!();
+            self.summary_by_month(from_date, to_date, args.last, footer)?;
+        }
+        
+        if args.histogram {
+            println!();
+            self.summary_histogram(from_date, to_date)?;
+        }
+        
+        if args.gaps {
             println!();
-            self.summary_by_month(from_date, to_date)?;
+            self.summary_gaps(from_date, to_date, args.by_day)?;
         }
         
         // Show monthly averages
@@ -146,196 +743,2568 @@ impl<R: ExpenseRepository> App<R> {
         println!("Monthly Averages by Category:");
         println!("{}", "-".repeat(50));
         
-        let averages = self.repository.get_monthly_category_averages(from_date, to_date)?;
+        let averages = if args.weighted_averages {
+            self.repository.get_weighted_monthly_category_averages(from_date, to_date)?
+        } else {
+            self.repository.get_monthly_category_averages(from_date, to_date)?
+        };
         
         if averages.is_empty() {
             println!("No data available for the selected period.");
-            return Ok(());
+        } else {
+            let mut sorted_averages = averages;
+            self.sort_categories(&mut sorted_averages, from_date, to_date, args.sort_by, args.sort_order)?;
+            
+            for (category, avg) in sorted_averages {
+                match self.config.budget_for(&category) {
+                    Some(budget) => println!("{:<20} {}/month of {} budget ({:.0}%){}",
+                        category, self.config.format_money(avg), self.config.format_money(budget),
+                        percent_of(avg, budget), if avg > budget { "  OVER BUDGET" } else { "" }),
+                    None => println!("{:<20} {}/month", category, self.config.format_money(avg)),
+                }
+            }
         }
         
-        // Sort averages by amount (descending)
-        let mut sorted_averages = averages;
-        sorted_averages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        
-        for (category, avg) in sorted_averages {
-            println!("{:<20} {} {:.2}/month", category, self.config.currency_symbol, avg);
+        let alerts = self.evaluate_alerts(from_date, to_date)?;
+        if !alerts.is_empty() {
+            println!();
+            println!("Alerts:");
+            println!("{}", "-".repeat(50));
+            for alert in alerts {
+                println!("{}", alert);
+            }
         }
         
         Ok(())
     }
     
-    fn summary_by_category(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
-        println!("Expenses by Category:");
-        
-        let mut total = 0.0;
-        let mut category_totals = Vec::new();
+    /// Check each configured alert rule against every month in the range, clipped to the range.
+    /// Returns a message per exceeded limit, ordered by rule then month
+    pub fn evaluate_alerts(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<Vec<String>, AppError> {
+        let mut months = Vec::new();
+        let mut month_start = from_date.with_day(1).unwrap_or(from_date);
+        while month_start <= to_date {
+            let next_month = month_start.checked_add_months(chrono::Months::new(1))
+                .ok_or_else(|| AppError::Other(format!("Date out of range: {}", month_start)))?;
+            let start = month_start.max(from_date);
+            let end = next_month.pred_opt().unwrap_or(next_month).min(to_date);
+            months.push((month_start.format("%Y-%m").to_string(), start, end));
+            month_start = next_month;
+        }
         
-        // Get totals for each category in registry
-        for category in self.category_registry.all_categories() {
-            let amount = self.repository.get_category_total(category.name(), from_date, to_date)?;
+        let mut alerts = Vec::new();
+        for rule in &self.config.alerts {
+            let category = rule.category.as_deref()
+                .map(|name| self.category_registry.get_category(name).map_or(name, |c| c.name()));
             
-            if amount > 0.0 {
-                category_totals.push((category.name().to_string(), amount));
-                total += amount;
+            for (month, start, end) in &months {
+                let spent = match category {
+                    Some(category) => self.repository.get_category_total(category, *start, *end)?,
+                    None => self.repository.get_total(*start, *end)?,
+                };
+                
+                if spent > rule.monthly_limit {
+                    alerts.push(format!("{} spent {} in {}, over the {} monthly limit",
+                        category.unwrap_or("Total"), self.config.format_money(spent), month,
+                        self.config.format_money(rule.monthly_limit)));
+                }
             }
         }
         
-        // Sort by amount (descending)
-        category_totals.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        
-        // Print results
-        for (category, amount) in category_totals {
-            let percentage = if total > 0.0 { (amount / total) * 100.0 } else { 0.0 };
-            println!("{:<20} {} {:<10.2} ({:.1}%)", 
-                category, 
-                self.config.currency_symbol, 
-                amount, 
-                percentage
-            );
-        }
-        
-        println!("{}", "-".repeat(50));
-        println!("Total: {} {:.2}", self.config.currency_symbol, total);
-        
-        Ok(())
+        Ok(alerts)
     }
     
-    fn summary_by_month(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
-        println!("Expenses by Month:");
-        
-        // Get all expenses in date range
-        let expenses = self.repository.get_by_date_range(from_date, to_date)?;
+    /// Print exceeded alert limits for a month, failing if there are any so scripts can react
+    pub fn check_alerts(&self, args: AlertsArgs) -> Result<(), AppError> {
+        let month = args.month.unwrap_or_else(|| self.clock.today().format("%Y-%m").to_string());
+        let (from_date, to_date) = parse_month_range(&month)?;
         
-        if expenses.is_empty() {
-            println!("No data available for the selected period.");
+        let alerts = self.evaluate_alerts(from_date, to_date)?;
+        if alerts.is_empty() {
+            println!("No alerts triggered.");
             return Ok(());
         }
         
-        // Group by month
-        let mut monthly_totals: std::collections::HashMap<(i32, u32), f64> = std::collections::HashMap::new();
-        
-        for expense in expenses {
-            let key = (expense.date().year(), expense.date().month());
-            *monthly_totals.entry(key).or_insert(0.0) += expense.amount();
+        for alert in &alerts {
+            println!("{}", alert);
         }
         
-        // Convert to vector and sort by date
-        let mut sorted_totals: Vec<_> = monthly_totals.into_iter().collect();
-        sorted_totals.sort_by_key(|&((year, month), _)| (year, month));
+        Err(AppError::Other(format!("{} alert(s) triggered", alerts.len())))
+    }
+    
+    /// Compare spending between two dates (inclusive) in every budgeted category with its
+    /// monthly budget, scaled by the number of months the range touches. Returns the category,
+    /// amount spent and budget of each category over budget, in name order
+    pub fn check_budgets(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<Vec<(String, Decimal, Decimal)>, AppError> {
+        let months = Decimal::from(months_between(from_date, to_date).len());
         
-        // Print results
-        let mut total = 0.0;
-        for ((year, month), amount) in sorted_totals {
-            let month_name = match month {
-                1 => "January",
-                2 => "February",
-                3 => "March",
-                4 => "April",
-                5 => "May",
-                6 => "June",
-                7 => "July",
-                8 => "August",
-                9 => "September",
-                10 => "October",
-                11 => "November",
-                12 => "December",
-                _ => "Unknown",
-            };
+        let mut overspent = Vec::new();
+        for (name, budget) in &self.config.budgets {
+            let category = self.category_registry.get_category(name).map_or(name.as_str(), |c| c.name());
+            let spent = self.repository.get_category_total(category, from_date, to_date)?;
+            let budget = *budget * months;
             
-            println!("{} {:<10} {} {:.2}", year, month_name, self.config.currency_symbol, amount);
-            total += amount;
+            if spent > budget {
+                overspent.push((category.to_string(), spent, budget));
+            }
         }
         
-        println!("{}", "-".repeat(50));
-        println!("Total: {} {:.2}", self.config.currency_symbol, total);
-        
-        Ok(())
+        Ok(overspent)
     }
     
-    pub fn manage_categories(&mut self, args: CategoryArgs) -> Result<(), AppError> {
-        match args.command {
-            CategoryCommands::List => {
-                println!("Available Categories:");
-                println!("{}", "-".repeat(50));
-                
-                let categories = self.category_registry.all_categories();
-                
-                if categories.is_empty() {
-                    println!("No categories defined.");
-                    return Ok(());
+    /// Spending from the start of the month to today in every budgeted category, in name order.
+    /// With `prorate`, each budget is also scaled to the share of the month elapsed
+    pub fn budget_status(&self, prorate: bool) -> Result<Vec<BudgetStatus>, AppError> {
+        let today = self.clock.today();
+        let (month_start, month_end) = parse_month_range(&today.format("%Y-%m").to_string())?;
+        let elapsed = Decimal::from(today.day()) / Decimal::from(month_end.day());
+        
+        let mut statuses = Vec::new();
+        for (name, budget) in &self.config.budgets {
+            let category = self.category_registry.get_category(name).map_or(name.as_str(), |c| c.name());
+            statuses.push(BudgetStatus {
+                category: category.to_string(),
+                spent: self.repository.get_category_total(category, month_start, today)?,
+                budget: *budget,
+                prorated: prorate.then(|| (*budget * elapsed).round_dp(2)),
+            });
+        }
+        
+        Ok(statuses)
+    }
+    
+    pub fn print_total(&self, args: TotalArgs) -> Result<(), AppError> {
+        let (from_date, to_date) = parse_date_range(args.from, args.to, self.clock.today())?;
+        
+        // Add up the same expenses `list` would show for the range
+        let total = self.find_listing(ListArgs {
+            category: args.category,
+            from: Some(from_date.to_string()),
+            to: Some(to_date.to_string()),
+            since: None,
+            until: None,
+            month: None,
+            description_glob: None,
+            account: None,
+            limit: None,
+            page: None,
+            page_size: 50,
+            watch: false,
+            interval: 2,
+            format: ListFormat::Table,
+        })?.total;
+        
+        if args.pretty {
+            println!("{}", self.config.format_money(total));
+        } else {
+            println!("{:.2}", total.round_dp(2));
+        }
+        
+        Ok(())
+    }
+    
+    // Get category totals sorted by amount (descending) along with the grand total
+    fn category_breakdown(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(Vec<(String, Decimal)>, Decimal), AppError> {
+        let mut category_totals: Vec<_> = self.repository.get_category_totals(from_date, to_date)?
+            .into_iter()
+            .filter(|(_, amount)| *amount > Decimal::ZERO)
+            .collect();
+        
+        category_totals.sort_by(|a, b| compare_amounts_desc(a.1, b.1));
+        
+        let total = category_totals.iter().map(|(_, amount)| amount).sum();
+        
+        Ok((category_totals, total))
+    }
+    
+    /// Category totals for two periods side by side, with the change from `previous` to `current`
+    fn summary_compare(&self, current: (NaiveDate, NaiveDate), previous: (NaiveDate, NaiveDate)) -> Result<(), AppError> {
+        println!("Compared with {} to {}:", previous.0, previous.1);
+        
+        let current_totals = self.repository.get_category_totals(current.0, current.1)?;
+        let previous_totals = self.repository.get_category_totals(previous.0, previous.1)?;
+        
+        // Categories from either period, with 0 for the period they're absent from
+        let mut rows: Vec<(String, Decimal, Decimal)> = current_totals.iter()
+            .map(|(category, amount)| (category.clone(), *amount, Decimal::ZERO))
+            .collect();
+        for (category, amount) in previous_totals {
+            match rows.iter_mut().find(|(name, _, _)| *name == category) {
+                Some(row) => row.2 = amount,
+                None => rows.push((category, Decimal::ZERO, amount)),
+            }
+        }
+        
+        if rows.is_empty() {
+            println!("No data available for either period.");
+            return Ok(());
+        }
+        
+        rows.sort_by(|a, b| compare_amounts_desc(a.1, b.1).then(compare_amounts_desc(a.2, b.2)));
+        
+        let amount_width = self.config.currency_symbol.chars().count() + 11;
+        let mut table = Table::new(&[("Category", 20), ("Current", amount_width), ("Previous", amount_width), ("Change", amount_width + 1), ("%", 0)])
+            .with_rule_width(75);
+        
+        let change_row = |category: String, current: Decimal, previous: Decimal| {
+            let change = current - previous;
+            let percent = if previous > Decimal::ZERO {
+                format!("{:+.1}%", percent_of(change, previous))
+            } else {
+                "new".to_string()
+            };
+            vec![
+                category,
+                self.config.format_money(current),
+                self.config.format_money(previous),
+                format!("{}{}", if change < Decimal::ZERO { "-" } else { "+" }, self.config.format_money(change.abs())),
+                percent,
+            ]
+        };
+        
+        let (mut current_total, mut previous_total) = (Decimal::ZERO, Decimal::ZERO);
+        for (category, current, previous) in rows {
+            current_total += current;
+            previous_total += previous;
+            table.add_row(change_row(category, current, previous));
+        }
+        table.add_row(change_row("Total".to_string(), current_total, previous_total));
+        
+        println!("{}", table.render(self.config.table_style));
+        
+        Ok(())
+    }
+    
+    // Print just the bottom line, optionally with one line per category above it
+    fn summary_total_only(&self, from_date: NaiveDate, to_date: NaiveDate, by_category: bool) -> Result<(), AppError> {
+        let (category_totals, total) = self.category_breakdown(from_date, to_date)?;
+        
+        if by_category {
+            for (category, amount) in category_totals {
+                println!("{:<20} {}", category, self.config.format_money(amount));
+            }
+        }
+        
+        println!("Total: {}", self.config.format_money(total));
+        
+        Ok(())
+    }
+    
+    /// Sort `(category, amount)` rows for display, looking up the number of expenses per
+    /// category in the range when sorting by count
+    fn sort_categories(&self, rows: &mut [(String, Decimal)], from_date: NaiveDate, to_date: NaiveDate, sort_by: CategorySort, order: Option<SortOrder>) -> Result<(), AppError> {
+        let counts = if sort_by == CategorySort::Count {
+            self.repository.get_category_transaction_stats(from_date, to_date)?
+                .into_iter()
+                .map(|(category, _, count)| (category, count))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        sort_category_rows(rows, &counts, sort_by, order);
+        
+        Ok(())
+    }
+    
+    fn summary_by_category(&self, from_date: NaiveDate, to_date: NaiveDate, args: &SummaryArgs, footer: bool) -> Result<(), AppError> {
+        let (percent_of_income, detailed, stats) = (args.percent_of_income, args.detailed, args.stats);
+        println!("Expenses by Category:");
+        
+        let (mut category_totals, mut total) = self.category_breakdown(from_date, to_date)?;
+        self.sort_categories(&mut category_totals, from_date, to_date, args.sort_by, args.sort_order)?;
+        
+        // Percentages are relative to total spending, or to total income when requested
+        let income = if percent_of_income {
+            let Some(income_category) = self.config.income_category.as_deref() else {
+                println!("No income category configured. Set 'income_category' in the config to use --percent-of-income.");
+                return Ok(());
+            };
+            let income_category = self.category_registry.get_category(income_category)
+                .map_or(income_category, |c| c.name());
+            
+            let income = self.repository.get_category_total(income_category, from_date, to_date)?;
+            if income <= Decimal::ZERO {
+                println!("No income recorded in '{}' for the selected period.", income_category);
+                return Ok(());
+            }
+            
+            // Income is the base, not part of the spending
+            category_totals.retain(|(category, _)| category != income_category);
+            total = category_totals.iter().map(|(_, amount)| amount).sum();
+            
+            Some(income)
+        } else {
+            None
+        };
+        
+        let base = income.unwrap_or(total);
+        
+        let amount_width = self.config.currency_symbol.chars().count() + 11;
+        let mut columns = vec![("Category", 20), ("Amount", amount_width), ("Share", 0)];
+        
+        // Number of expenses per category, for the detailed view
+        let counts: Vec<(String, usize)> = if detailed {
+            columns.extend([("Count", 6), ("Average", amount_width)]);
+            self.repository.get_category_transaction_stats(from_date, to_date)?
+                .into_iter()
+                .map(|(category, _, count)| (category, count))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        let amount_stats = if stats {
+            columns.extend([("Min", amount_width), ("Median", amount_width), ("Max", amount_width)]);
+            self.repository.get_category_amount_stats(from_date, to_date)?
+        } else {
+            Vec::new()
+        };
+        
+        // The share column is padded only when more columns follow it, and the last never is
+        if columns.len() > 3 {
+            columns[2].1 = 9;
+        }
+        if let Some(last) = columns.last_mut() {
+            last.1 = 0;
+        }
+        
+        let mut table = Table::new(&columns)
+            .with_rule_width(50)
+            .without_plain_header();
+        let mut transactions = 0;
+        
+        // Shares of total spending are rounded to add up to 100%; shares of income needn't
+        let percentages = if income.is_some() {
+            category_totals.iter().map(|(_, amount)| if base > Decimal::ZERO { percent_of(*amount, base) } else { 0.0 }).collect()
+        } else {
+            distribute_percentages(&category_totals.iter().map(|(_, amount)| *amount).collect::<Vec<_>>())
+        };
+        
+        for ((category, amount), percentage) in category_totals.into_iter().zip(percentages) {
+            let mut row = vec![
+                self.config.format_money(amount),
+                format!("({:.1}%)", percentage),
+            ];
+            
+            if detailed {
+                let count = counts.iter().find(|(name, _)| *name == category).map_or(0, |(_, count)| *count);
+                transactions += count;
+                row.push(count.to_string());
+                row.push(if count > 0 { self.config.format_money(amount / Decimal::from(count)) } else { String::new() });
+            }
+            
+            if stats {
+                match amount_stats.iter().find(|(name, _)| *name == category) {
+                    Some((_, spread)) => row.extend([spread.min, spread.median, spread.max].map(|amount| self.config.format_money(amount))),
+                    None => row.extend(["-", "-", "-"].map(String::from)),
                 }
-                
-                for category in categories {
-                    if let Some(desc) = category.description() {
-                        println!("{:<20} - {}", category.name(), desc);
-                    } else {
-                        println!("{}", category.name());
-                    }
+            }
+            
+            row.insert(0, category);
+            table.add_row(row);
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        if footer && detailed {
+            println!("Total: {} over {} expenses", self.config.format_money(total), transactions);
+        } else if footer {
+            println!("Total: {}", self.config.format_money(total));
+        }
+        
+        if let Some(income) = income {
+            println!("Income: {} ({:.1}% spent)", self.config.format_money(income), percent_of(total, income));
+        }
+        
+        Ok(())
+    }
+    
+    // Write the category breakdown as CSV to stdout
+    fn summary_csv(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        let mut writer = csv::Writer::from_writer(io::stdout());
+        writer.write_record(SUMMARY_COLUMNS)?;
+        
+        for record in self.summary_records(from_date, to_date)? {
+            writer.write_record(record)?;
+        }
+        
+        writer.flush()?;
+        
+        Ok(())
+    }
+    
+    // Write the category breakdown as tab-separated values to stdout
+    fn summary_tsv(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        println!("{}", SUMMARY_COLUMNS.join("\t"));
+        
+        for record in self.summary_records(from_date, to_date)? {
+            println!("{}", record.iter().map(|field| escape_tsv_field(field)).collect::<Vec<_>>().join("\t"));
+        }
+        
+        Ok(())
+    }
+    
+    /// Print spending per category (rows) and month (columns) with row and column totals
+    fn summary_matrix(&self, from_date: NaiveDate, to_date: NaiveDate, format: SummaryFormat) -> Result<(), AppError> {
+        let months = months_between(from_date, to_date);
+        let rows = category_month_matrix(&self.repository.get_monthly_category_totals(from_date, to_date)?, &months);
+        
+        // Column totals, with the grand total last
+        let mut column_totals = vec![Decimal::ZERO; months.len() + 1];
+        for (_, amounts) in &rows {
+            for (total, cell) in column_totals.iter_mut().zip(amounts.iter().chain([&amounts.iter().sum()])) {
+                *total += cell;
+            }
+        }
+        
+        let header: Vec<String> = std::iter::once("category".to_string())
+            .chain(months.iter().map(|(year, month)| format!("{}-{:02}", year, month)))
+            .chain(std::iter::once("total".to_string()))
+            .collect();
+        let amount = |amount: &Decimal| format!("{:.2}", amount.round_dp(2));
+        let records: Vec<Vec<String>> = rows.iter()
+            .map(|(category, amounts)| (category.as_str(), amounts.iter().chain([&amounts.iter().sum()]).map(amount).collect::<Vec<_>>()))
+            .chain(std::iter::once(("Total", column_totals.iter().map(amount).collect())))
+            .map(|(label, cells)| std::iter::once(label.to_string()).chain(cells).collect())
+            .collect();
+        
+        match format {
+            SummaryFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(io::stdout());
+                writer.write_record(&header)?;
+                for record in &records {
+                    writer.write_record(record)?;
                 }
+                writer.flush()?;
             },
-            CategoryCommands::Add { name, description } => {
-                // Add the category
-                match self.category_registry.add_category(&name, description.as_deref()) {
-                    Ok(category) => {
-                        println!("Added category: {}", category.name());
-                        
-                        // Update the config and save it
-                        self.update_config_categories()?;
-                    },
-                    Err(e) => {
-                        return Err(AppError::Other(format!("Failed to add category: {}", e)));
-                    }
+            SummaryFormat::Tsv => {
+                for record in std::iter::once(&header).chain(&records) {
+                    println!("{}", record.iter().map(|field| escape_tsv_field(field)).collect::<Vec<_>>().join("\t"));
                 }
             },
-            CategoryCommands::Remove { name } => {
-                // First check if there are any expenses with this category
-                if let Ok(expenses) = self.repository.get_by_category(&name) {
-                    if !expenses.is_empty() {
-                        // Ask for confirmation
-                        print!("There are {} expenses with category '{}'. Are you sure you want to remove it? (y/N): ", 
-                            expenses.len(), name);
-                        io::stdout().flush()?;
-                        
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input)?;
-                        
-                        if !input.trim().eq_ignore_ascii_case("y") {
-                            println!("Operation cancelled.");
-                            return Ok(());
-                        }
-                    }
+            SummaryFormat::Text => {
+                let mut columns: Vec<(&str, usize)> = header.iter().map(|name| (name.as_str(), 11)).collect();
+                columns[0] = ("Category", 20);
+                if let Some(last) = columns.last_mut() {
+                    *last = ("Total", 0);
                 }
                 
-                // Remove the category
-                match self.category_registry.remove_category(&name) {
-                    Ok(_) => {
-                        println!("Removed category: {}", name);
-                        
-                        // Update the config and save it
-                        self.update_config_categories()?;
-                    },
-                    Err(e) => {
-                        return Err(AppError::Other(format!("Failed to remove category: {}", e)));
-                    }
+                let mut table = Table::new(&columns);
+                for record in records {
+                    table.add_row(record);
                 }
-            }
+                println!("{}", table.render(self.config.table_style));
+            },
+            SummaryFormat::Html => {
+                return Err(AppError::Other("--matrix can be printed as text, CSV or TSV, not HTML".to_string()));
+            },
         }
         
         Ok(())
     }
     
-    // Update config with the current categories and save it
-    fn update_config_categories(&mut self) -> Result<(), AppError> {
-        // Update config with current categories
-        self.config.categories = self.category_registry.all_categories()
-            .into_iter()
-            .cloned()
-            .collect();
+    /// Category breakdown rows for the delimited summary formats, in `SUMMARY_COLUMNS` order
+    fn summary_records(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<Vec<[String; 3]>, AppError> {
+        let (category_totals, _) = self.category_breakdown(from_date, to_date)?;
         
-        // Save config
-        let config_path = Path::new("expense_log.yaml");
-        self.config.save(&config_path)?;
+        let percentages = distribute_percentages(&category_totals.iter().map(|(_, amount)| *amount).collect::<Vec<_>>());
+        
+        Ok(category_totals.into_iter().zip(percentages)
+            .map(|((category, amount), percentage)| [
+                category,
+                format!("{:.2}", amount.round_dp(2)),
+                format!("{:.1}", percentage),
+            ])
+            .collect())
+    }
+    
+    fn summary_by_month(&self, from_date: NaiveDate, to_date: NaiveDate, last: Option<usize>, footer: bool) -> Result<(), AppError> {
+        println!("Expenses by Month:");
+        
+        let mut sorted_totals = self.monthly_totals(from_date, to_date)?;
+        
+        if sorted_totals.is_empty() {
+            println!("No data available for the selected period.");
+            return Ok(());
+        }
+        
+        // Keep only the most recent months if requested
+        if let Some(last) = last {
+            let skip = sorted_totals.len().saturating_sub(last);
+            sorted_totals.drain(..skip);
+        }
+        
+        // Print results
+        let total: Decimal = sorted_totals.iter().map(|(_, amount)| amount).sum();
+        for ((year, month), amount) in sorted_totals {
+            println!("{} {:<10} {}", year, self.config.month_name(month), self.config.format_money(amount));
+        }
+        
+        if footer {
+            println!("{}", "-".repeat(50));
+            println!("Total: {}", self.config.format_money(total));
+        }
+        
+        Ok(())
+    }
+    
+    /// Spending per `(year, month)` in the date range, oldest first
+    fn monthly_totals(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<MonthlyTotals, AppError> {
+        Ok(self.repository.get_monthly_totals(from_date, to_date)?)
+    }
+    
+    /// Gather the summary for a date range into a `SummaryReport`, keeping only the
+    /// `last` months of monthly totals if given
+    pub(crate) fn summary_report(&self, from_date: NaiveDate, to_date: NaiveDate, last: Option<usize>) -> Result<SummaryReport, AppError> {
+        let (category_totals, total) = self.category_breakdown(from_date, to_date)?;
+        
+        let mut monthly_totals = self.monthly_totals(from_date, to_date)?;
+        if let Some(last) = last {
+            let skip = monthly_totals.len().saturating_sub(last);
+            monthly_totals.drain(..skip);
+        }
+        
+        let mut monthly_averages = self.repository.get_monthly_category_averages(from_date, to_date)?;
+        monthly_averages.sort_by(|a, b| compare_amounts_desc(a.1, b.1));
+        
+        Ok(SummaryReport {
+            from: from_date,
+            to: to_date,
+            currency_symbol: self.config.currency_symbol.clone(),
+            category_totals,
+            total,
+            monthly_totals,
+            monthly_averages,
+        })
+    }
+    
+    fn summary_by_secondary(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        println!("Expenses by Secondary Category:");
+        
+        let mut totals = self.repository.get_secondary_category_totals(from_date, to_date)?;
+        
+        if totals.is_empty() {
+            println!("No expenses with a secondary category in the selected period.");
+            return Ok(());
+        }
+        
+        totals.sort_by(|a, b| compare_amounts_desc(a.1, b.1));
+        
+        let mut table = Table::new(&[("Category", 20), ("Amount", 0)]).with_rule_width(50).without_plain_header();
+        for (category, total) in totals {
+            table.add_row(vec![category, self.config.format_money(total)]);
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        println!("These amounts are also counted under their main category.");
+        
+        Ok(())
+    }
+    
+    fn summary_by_account(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        println!("Expenses by Account:");
+        
+        let mut totals = self.repository.get_account_totals(from_date, to_date)?;
+        
+        if totals.is_empty() {
+            println!("No expenses with an account in the selected period.");
+            return Ok(());
+        }
+        
+        totals.sort_by(|a, b| compare_amounts_desc(a.1, b.1));
+        
+        // Expenses recorded without an account make up the rest of the total
+        let unassigned = self.repository.get_total(from_date, to_date)? - totals.iter().map(|(_, total)| total).sum::<Decimal>();
+        
+        let mut table = Table::new(&[("Account", 20), ("Amount", 0)]).with_rule_width(50).without_plain_header();
+        for (account, total) in totals {
+            table.add_row(vec![account, self.config.format_money(total)]);
+        }
+        if unassigned > Decimal::ZERO {
+            table.add_row(vec!["(no account)".to_string(), self.config.format_money(unassigned)]);
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        
+        Ok(())
+    }
+    
+    fn summary_by_description(&self, from_date: NaiveDate, to_date: NaiveDate, normalize: bool) -> Result<(), AppError> {
+        const TOP_DESCRIPTIONS: usize = 10;
+        
+        println!("Top Descriptions:");
+        
+        let totals = self.repository.get_description_totals(from_date, to_date, normalize)?;
+        
+        if totals.is_empty() {
+            println!("No data available for the selected period.");
+            return Ok(());
+        }
+        
+        let mut table = Table::new(&[("Description", 30), ("Count", 6), ("Total", 0)]).with_rule_width(50);
+        for (description, total, count) in totals.into_iter().take(TOP_DESCRIPTIONS) {
+            table.add_row(vec![
+                description,
+                count.to_string(),
+                self.config.format_money(total),
+            ]);
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        
+        Ok(())
+    }
+    
+    fn summary_by_day_of_month(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        const BAR_WIDTH: f64 = 40.0;
+        
+        println!("Expenses by Day of Month:");
+        println!("{}", "-".repeat(50));
+        
+        let totals = self.repository.get_day_of_month_totals(from_date, to_date)?;
+        
+        if totals.is_empty() {
+            println!("No expenses found for the selected period.");
+            return Ok(());
+        }
+        
+        let max = totals.iter().map(|(_, amount)| *amount).max().unwrap_or(Decimal::ZERO);
+        let amounts: Vec<String> = totals.iter().map(|(_, amount)| self.config.format_money(*amount)).collect();
+        let amount_width = amounts.iter().map(|amount| amount.chars().count()).max().unwrap_or(0);
+        
+        // Every day is listed so spikes stand out against the quiet days around them
+        for day in 1..=31 {
+            match totals.iter().position(|(d, _)| *d == day) {
+                Some(index) => {
+                    let bar = if max > Decimal::ZERO { ((totals[index].1 / max).to_f64().unwrap_or(0.0) * BAR_WIDTH).round() as usize } else { 0 };
+                    println!("{:>2} {:>width$} {}", day, amounts[index], "#".repeat(bar), width = amount_width);
+                },
+                None => println!("{:>2} {:>width$}", day, "-", width = amount_width),
+            }
+        }
         
         Ok(())
     }
+    
+    fn summary_histogram(&self, from_date: NaiveDate, to_date: NaiveDate) -> Result<(), AppError> {
+        const DAYS_PER_LINE: usize = 60;
+        
+        println!("Daily Spending:");
+        
+        let histogram = self.repository.get_date_histogram(from_date, to_date)?;
+        let max = histogram.iter().map(|(_, amount)| *amount).max().unwrap_or(Decimal::ZERO);
+        
+        for chunk in histogram.chunks(DAYS_PER_LINE) {
+            let amounts: Vec<Decimal> = chunk.iter().map(|(_, amount)| *amount).collect();
+            println!("{} {}", chunk[0].0, sparkline(&amounts, max, &BLOCK_BARS));
+        }
+        
+        println!("Peak day: {} (blank = no spending)", self.config.format_money(max));
+        
+        Ok(())
+    }
+    
+    /// List the periods in the range with no recorded spending, by month or by day
+    fn summary_gaps(&self, from_date: NaiveDate, to_date: NaiveDate, by_day: bool) -> Result<(), AppError> {
+        let histogram = self.repository.get_date_histogram(from_date, to_date)?;
+        
+        let gaps: Vec<String> = if by_day {
+            println!("Days Without Expenses:");
+            
+            zero_runs(&histogram).into_iter()
+                .map(|(first, last)| match (last - first).num_days() + 1 {
+                    1 => first.to_string(),
+                    days => format!("{} to {} ({} days)", first, last, days),
+                })
+                .collect()
+        } else {
+            println!("Months Without Expenses:");
+            
+            // Fold the daily totals into a dense list of calendar months
+            let mut months: Vec<(NaiveDate, Decimal)> = Vec::new();
+            for (date, amount) in histogram {
+                let month = date.with_day(1).unwrap_or(date);
+                match months.last_mut() {
+                    Some((last, total)) if *last == month => *total += amount,
+                    _ => months.push((month, amount)),
+                }
+            }
+            
+            zero_runs(&months).into_iter()
+                .map(|(first, last)| if first == last {
+                    first.format("%Y-%m").to_string()
+                } else {
+                    format!("{} to {}", first.format("%Y-%m"), last.format("%Y-%m"))
+                })
+                .collect()
+        };
+        
+        if gaps.is_empty() {
+            println!("No gaps: every period in the range has expenses.");
+        }
+        for gap in gaps {
+            println!("  {}", gap);
+        }
+        
+        Ok(())
+    }
+    
+    /// Project end-of-month totals per category from the spending so far.
+    ///
+    /// This is a linear run rate: the amount spent up to and including `today` is scaled
+    /// by days in month / days elapsed. It ignores recurring and seasonal expenses, so a
+    /// rent payment on the 1st projects as if rent were paid every day.
+    /// Returns `(category, spent, projected)`, largest projection first, leaving out the income category.
+    pub fn forecast_month(&self, today: NaiveDate) -> Result<Vec<(String, Decimal, Decimal)>, AppError> {
+        let (first, last) = parse_month_range(&today.format("%Y-%m").to_string())?;
+        let run_rate = Decimal::from(last.day()) / Decimal::from(today.day());
+        let income = self.config.income_category.as_deref();
+        
+        let mut forecast: Vec<(String, Decimal, Decimal)> = self.repository.get_category_totals(first, today)?
+            .into_iter()
+            .filter(|(category, spent)| *spent > Decimal::ZERO && income.is_none_or(|i| !i.eq_ignore_ascii_case(category)))
+            .map(|(category, spent)| (category, spent, spent * run_rate))
+            .collect();
+        forecast.sort_by(|a, b| compare_amounts_desc(a.2, b.2));
+        
+        Ok(forecast)
+    }
+    
+    pub fn forecast(&self) -> Result<(), AppError> {
+        let today = self.clock.today();
+        let forecast = self.forecast_month(today)?;
+        
+        println!("Forecast for {} (day {}, linear run rate):", today.format("%B %Y"), today.day());
+        
+        if forecast.is_empty() {
+            println!("No expenses recorded this month.");
+            return Ok(());
+        }
+        
+        let mut table = Table::new(&[("Category", 20), ("Spent", 12), ("Projected", 0)]).with_rule_width(50);
+        let (mut spent_total, mut projected_total) = (Decimal::ZERO, Decimal::ZERO);
+        for (category, spent, projected) in &forecast {
+            table.add_row(vec![
+                category.clone(),
+                self.config.format_money(*spent),
+                self.config.format_money(*projected),
+            ]);
+            spent_total += spent;
+            projected_total += projected;
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        println!("Total: {} spent, {} projected",
+            self.config.format_money(spent_total),
+            self.config.format_money(projected_total));
+        
+        // Warn about categories heading above their usual monthly spend over the last year
+        let (first, _) = parse_month_range(&today.format("%Y-%m").to_string())?;
+        let history_start = first - chrono::Months::new(12);
+        let averages = self.repository.get_monthly_category_averages(history_start, first.pred_opt().unwrap_or(first))?;
+        for (category, _, projected) in &forecast {
+            if let Some((_, average)) = averages.iter().find(|(name, _)| name == category)
+                && projected > average {
+                println!("Warning: {} is projected at {}, above its monthly average of {}",
+                    category,
+                    self.config.format_money(*projected),
+                    self.config.format_money(*average));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    pub fn show_stats(&self, args: StatsArgs) -> Result<(), AppError> {
+        let stats = LifetimeStats::from_expenses(&self.repository.get_all()?);
+        
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(());
+        }
+        
+        let Some(stats) = stats else {
+            println!("No expenses recorded yet.");
+            return Ok(());
+        };
+        
+        println!("Lifetime Statistics:");
+        println!("{}", "-".repeat(50));
+        println!("{:<20} {}", "Expenses:", stats.total_count);
+        println!("{:<20} {}", "Total spent:", self.config.format_money(stats.total_amount));
+        println!("{:<20} {} to {} ({} days)", "Date range:", stats.first_date, stats.last_date, stats.days);
+        println!("{:<20} {}", "Average per day:", self.config.format_money(stats.average_per_day));
+        println!("{:<20} {} ({} expenses)", "Busiest category:", stats.busiest_category, stats.busiest_category_count);
+        println!("{:<20} {} for {} on {}", "Largest expense:",
+            self.config.format_money(stats.largest_expense.amount()),
+            stats.largest_expense.description(),
+            stats.largest_expense.date());
+        
+        // Trend of spending per month over the whole history, on one line
+        let monthly_totals = self.repository.get_monthly_totals(stats.first_date, stats.last_date)?;
+        let amounts = dense_monthly_amounts(&monthly_totals);
+        let max = amounts.iter().copied().max().unwrap_or(Decimal::ZERO);
+        let bars: &[char] = if args.ascii || !locale_is_utf8(|name| std::env::var(name).ok()) { &ASCII_BARS } else { &BLOCK_BARS };
+        println!("{:<20} {} (peak {})", "Monthly spending:", sparkline(&amounts, max, bars), self.config.format_money(max));
+        
+        Ok(())
+    }
+    
+    pub fn show_history(&self, args: HistoryArgs) -> Result<(), AppError> {
+        if !self.config.audit_log {
+            println!("Change history is not being recorded. Set 'audit_log: true' in the config to enable it.");
+            return Ok(());
+        }
+        
+        let history = self.repository.get_history(args.id)?;
+        
+        if history.is_empty() {
+            println!("No recorded changes for expense {}.", args.id);
+            return Ok(());
+        }
+        
+        println!("History of expense {}:", args.id);
+        
+        let mut table = Table::new(&[("Changed At", 20), ("Action", 8), ("Field", 12), ("Old Value", 20), ("New Value", 20)]);
+        for entry in history {
+            table.add_row(vec![
+                entry.changed_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                entry.operation,
+                entry.field,
+                entry.old_value.unwrap_or_else(|| "-".to_string()),
+                entry.new_value.unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+        
+        println!("{}", table.render(self.config.table_style));
+        
+        Ok(())
+    }
+    
+    pub fn backup(&self, args: BackupArgs) -> Result<(), AppError> {
+        let expenses = self.repository.get_all()?;
+        
+        match args.format {
+            BackupFormat::Zip => backup::write_archive(&args.output, &expenses, &self.config)?,
+            format => backup::write_backup(&args.output, &expenses, format)?,
+        }
+        
+        println!("Backed up {} expenses to {}", expenses.len(), args.output.display());
+        
+        Ok(())
+    }
+    
+    /// Write expenses, optionally limited to a date range, to a CSV file
+    pub fn export_expenses(&self, args: ExportArgs) -> Result<(), AppError> {
+        let expenses = if args.from.is_some() || args.to.is_some() {
+            // Like bulk-edit, a missing start means no lower bound rather than a year ago
+            let from = args.from.or_else(|| Some("0001-01-01".to_string()));
+            let (from_date, to_date) = parse_date_range(from, args.to, self.clock.today())?;
+            self.repository.get_by_date_range(from_date, to_date)?
+        } else {
+            self.repository.get_all()?
+        };
+        
+        let file = std::fs::File::create(&args.output)?;
+        export::csv::write_expenses(io::BufWriter::new(file), &expenses)?;
+        
+        println!("Exported {} expenses to {}", expenses.len(), args.output.display());
+        
+        Ok(())
+    }
+    
+    pub fn restore(&mut self, args: RestoreArgs) -> Result<(), AppError> {
+        self.ensure_writable("restoring a backup")?;
+        
+        let (mut expenses, config) = match args.format {
+            BackupFormat::Zip => backup::read_archive(&args.input)?,
+            format => (backup::read_backup(&args.input, format)?, None),
+        };
+        
+        // Categories from an archived config are added so the restored expenses stay valid;
+        // the rest of the local config is left alone
+        if let Some(config) = config {
+            let (added, _) = self.import_categories(config.categories);
+            for name in &added {
+                println!("Added category: {}", name);
+            }
+            if !added.is_empty() {
+                self.update_config_categories()?;
+            }
+        }
+        
+        // Restored expenses are added as new rows
+        expenses.iter_mut().for_each(Expense::clear_id);
+        self.repository.save_batch(&mut expenses)?;
+        
+        println!("Restored {} expenses from {}", expenses.len(), args.input.display());
+        
+        Ok(())
+    }
+    
+    pub fn import_expenses(&mut self, args: ImportArgs) -> Result<(), AppError> {
+        if !args.dry_run {
+            self.ensure_writable("importing expenses")?;
+        }
+        
+        let PreparedImport { mut expenses, errors, duplicates, new_categories } = self.prepare_import(&args)?;
+        
+        let verb = if args.skip_invalid || args.dry_run { "Skipped" } else { "Invalid" };
+        for (record, error) in &errors {
+            println!("{} record {}: {}", verb, record, error);
+        }
+        for record in &duplicates {
+            println!("Skipped record {}: duplicate of an existing expense", record);
+        }
+        
+        if args.dry_run {
+            for category in &new_categories {
+                println!("Would create category: {}", category.name());
+            }
+            println!("{} valid, {} invalid and {} duplicate records in {}; nothing was imported",
+                expenses.len(), errors.len(), duplicates.len(), args.input.display());
+            return Ok(());
+        }
+        
+        if !errors.is_empty() && !args.skip_invalid {
+            return Err(ImportError::InvalidRecords(errors.len()).into());
+        }
+        
+        self.repository.save_batch(&mut expenses)?;
+        
+        // Only register the new categories once their expenses are stored
+        if !new_categories.is_empty() {
+            let (added, _) = self.import_categories(new_categories);
+            self.update_config_categories()?;
+            for name in &added {
+                println!("Created category: {}", name);
+            }
+        }
+        
+        println!("Imported {} expenses from {} ({} invalid, {} duplicates skipped)",
+            expenses.len(), args.input.display(), errors.len(), duplicates.len());
+        
+        Ok(())
+    }
+    
+    /// Read and validate the records of an import file, without saving anything
+    fn prepare_import(&self, args: &ImportArgs) -> Result<PreparedImport, AppError> {
+        let records: Vec<Result<Expense, String>> = match args.format {
+            ImportFormat::Json => {
+                let content = std::fs::read_to_string(&args.input)?;
+                let expenses: Vec<Expense> = serde_json::from_str(&content)?;
+                expenses.into_iter().map(Ok).collect()
+            },
+            ImportFormat::Csv => self.read_csv_expenses(args)?,
+        };
+        
+        let unknown_target = match &args.map_unknown_to {
+            Some(name) => Some(self.category_registry.get_category(name)
+                .cloned()
+                .ok_or_else(|| CliError::CategoryNotFound(name.clone()))?),
+            None => None,
+        };
+        
+        // Validate every record, keeping the valid ones as new expenses
+        let mut expenses = Vec::new();
+        let mut errors = Vec::new();
+        let mut duplicates = Vec::new();
+        let mut new_categories: Vec<Category> = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            let validation = record.and_then(|mut expense| {
+                let name = expense.category().name().trim();
+                let is_new = !self.category_registry.category_exists(name);
+                
+                if is_new && let Some(category) = &unknown_target {
+                    expense.set_category(category.clone());
+                } else if is_new && args.create_missing_categories {
+                    // Later spellings of a new category use the first one seen
+                    let category = match new_categories.iter().find(|c| c.name().eq_ignore_ascii_case(name)) {
+                        Some(category) => category.clone(),
+                        None => {
+                            let category = Category::new(name, expense.category().description()).map_err(|e| e.to_string())?;
+                            new_categories.push(category.clone());
+                            category
+                        }
+                    };
+                    expense.set_category(category);
+                }
+                
+                self.normalize_expense(&mut expense);
+                validate_amount(expense.amount())
+                    .and_then(|_| validate_precision(expense.amount(), self.config.decimal_places))
+                    .and_then(|_| if args.create_missing_categories {
+                        Ok(())
+                    } else {
+                        validate_category(expense.category().name(), &self.category_registry)
+                    })
+                    .map(|_| expense)
+                    .map_err(|e| e.to_string())
+            });
+            
+            match validation {
+                Ok(mut expense) => {
+                    expense.clear_id();
+                    if let Some(account) = &args.account {
+                        expense.set_account(Some(account.clone()));
+                    }
+                    if !args.allow_duplicates && self.is_duplicate_import(&expense, &expenses)? {
+                        duplicates.push(index + 1);
+                    } else {
+                        expenses.push(expense);
+                    }
+                },
+                Err(e) => errors.push((index + 1, e)),
+            }
+        }
+        
+        Ok(PreparedImport { expenses, errors, duplicates, new_categories })
+    }
+    
+    /// Whether an imported expense matches one already saved, or an earlier record of the same import,
+    /// on amount, category, date and description
+    fn is_duplicate_import(&self, expense: &Expense, earlier: &[Expense]) -> Result<bool, AppError> {
+        let same = |other: &Expense| {
+            other.amount_cents() == expense.amount_cents()
+                && other.category().name() == expense.category().name()
+                && other.date() == expense.date()
+                && other.description() == expense.description()
+        };
+        
+        if earlier.iter().any(same) {
+            return Ok(true);
+        }
+        
+        Ok(self.repository.exists(expense.amount(), expense.category().name(), *expense.date(), expense.description())?)
+    }
+    
+    /// Read a CSV file into expenses using the `--map` column mapping, which is either
+    /// an inline spec or the name of a mapping in the config
+    fn read_csv_expenses(&self, args: &ImportArgs) -> Result<Vec<Result<Expense, String>>, AppError> {
+        let mapping = match &args.map {
+            Some(map) => {
+                let spec = self.config.csv_mappings.get(map).unwrap_or(map);
+                ColumnMapping::parse(spec)?
+            },
+            None => ColumnMapping::default(),
+        };
+        
+        let file = std::fs::File::open(&args.input)?;
+        let records = import::read_csv(file, &mapping, &args.date_format, !args.no_header)?;
+        
+        Ok(records.into_iter().map(|record| {
+            let record = record?;
+            let category_name = record.category.as_deref().unwrap_or(&args.default_category);
+            let category = match self.category_registry.get_category(category_name) {
+                Some(category) => category.clone(),
+                None => Category::new(category_name, None).map_err(|e| e.to_string())?,
+            };
+            let description = default_description(record.description, category.name());
+            
+            let mut expense = Expense::new(record.amount, category, record.date, description);
+            expense.set_currency(expense.category().currency().map(String::from));
+            Ok(expense)
+        }).collect())
+    }
+    
+    /// Apply the same change to every expense matching the filter, in a single batch
+    pub fn bulk_edit(&self, args: BulkEditArgs) -> Result<(), AppError> {
+        self.ensure_writable("editing expenses")?;
+        
+        let category = match &args.category {
+            Some(name) => {
+                validate_category(name, &self.category_registry)?;
+                self.category_registry.get_category(name).map(|c| c.name().to_string())
+            },
+            None => None,
+        };
+        
+        let new_category = match &args.set_category {
+            Some(name) => {
+                validate_category(name, &self.category_registry)?;
+                self.category_registry.get_category(name).cloned()
+            },
+            None => None,
+        };
+        
+        let mut expenses = if args.from.is_some() || args.to.is_some() {
+            // Unlike list and summary, a missing start means no lower bound rather than a year ago
+            let from = args.from.or_else(|| Some("0001-01-01".to_string()));
+            let (from_date, to_date) = parse_date_range(from, args.to, self.clock.today())?;
+            let mut expenses = self.repository.get_by_date_range(from_date, to_date)?;
+            if let Some(category) = &category {
+                expenses.retain(|e| e.category().name() == category);
+            }
+            expenses
+        } else if let Some(category) = &category {
+            self.repository.get_by_category(category)?
+        } else {
+            Vec::new()
+        };
+        
+        if expenses.is_empty() {
+            println!("No expenses found matching the criteria.");
+            return Ok(());
+        }
+        
+        let prompt = format!("Update {} expenses?", expenses.len());
+        if !self.confirm(&prompt)? {
+            println!("Operation cancelled.");
+            return Ok(());
+        }
+        
+        for expense in expenses.iter_mut() {
+            if let Some(category) = &new_category {
+                expense.set_category(category.clone());
+            }
+            if let Some(description) = &args.set_description {
+                expense.set_description(description.clone());
+            }
+            self.normalize_expense(expense);
+        }
+        
+        self.repository.save_batch(&mut expenses)?;
+        println!("Updated {} expenses", expenses.len());
+        
+        Ok(())
+    }
+    
+    pub fn manage_trash(&self, args: TrashArgs) -> Result<(), AppError> {
+        match args.command {
+            TrashCommands::List => {
+                let trashed = ExpenseListing::new(self.repository.get_deleted()?);
+                
+                if trashed.expenses.is_empty() {
+                    println!("The trash is empty.");
+                } else {
+                    self.print_expense_table(&trashed);
+                }
+            },
+            TrashCommands::Restore { id } => {
+                self.ensure_writable("restoring expenses")?;
+                
+                if !self.repository.restore_deleted(id)? {
+                    return Err(RepositoryError::NotFound(format!("No deleted expense found with ID {}", id)).into());
+                }
+                
+                println!("Restored expense {}", id);
+            },
+            TrashCommands::Empty => {
+                self.ensure_writable("emptying the trash")?;
+                
+                let count = self.repository.get_deleted()?.len();
+                if count == 0 {
+                    println!("The trash is empty.");
+                    return Ok(());
+                }
+                
+                let prompt = format!("Permanently remove {} deleted expenses?", count);
+                if !self.confirm(&prompt)? {
+                    println!("Operation cancelled.");
+                    return Ok(());
+                }
+                
+                let removed = self.repository.empty_trash()?;
+                println!("Permanently removed {} expenses", removed);
+            },
+        }
+        
+        Ok(())
+    }
+    
+    /// Report stored data that the entry commands would have rejected, failing if any is found
+    pub fn doctor(&self) -> Result<(), AppError> {
+        let today = self.clock.today();
+        // Future dates are intended when `allow_future_dates` is set
+        let future = if self.config.allow_future_dates {
+            ExpenseListing::new(Vec::new())
+        } else {
+            ExpenseListing::new(self.repository.get_future_dated(today)?)
+        };
+        
+        if future.expenses.is_empty() {
+            println!("No problems found.");
+            return Ok(());
+        }
+        
+        println!("Expenses dated after today ({}):", today);
+        self.print_expense_table(&future);
+        
+        Err(AppError::Other(format!("{} future-dated expense(s) found", future.count())))
+    }
+    
+    pub fn print_schema(&self) -> Result<(), AppError> {
+        println!("{}", serde_json::to_string_pretty(&export::schema::json_schema())?);
+        Ok(())
+    }
+    
+    /// Registered category names in sorted order, so completion output is stable
+    fn category_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.category_registry.all_categories()
+            .iter()
+            .map(|category| category.name().to_string())
+            .collect();
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+    
+    pub fn complete_categories(&self) -> Result<(), AppError> {
+        for name in self.category_names() {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+    
+    pub fn manage_config(&self, args: ConfigArgs) -> Result<(), AppError> {
+        self.ensure_writable("changing the config")?;
+        
+        match args.command {
+            ConfigCommands::Migrate => {
+                let added = Config::migrate_as(&self.config_path, self.config_format())?;
+                
+                if added.is_empty() {
+                    println!("{} is up to date", self.config_path.display());
+                } else {
+                    println!("Added to {}: {}", self.config_path.display(), added.join(", "));
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    pub fn manage_rollups(&self, args: RollupArgs) -> Result<(), AppError> {
+        match args.command {
+            RollupCommands::Rebuild => {
+                self.ensure_writable("rebuilding rollups")?;
+                
+                let rebuilt = self.repository.rebuild_monthly_rollups()?;
+                println!("Rebuilt {} monthly category totals", rebuilt);
+            },
+        }
+        
+        Ok(())
+    }
+    
+    pub fn manage_budgets(&mut self, args: BudgetArgs) -> Result<(), AppError> {
+        match args.command {
+            BudgetCommands::List => {
+                println!("Monthly Budgets:");
+                println!("{}", "-".repeat(50));
+                
+                if self.config.budgets.is_empty() {
+                    println!("No budgets set.");
+                }
+                
+                for (category, budget) in &self.config.budgets {
+                    println!("{:<20} {}", category, self.config.format_money(*budget));
+                }
+                
+                return Ok(());
+            },
+            BudgetCommands::Status { prorate } => {
+                let today = self.clock.today();
+                println!("Budget status for {} (day {} of {}):", today.format("%Y-%m"), today.day(),
+                    parse_month_range(&today.format("%Y-%m").to_string())?.1.day());
+                println!("{}", "-".repeat(50));
+                
+                let statuses = self.budget_status(prorate)?;
+                if statuses.is_empty() {
+                    println!("No budgets set.");
+                }
+                
+                for status in &statuses {
+                    let over = if status.spent > status.target() { "  OVER BUDGET" } else { "" };
+                    match status.prorated {
+                        Some(prorated) => println!("{:<20} {} of {} so far ({} for the month){}",
+                            status.category, self.config.format_money(status.spent), self.config.format_money(prorated),
+                            self.config.format_money(status.budget), over),
+                        None => println!("{:<20} {} of {}{}",
+                            status.category, self.config.format_money(status.spent), self.config.format_money(status.budget), over),
+                    }
+                }
+                
+                return Ok(());
+            },
+            BudgetCommands::Set { category, amount } => {
+                self.ensure_writable("changing budgets")?;
+                validate_amount(amount)?;
+                validate_category(&category, &self.category_registry)?;
+                let category = self.category_registry.get_category(&category)
+                    .map_or(category.clone(), |c| c.name().to_string());
+                
+                self.config.budgets.retain(|name, _| !name.eq_ignore_ascii_case(&category));
+                self.config.budgets.insert(category.clone(), amount);
+                println!("Set the monthly budget of {} to {}", category, self.config.format_money(amount));
+            },
+            BudgetCommands::Remove { category } => {
+                self.ensure_writable("changing budgets")?;
+                
+                let before = self.config.budgets.len();
+                self.config.budgets.retain(|name, _| !name.eq_ignore_ascii_case(&category));
+                if self.config.budgets.len() == before {
+                    return Err(AppError::Other(format!("No budget set for {}", category)));
+                }
+                println!("Removed the budget of {}", category);
+            },
+        }
+        
+        Config::save_field_as(&self.config_path, self.config_format(), "budgets", &self.config.budgets)?;
+        
+        Ok(())
+    }
+    
+    pub fn manage_recurring(&self, args: RecurringArgs) -> Result<(), AppError> {
+        match args.command {
+            RecurringCommands::List => {
+                let templates = self.repository.get_recurring()?;
+                if templates.is_empty() {
+                    println!("No recurring expenses set up.");
+                    return Ok(());
+                }
+                
+                let mut table = Table::new(&[("ID", 5), ("Day", 4), ("Category", 15), ("Amount", 10), ("Description", 30), ("Last applied", 0)]);
+                for template in templates {
+                    table.add_row(vec![
+                        template.id.unwrap_or(0).to_string(),
+                        template.day_of_month.to_string(),
+                        template.category,
+                        self.config.format_money(template.amount),
+                        template.description,
+                        template.last_applied.unwrap_or_else(|| "never".to_string()),
+                    ]);
+                }
+                
+                println!("{}", table.render(self.config.table_style));
+            },
+            RecurringCommands::Add { amount, category, day, description } => {
+                self.ensure_writable("adding recurring expenses")?;
+                
+                let amount = parse_amount_expr(&amount)?;
+                validate_amount(amount)?;
+                validate_precision(amount, self.config.decimal_places)?;
+                validate_category(&category, &self.category_registry)?;
+                let description = default_description(description, &category);
+                let category = self.category_registry.get_category(&category)
+                    .map_or(category.clone(), |c| c.name().to_string());
+                
+                let mut template = RecurringExpense::new(amount, category, day, description);
+                self.repository.save_recurring(&mut template)?;
+                
+                println!("Added recurring expense #{}: {} for {} on day {} of every month",
+                    template.id.unwrap_or(0), self.config.format_money(template.amount), template.description, day);
+            },
+            RecurringCommands::Remove { id } => {
+                self.ensure_writable("removing recurring expenses")?;
+                
+                if !self.repository.delete_recurring(id)? {
+                    return Err(RepositoryError::NotFound(format!("No recurring expense found with ID {}", id)).into());
+                }
+                println!("Removed recurring expense #{}", id);
+            },
+            RecurringCommands::Apply => {
+                let added = self.apply_recurring()?;
+                println!("Added {} recurring expenses for {}", added.len(), self.clock.today().format("%Y-%m"));
+            },
+        }
+        
+        Ok(())
+    }
+    
+    /// Number of recurring templates whose expense for the month of `today` is due but
+    /// hasn't been created yet
+    pub fn count_due_recurring(&self, today: NaiveDate) -> Result<usize, AppError> {
+        Ok(self.repository.get_recurring()?.iter().filter(|template| template.is_due(today)).count())
+    }
+    
+    /// Print a reminder to stderr when recurring expenses are due, so it stays out of
+    /// output meant for other programs. A failing check is not worth reporting here
+    pub fn remind_due_recurring(&self) {
+        match self.count_due_recurring(self.clock.today()) {
+            Ok(1) => eprintln!("1 recurring expense is due. Run `expense_log recurring apply`."),
+            Ok(due) if due > 1 => eprintln!("{} recurring expenses are due. Run `expense_log recurring apply`.", due),
+            _ => {}
+        }
+    }
+    
+    /// Create this month's expense from every recurring template whose day has come and that
+    /// hasn't been applied this month yet, so running it twice in a month adds nothing.
+    /// Returns the expenses added
+    pub fn apply_recurring(&self) -> Result<Vec<Expense>, AppError> {
+        self.ensure_writable("adding expenses")?;
+        
+        let today = self.clock.today();
+        let month = today.format("%Y-%m").to_string();
+        
+        let mut added = Vec::new();
+        for template in self.repository.get_recurring()? {
+            let (Some(id), Some(date)) = (template.id, template.date_in(today.year(), today.month())) else {
+                continue;
+            };
+            if !template.is_due(today) {
+                continue;
+            }
+            
+            let Some(category) = self.category_registry.get_category(&template.category) else {
+                println!("Skipped recurring expense #{}: {}", id, CliError::CategoryNotFound(template.category.clone()));
+                continue;
+            };
+            
+            let mut expense = Expense::new(template.amount, category.clone(), date, template.description.clone());
+            expense.set_currency(category.currency().map(String::from));
+            
+            if self.repository.apply_recurring(id, &month, &mut expense)? {
+                println!("Added: {} for {} on {}", self.config.format_money(expense.amount()), expense.description(), expense.date());
+                added.push(expense);
+            }
+        }
+        
+        Ok(added)
+    }
+    
+    pub fn manage_categories(&mut self, args: CategoryArgs) -> Result<(), AppError> {
+        match args.command {
+            CategoryCommands::List => {
+                println!("Available Categories:");
+                println!("{}", "-".repeat(50));
+                
+                let categories = self.category_registry.all_categories();
+                
+                if categories.is_empty() {
+                    println!("No categories defined.");
+                    return Ok(());
+                }
+                
+                for category in categories {
+                    if let Some(desc) = category.description() {
+                        println!("{:<20} - {}", category.name(), desc);
+                    } else {
+                        println!("{}", category.name());
+                    }
+                }
+            },
+            CategoryCommands::Add { name, description } => {
+                self.ensure_writable("changing categories")?;
+                
+                // Add the category
+                match self.category_registry.add_category(&name, description.as_deref()) {
+                    Ok(category) => {
+                        println!("Added category: {}", category.name());
+                        
+                        // Update the config and save it
+                        self.update_config_categories()?;
+                    },
+                    Err(e) => {
+                        return Err(AppError::Other(format!("Failed to add category: {}", e)));
+                    }
+                }
+            },
+            CategoryCommands::Import { file } => {
+                self.ensure_writable("changing categories")?;
+                
+                let content = std::fs::read_to_string(&file)?;
+                let categories: Vec<Category> = serde_yaml::from_str(&content)
+                    .map_err(crate::config::ConfigError::from)?;
+                
+                let (added, skipped) = self.import_categories(categories);
+                for name in &added {
+                    println!("Added category: {}", name);
+                }
+                for (name, e) in &skipped {
+                    println!("Skipped category '{}': {}", name, e);
+                }
+                println!("Added {} categories from {} ({} skipped)", added.len(), file.display(), skipped.len());
+                
+                if !added.is_empty() {
+                    self.update_config_categories()?;
+                }
+            },
+            CategoryCommands::Prune { dry_run } => {
+                let unused = self.unused_custom_categories()?;
+                
+                if unused.is_empty() {
+                    println!("No unused custom categories.");
+                    return Ok(());
+                }
+                
+                if dry_run {
+                    for name in &unused {
+                        println!("Would remove category: {}", name);
+                    }
+                    return Ok(());
+                }
+                
+                self.ensure_writable("changing categories")?;
+                
+                let prompt = format!("Remove {} unused categories ({})?", unused.len(), unused.join(", "));
+                if !self.confirm(&prompt)? {
+                    println!("Operation cancelled.");
+                    return Ok(());
+                }
+                
+                for name in &unused {
+                    self.category_registry.remove_category(name)
+                        .map_err(|e| AppError::Other(format!("Failed to remove category: {}", e)))?;
+                    println!("Removed category: {}", name);
+                }
+                
+                self.update_config_categories()?;
+            },
+            CategoryCommands::Remove { name } => {
+                self.ensure_writable("changing categories")?;
+                
+                // First check if there are any expenses with this category
+                if let Ok(expenses) = self.repository.get_by_category(&name)
+                    && !expenses.is_empty() {
+                    // Ask for confirmation
+                    let prompt = format!("There are {} expenses with category '{}'. Are you sure you want to remove it?", 
+                        expenses.len(), name);
+                    
+                    if !self.confirm(&prompt)? {
+                        println!("Operation cancelled.");
+                        return Ok(());
+                    }
+                }
+                
+                // Remove the category
+                match self.category_registry.remove_category(&name) {
+                    Ok(_) => {
+                        println!("Removed category: {}", name);
+                        
+                        // Update the config and save it
+                        self.update_config_categories()?;
+                    },
+                    Err(e) => {
+                        return Err(AppError::Other(format!("Failed to remove category: {}", e)));
+                    }
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Registered categories that no expense, not even a trashed one, uses, leaving out the
+    /// fallback and, with `use_system_categories`, the built-in ones. Names are compared
+    /// case-insensitively, sorted the same way
+    fn unused_custom_categories(&self) -> Result<Vec<String>, AppError> {
+        let mut kept = self.repository.get_distinct_categories()?;
+        kept.push(config::FALLBACK_CATEGORY.to_string());
+        if self.config.use_system_categories {
+            kept.extend(Config::system_categories().iter().map(|category| category.name().to_string()));
+        }
+        
+        let mut unused: Vec<String> = self.category_registry.all_categories()
+            .into_iter()
+            .map(|category| category.name())
+            .filter(|name| !kept.iter().any(|kept| kept.eq_ignore_ascii_case(name)))
+            .map(String::from)
+            .collect();
+        
+        unused.sort_by_key(|name| name.to_lowercase());
+        
+        Ok(unused)
+    }
+    
+    /// Add each category that doesn't exist yet, comparing names case-insensitively.
+    /// Returns the names added, and the names skipped with the reason.
+    fn import_categories(&mut self, categories: Vec<Category>) -> (Vec<String>, Vec<(String, CategoryError)>) {
+        let (mut added, mut skipped) = (Vec::new(), Vec::new());
+        
+        for category in categories {
+            match self.category_registry.add_category(category.name(), category.description()) {
+                Ok(category) => added.push(category.name().to_string()),
+                Err(e) => skipped.push((category.name().to_string(), e)),
+            }
+        }
+        
+        (added, skipped)
+    }
+    
+    fn update_config_categories(&mut self) -> Result<(), AppError> {
+        // Update config with current categories
+        self.config.categories = self.category_registry.all_categories()
+            .into_iter()
+            .cloned()
+            .collect();
+        
+        // Save config
+        Config::save_field_as(&self.config_path, self.config_format(), "categories", &self.config.categories)?;
+        
+        Ok(())
+    }
+}
+
+/// Columns of the CSV and TSV summary formats
+const SUMMARY_COLUMNS: [&str; 3] = ["category", "total", "percentage"];
+
+/// Escape the characters that would break a tab-separated line
+fn escape_tsv_field(field: &str) -> String {
+    field.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Order amounts from largest to smallest
+fn compare_amounts_desc(a: Decimal, b: Decimal) -> Ordering {
+    b.cmp(&a)
+}
+
+/// `part` as a percentage of `whole`, which must not be zero
+fn percent_of(part: Decimal, whole: Decimal) -> f64 {
+    (part / whole * Decimal::ONE_HUNDRED).to_f64().unwrap_or(0.0)
+}
+
+/// Sort `(category, amount)` rows by name, amount or number of expenses (from `counts`).
+/// Without an explicit order, names sort ascending and amounts and counts descending. Ties
+/// fall back to the larger amount, then the name
+fn sort_category_rows(rows: &mut [(String, Decimal)], counts: &[(String, usize)], sort_by: CategorySort, order: Option<SortOrder>) {
+    let count_of = |category: &str| counts.iter().find(|(name, _)| name == category).map_or(0, |(_, count)| *count);
+    let by_name = |a: &(String, Decimal), b: &(String, Decimal)| a.0.to_lowercase().cmp(&b.0.to_lowercase()).then_with(|| a.0.cmp(&b.0));
+    
+    let descending = order.map_or(sort_by != CategorySort::Name, |order| order == SortOrder::Desc);
+    
+    rows.sort_by(|a, b| {
+        let ordering = match sort_by {
+            CategorySort::Name => by_name(a, b),
+            CategorySort::Amount => compare_amounts_desc(b.1, a.1),
+            CategorySort::Count => count_of(&a.0).cmp(&count_of(&b.0)),
+        };
+        let ordering = if descending { ordering.reverse() } else { ordering };
+        
+        ordering
+            .then_with(|| compare_amounts_desc(a.1, b.1))
+            .then_with(|| by_name(a, b))
+    });
+}
+
+/// Each total's share of their sum as a percentage with one decimal place, using
+/// largest-remainder rounding so the shares add up to exactly 100
+fn distribute_percentages(totals: &[Decimal]) -> Vec<f64> {
+    let sum: Decimal = totals.iter().sum();
+    if sum <= Decimal::ZERO || totals.iter().any(|t| *t < Decimal::ZERO) {
+        return totals.iter().map(|t| if sum > Decimal::ZERO { (percent_of(*t, sum) * 10.0).round() / 10.0 } else { 0.0 }).collect();
+    }
+    
+    // Work in tenths of a percent: floor each share, then hand the leftover tenths
+    // to the shares that lost the most in the floor
+    let exact: Vec<Decimal> = totals.iter().map(|t| t / sum * Decimal::ONE_THOUSAND).collect();
+    let mut tenths: Vec<u64> = exact.iter().map(|e| e.floor().to_u64().unwrap_or(0)).collect();
+    let leftover = 1000u64.saturating_sub(tenths.iter().sum());
+    
+    let mut by_remainder: Vec<usize> = (0..exact.len()).collect();
+    by_remainder.sort_by(|&a, &b| compare_amounts_desc(exact[a].fract(), exact[b].fract()));
+    for &index in by_remainder.iter().take(leftover as usize) {
+        tenths[index] += 1;
+    }
+    
+    tenths.into_iter().map(|t| t as f64 / 10.0).collect()
+}
+
+/// Sparkline levels, lowest first
+const BLOCK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Sparkline levels for terminals that can't show the block characters
+const ASCII_BARS: [char; 8] = ['_', '.', ':', '-', '=', '+', '*', '#'];
+
+/// Render values as a line of `bars` characters scaled to `max`; zero values are blank
+fn sparkline(values: &[Decimal], max: Decimal, bars: &[char]) -> String {
+    values.iter()
+        .map(|&value| {
+            if value <= Decimal::ZERO || max <= Decimal::ZERO {
+                ' '
+            } else {
+                let level = ((value / max).to_f64().unwrap_or(0.0) * (bars.len() - 1) as f64).round() as usize;
+                bars[level.min(bars.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+/// Spread monthly totals over every month from the first to the last, with zero for
+/// months that have none
+fn dense_monthly_amounts(totals: &MonthlyTotals) -> Vec<Decimal> {
+    let (Some(((first_year, first_month), _)), Some(((last_year, last_month), _))) = (totals.first(), totals.last()) else {
+        return Vec::new();
+    };
+    
+    let index = |year: i32, month: u32| (year * 12 + month as i32 - 1) as usize;
+    let start = index(*first_year, *first_month);
+    let mut amounts = vec![Decimal::ZERO; index(*last_year, *last_month) - start + 1];
+    for ((year, month), amount) in totals {
+        amounts[index(*year, *month) - start] += amount;
+    }
+    
+    amounts
+}
+
+/// Every `(year, month)` from the month of `from` to the month of `to`, oldest first
+fn months_between(from: NaiveDate, to: NaiveDate) -> Vec<(i32, u32)> {
+    let mut months = Vec::new();
+    let mut month_start = from.with_day(1).unwrap_or(from);
+    
+    while month_start <= to {
+        months.push((month_start.year(), month_start.month()));
+        let Some(next_month) = month_start.checked_add_months(chrono::Months::new(1)) else {
+            break;
+        };
+        month_start = next_month;
+    }
+    
+    months
+}
+
+/// Spending per category for each of `months`, 0 where a category has none.
+/// Categories are ordered by their total, largest first, then by name
+fn category_month_matrix(totals: &MonthlyCategoryTotals, months: &[(i32, u32)]) -> Vec<(String, Vec<Decimal>)> {
+    let mut rows: Vec<(String, Vec<Decimal>)> = Vec::new();
+    
+    for (month, category, amount) in totals {
+        let Some(column) = months.iter().position(|m| m == month) else {
+            continue;
+        };
+        let row = match rows.iter().position(|(name, _)| name == category) {
+            Some(index) => index,
+            None => {
+                rows.push((category.clone(), vec![Decimal::ZERO; months.len()]));
+                rows.len() - 1
+            },
+        };
+        rows[row].1[column] += amount;
+    }
+    
+    rows.sort_by(|(a_name, a), (b_name, b)| {
+        b.iter().sum::<Decimal>().cmp(&a.iter().sum::<Decimal>()).then_with(|| a_name.cmp(b_name))
+    });
+    
+    rows
+}
+
+/// Whether the locale, as set by `LC_ALL`, `LC_CTYPE` or `LANG` in that order of precedence,
+/// uses UTF-8. Without any of them set the terminal is assumed to handle UTF-8
+fn locale_is_utf8(lookup: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .find_map(|name| lookup(name).filter(|value| !value.is_empty()))
+        .is_none_or(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// Find the runs of consecutive zero-valued entries in a dense, ordered series,
+/// returning the first and last key of each run
+fn zero_runs<T: Copy>(series: &[(T, Decimal)]) -> Vec<(T, T)> {
+    let mut runs = Vec::new();
+    let mut current: Option<(T, T)> = None;
+    
+    for &(key, value) in series {
+        if value.is_zero() {
+            current = Some(current.map_or((key, key), |(first, _)| (first, key)));
+        } else if let Some(run) = current.take() {
+            runs.push(run);
+        }
+    }
+    runs.extend(current);
+    
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::clock::FixedClock;
+    use crate::cli::helpers::CliError;
+    use crate::config::AlertRule;
+    use crate::repository::sqlite::SqliteExpenseRepository;
+    
+    fn create_test_app() -> App<SqliteExpenseRepository> {
+        let repository = SqliteExpenseRepository::new_in_memory().unwrap();
+        App::new(repository, Config::default())
+            .with_clock(FixedClock(NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()))
+    }
+    
+    fn add_args(amount: &str, category: &str, date: Option<&str>) -> AddArgs {
+        AddArgs {
+            amount: amount.to_string(),
+            category: category.to_string(),
+            date: date.map(String::from),
+            description: None,
+            currency: None,
+            secondary: None,
+            round: None,
+            account: None,
+            if_not_exists: false,
+        }
+    }
+    
+    #[test]
+    fn add_if_not_exists_skips_identical_expense() {
+        let app = create_test_app();
+        let args = AddArgs { if_not_exists: true, ..add_args("12.50", "Groceries", Some("2025-04-10")) };
+        
+        assert!(app.try_add_expense(args.clone()).unwrap().id().is_some());
+        assert!(app.try_add_expense(args.clone()).unwrap().id().is_none());
+        assert_eq!(app.repository.get_all().unwrap().len(), 1);
+        
+        // A different amount or date is a new expense
+        app.try_add_expense(AddArgs { amount: "12.51".to_string(), ..args.clone() }).unwrap();
+        app.try_add_expense(AddArgs { date: Some("2025-04-11".to_string()), ..args }).unwrap();
+        assert_eq!(app.repository.get_all().unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn try_add_expense_returns_saved_expense() {
+        let app = create_test_app();
+        
+        let expense = app.try_add_expense(add_args("12.50", "groceries", None)).unwrap();
+        
+        assert!(expense.id().is_some());
+        assert_eq!(expense.amount(), dec!(12.50));
+        assert_eq!(expense.category().name(), "Groceries");
+        assert_eq!(*expense.date(), NaiveDate::from_ymd_opt(2025, 4, 11).unwrap());
+        assert_eq!(expense.description(), "Expense in groceries");
+        assert_eq!(app.repository.get_all().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn forecast_month_extrapolates_run_rate() {
+        let app = create_test_app();
+        app.try_add_expense(add_args("30", "Groceries", Some("2025-04-02"))).unwrap();
+        app.try_add_expense(add_args("10", "Dining", Some("2025-04-10"))).unwrap();
+        app.try_add_expense(add_args("99", "Dining", Some("2025-03-31"))).unwrap();
+        
+        // 10 of April's 30 days have passed, so spending so far is tripled
+        let forecast = app.forecast_month(NaiveDate::from_ymd_opt(2025, 4, 10).unwrap()).unwrap();
+        
+        assert_eq!(forecast, vec![
+            ("Groceries".to_string(), dec!(30.0), dec!(90.0)),
+            ("Dining".to_string(), dec!(10.0), dec!(30.0)),
+        ]);
+    }
+    
+    fn csv_import_args(file: &tempfile::NamedTempFile) -> ImportArgs {
+        ImportArgs {
+            input: file.path().to_path_buf(),
+            format: ImportFormat::Csv,
+            map: None,
+            date_format: "%Y-%m-%d".to_string(),
+            no_header: false,
+            default_category: "Miscellaneous".to_string(),
+            account: None,
+            create_missing_categories: false,
+            map_unknown_to: None,
+            skip_invalid: true,
+            allow_duplicates: false,
+            dry_run: false,
+        }
+    }
+    
+    fn import_test_file() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "date,amount,category,description\n\
+            2025-04-01,12.50,Groceries,Milk\n\
+            2025-04-02,abc,Dining,Lunch\n\
+            2025-04-03,8.00,Yachts,Sails\n\
+            2025-04-04,3.20,dining,Coffee\n").unwrap();
+        file
+    }
+    
+    #[test]
+    fn import_dry_run_reports_errors_without_saving() {
+        let mut app = create_test_app();
+        let file = import_test_file();
+        let args = ImportArgs { dry_run: true, ..csv_import_args(&file) };
+        
+        let prepared = app.prepare_import(&args).unwrap();
+        assert_eq!(prepared.expenses.len(), 2);
+        assert_eq!(prepared.errors.iter().map(|(record, _)| *record).collect::<Vec<_>>(), vec![2, 3]);
+        
+        app.import_expenses(args.clone()).unwrap();
+        assert!(app.repository.get_all().unwrap().is_empty());
+        
+        // The real import saves exactly the records the dry run accepted
+        app.import_expenses(ImportArgs { dry_run: false, ..args }).unwrap();
+        assert_eq!(app.repository.get_all().unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn import_aborts_on_invalid_records_and_skips_duplicates() {
+        let mut app = create_test_app();
+        let file = import_test_file();
+        
+        // Without --skip-invalid a single bad record keeps the whole file out
+        let result = app.import_expenses(ImportArgs { skip_invalid: false, ..csv_import_args(&file) });
+        assert!(matches!(result, Err(AppError::ImportError(ImportError::InvalidRecords(2)))));
+        assert!(app.repository.get_all().unwrap().is_empty());
+        
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "date,amount,category,description\n\
+            2025-04-01,12.50,Groceries,Milk\n\
+            2025-04-01,12.5,Groceries,Milk\n\
+            2025-04-02,3.20,Dining,Coffee\n").unwrap();
+        
+        // The repeated row within the file is only imported once
+        let prepared = app.prepare_import(&csv_import_args(&file)).unwrap();
+        assert_eq!(prepared.duplicates, vec![2]);
+        app.import_expenses(csv_import_args(&file)).unwrap();
+        assert_eq!(app.repository.get_all().unwrap().len(), 2);
+        
+        // Importing the same file again adds nothing unless duplicates are allowed
+        app.import_expenses(csv_import_args(&file)).unwrap();
+        assert_eq!(app.repository.get_all().unwrap().len(), 2);
+        app.import_expenses(ImportArgs { allow_duplicates: true, ..csv_import_args(&file) }).unwrap();
+        assert_eq!(app.repository.get_all().unwrap().len(), 5);
+    }
+    
+    #[test]
+    fn import_creates_or_remaps_unknown_categories() {
+        let mut file = import_test_file();
+        writeln!(file, "2025-04-05,2.00,yachts,Rope").unwrap();
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("expense_log.yaml");
+        
+        let mut app = create_test_app().with_config_path(&config_path);
+        app.import_expenses(ImportArgs { create_missing_categories: true, ..csv_import_args(&file) }).unwrap();
+        
+        // Both spellings of the unknown category end up in a single new category
+        assert_eq!(app.repository.get_by_category("Yachts").unwrap().len(), 2);
+        assert!(app.category_registry.category_exists("Yachts"));
+        let saved = Config::load(&config_path).unwrap();
+        assert!(saved.categories.iter().any(|c| c.name() == "Yachts"));
+        
+        let mut app = create_test_app();
+        app.import_expenses(ImportArgs { map_unknown_to: Some("miscellaneous".to_string()), ..csv_import_args(&file) }).unwrap();
+        assert_eq!(app.repository.get_by_category("Miscellaneous").unwrap().len(), 2);
+        assert!(!app.category_registry.category_exists("Yachts"));
+        
+        // The target has to exist already
+        let result = app.import_expenses(ImportArgs { map_unknown_to: Some("Boats".to_string()), ..csv_import_args(&file) });
+        assert!(matches!(result, Err(AppError::CliError(CliError::CategoryNotFound(_)))));
+    }
+    
+    #[test]
+    fn import_categories_skips_existing_names() {
+        let mut app = create_test_app();
+        let categories = vec![
+            Category::new("Pets", Some("Food, vet, toys")).unwrap(),
+            Category::new("groceries", None).unwrap(),
+            Category::new("PETS", None).unwrap(),
+        ];
+        
+        let (added, skipped) = app.import_categories(categories);
+        
+        assert_eq!(added, vec!["Pets".to_string()]);
+        assert_eq!(skipped.len(), 2);
+        let pets = app.category_registry.get_category("pets").unwrap();
+        assert_eq!(pets.name(), "Pets");
+        assert_eq!(pets.description(), Some("Food, vet, toys"));
+    }
+    
+    #[test]
+    fn edit_changes_only_the_given_fields() {
+        let app = create_test_app();
+        let id = app.try_add_expense(AddArgs { description: Some("Lunch".to_string()), ..add_args("12.50", "Dining", Some("2025-04-01")) }).unwrap().id().unwrap();
+        let edit_args = |id| EditArgs { id, amount: None, category: None, date: None, description: None };
+        
+        app.edit_expense(EditArgs { amount: Some("14+1.5".to_string()), category: Some("groceries".to_string()), ..edit_args(id) }).unwrap();
+        
+        let expense = app.repository.get_by_id(id).unwrap().unwrap();
+        assert_eq!(expense.amount(), dec!(15.5));
+        assert_eq!(expense.category().name(), "Groceries");
+        assert_eq!(expense.description(), "Lunch");
+        assert_eq!(expense.date().to_string(), "2025-04-01");
+        
+        // Invalid changes are rejected without saving anything
+        assert!(matches!(
+            app.edit_expense(EditArgs { category: Some("Yachts".to_string()), ..edit_args(id) }),
+            Err(AppError::CliError(CliError::CategoryNotFound(_)))
+        ));
+        assert!(matches!(
+            app.edit_expense(EditArgs { date: Some("2025-05-01".to_string()), ..edit_args(id) }),
+            Err(AppError::ExpenseError(ExpenseError::InvalidDate(_)))
+        ));
+        assert_eq!(app.repository.get_by_id(id).unwrap().unwrap().category().name(), "Groceries");
+        
+        // A missing ID is an error rather than a new expense
+        let missing = app.edit_expense(EditArgs { amount: Some("1".to_string()), ..edit_args(999) }).unwrap_err();
+        assert_eq!(missing.exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(app.repository.get_all().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn export_writes_expenses_in_range_to_csv() {
+        let app = create_test_app();
+        app.add_expense(AddArgs { description: Some("Pizza, large".to_string()), ..add_args("12.5", "Dining", Some("2025-03-31")) }).unwrap();
+        app.add_expense(add_args("7", "Groceries", Some("2025-04-02"))).unwrap();
+        
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("expenses.csv");
+        let export_args = |from: Option<&str>| ExportArgs { output: output.clone(), from: from.map(String::from), to: None };
+        
+        app.export_expenses(export_args(None)).unwrap();
+        let content = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(content.contains(",2025-03-31,Dining,12.50,\"Pizza, large\"\n"));
+        
+        app.export_expenses(export_args(Some("2025-04"))).unwrap();
+        let content = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(content.lines().collect::<Vec<_>>()[1..], ["2,2025-04-02,Groceries,7.00,Expense in Groceries"]);
+    }
+    
+    #[test]
+    fn delete_moves_expense_to_trash() {
+        let app = create_test_app();
+        let id = app.try_add_expense(add_args("12.50", "Dining", Some("2025-04-01"))).unwrap().id().unwrap();
+        
+        app.delete_expense(DeleteArgs { id, yes: true, permanent: false }).unwrap();
+        
+        assert!(app.repository.get_by_id(id).unwrap().is_none());
+        assert_eq!(app.repository.get_deleted().unwrap().len(), 1);
+        
+        let missing = app.delete_expense(DeleteArgs { id: 999, yes: true, permanent: false }).unwrap_err();
+        assert_eq!(missing.to_string(), "Repository error: Entity not found: No expense found with ID 999");
+        assert_eq!(missing.exit_code(), EXIT_NOT_FOUND);
+    }
+    
+    #[test]
+    fn delete_permanent_skips_the_trash() {
+        let app = create_test_app().with_assume_yes(true);
+        let id = app.try_add_expense(add_args("12.50", "Dining", Some("2025-04-01"))).unwrap().id().unwrap();
+        
+        app.delete_expense(DeleteArgs { id, yes: false, permanent: true }).unwrap();
+        
+        assert!(app.repository.get_by_id(id).unwrap().is_none());
+        assert!(app.repository.get_deleted().unwrap().is_empty());
+        assert!(app.delete_expense(DeleteArgs { id, yes: true, permanent: true }).is_err());
+    }
+    
+    #[test]
+    fn list_returns_expenses_and_their_total() {
+        let app = create_test_app();
+        for amount in ["0.1", "0.2", "19.99"] {
+            app.add_expense(add_args(amount, "Groceries", Some("2025-04-01"))).unwrap();
+        }
+        app.add_expense(add_args("7", "Dining", Some("2025-04-02"))).unwrap();
+        
+        let groceries = ListArgs {
+            category: Some("Groceries".to_string()),
+            from: None,
+            to: None,
+            since: None,
+            until: None,
+            month: None,
+            description_glob: None,
+            account: None,
+            limit: None,
+            page: None,
+            page_size: 50,
+            watch: false,
+            interval: 2,
+            format: ListFormat::Table,
+        };
+        let listing = app.find_listing(groceries.clone()).unwrap();
+        
+        assert_eq!(listing.count(), 3);
+        let cents: i64 = listing.expenses.iter().map(Expense::amount_cents).sum();
+        assert_eq!(listing.total, Decimal::new(cents, 2));
+        assert_eq!(listing.total, dec!(20.29));
+        
+        // A date range narrows a category filter down further
+        let listing = app.find_listing(ListArgs { from: Some("2025-04-02".to_string()), ..groceries }).unwrap();
+        assert_eq!(listing.count(), 0);
+        assert_eq!(listing.total, Decimal::ZERO);
+    }
+    
+    #[test]
+    fn list_by_category_and_month() {
+        let app = create_test_app();
+        app.add_expense(add_args("4", "Groceries", Some("2025-03-31"))).unwrap();
+        app.add_expense(add_args("5", "Groceries", Some("2025-04-01"))).unwrap();
+        app.add_expense(add_args("6", "Dining", Some("2025-04-02"))).unwrap();
+        
+        let listing = app.find_listing(ListArgs {
+            category: Some("Groceries".to_string()),
+            from: None,
+            to: None,
+            since: None,
+            until: None,
+            month: Some("2025-04".to_string()),
+            description_glob: None,
+            account: None,
+            limit: None,
+            page: None,
+            page_size: 50,
+            watch: false,
+            interval: 2,
+            format: ListFormat::Table,
+        }).unwrap();
+        
+        assert_eq!(listing.expenses.iter().map(Expense::amount).collect::<Vec<_>>(), vec![dec!(5)]);
+    }
+    
+    #[test]
+    fn list_pages_with_and_without_filters() {
+        let app = create_test_app();
+        for day in 1..=5 {
+            app.add_expense(add_args(&day.to_string(), "Groceries", Some(&format!("2025-04-0{}", day)))).unwrap();
+        }
+        app.add_expense(add_args("9", "Dining", Some("2025-04-06"))).unwrap();
+        
+        let list_args = ListArgs {
+            category: None,
+            from: None,
+            to: None,
+            since: None,
+            until: None,
+            month: None,
+            description_glob: None,
+            account: None,
+            limit: None,
+            page: Some(2),
+            page_size: 4,
+            watch: false,
+            interval: 2,
+            format: ListFormat::Json,
+        };
+        
+        let listing = app.find_listing(list_args.clone()).unwrap();
+        assert_eq!(listing.expenses.iter().map(Expense::amount).collect::<Vec<_>>(), vec![dec!(2), dec!(1)]);
+        assert_eq!(listing.page, Some((2, 2)));
+        
+        let listing = app.find_listing(ListArgs { category: Some("Groceries".to_string()), page: Some(1), ..list_args.clone() }).unwrap();
+        assert_eq!(listing.count(), 4);
+        assert_eq!(listing.page, Some((1, 2)));
+        
+        let listing = app.find_listing(ListArgs { page: None, limit: Some(3), ..list_args }).unwrap();
+        assert_eq!(listing.expenses.iter().map(Expense::amount).collect::<Vec<_>>(), vec![dec!(9), dec!(5), dec!(4)]);
+        assert_eq!(listing.page, None);
+    }
+    
+    #[test]
+    fn prune_removes_only_unused_custom_categories() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("expense_log.yaml");
+        
+        let mut app = create_test_app().with_config_path(&config_path).with_assume_yes(true);
+        app.import_categories(vec![
+            Category::new("Pets", None).unwrap(),
+            Category::new("Yachts", None).unwrap(),
+            Category::new("Aardvarks", None).unwrap(),
+        ]);
+        app.add_expense(add_args("12", "pets", Some("2025-04-01"))).unwrap();
+        
+        assert_eq!(app.unused_custom_categories().unwrap(), vec!["Aardvarks", "Yachts"]);
+        
+        app.manage_categories(CategoryArgs { command: CategoryCommands::Prune { dry_run: true } }).unwrap();
+        assert!(app.category_registry.category_exists("Yachts"));
+        
+        app.manage_categories(CategoryArgs { command: CategoryCommands::Prune { dry_run: false } }).unwrap();
+        assert!(!app.category_registry.category_exists("Yachts"));
+        assert!(!app.category_registry.category_exists("Aardvarks"));
+        assert!(app.category_registry.category_exists("Pets"));
+        // Unused built-in categories stay
+        assert!(app.category_registry.category_exists("Healthcare"));
+        
+        let saved = Config::load(&config_path).unwrap();
+        assert!(!saved.categories.iter().any(|c| c.name() == "Yachts"));
+        
+        // Without the built-in categories they are pruned too, except the fallback
+        let config = Config { use_system_categories: false, ..Config::default() };
+        let app = App::new(SqliteExpenseRepository::new_in_memory().unwrap(), config);
+        let unused = app.unused_custom_categories().unwrap();
+        assert!(unused.contains(&"Healthcare".to_string()));
+        assert!(!unused.contains(&config::FALLBACK_CATEGORY.to_string()));
+    }
+    
+    #[test]
+    fn apply_recurring_adds_due_templates_once_per_month() {
+        let app = create_test_app();
+        let add = |amount: &str, category: &str, day| RecurringArgs {
+            command: RecurringCommands::Add { amount: amount.to_string(), category: category.to_string(), day, description: None },
+        };
+        app.manage_recurring(add("950", "household", 1)).unwrap();
+        app.manage_recurring(add("10.99", "Hobbies", 15)).unwrap();
+        assert!(app.manage_recurring(add("5", "Yachts", 1)).is_err());
+        
+        let today = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        assert_eq!(app.count_due_recurring(today).unwrap(), 1);
+        assert_eq!(app.count_due_recurring(NaiveDate::from_ymd_opt(2025, 4, 15).unwrap()).unwrap(), 2);
+        
+        // Only the template whose day has come is applied, and only once
+        let added = app.apply_recurring().unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].category().name(), "Household");
+        assert_eq!(added[0].date(), &NaiveDate::from_ymd_opt(2025, 4, 1).unwrap());
+        
+        assert!(app.apply_recurring().unwrap().is_empty());
+        assert_eq!(app.repository.get_all().unwrap().len(), 1);
+        assert_eq!(app.count_due_recurring(today).unwrap(), 0);
+    }
+    
+    #[test]
+    fn budgets_are_saved_and_checked_per_month() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("expense_log.yaml");
+        
+        let mut app = create_test_app().with_config_path(&config_path);
+        let budget = |category: &str, amount| BudgetArgs { command: BudgetCommands::Set { category: category.to_string(), amount } };
+        app.manage_budgets(budget("dining", dec!(50))).unwrap();
+        app.manage_budgets(budget("Groceries", dec!(200))).unwrap();
+        assert!(app.manage_budgets(budget("Yachts", dec!(10))).is_err());
+        
+        app.add_expense(add_args("30", "Dining", Some("2025-03-05"))).unwrap();
+        app.add_expense(add_args("40", "Dining", Some("2025-04-05"))).unwrap();
+        app.add_expense(add_args("25", "Dining", Some("2025-04-06"))).unwrap();
+        
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(app.check_budgets(date("2025-04-01"), date("2025-04-30")).unwrap(), vec![("Dining".to_string(), dec!(65), dec!(50))]);
+        // Over two months the budget doubles
+        assert!(app.check_budgets(date("2025-03-01"), date("2025-04-30")).unwrap().is_empty());
+        
+        app.manage_budgets(BudgetArgs { command: BudgetCommands::Remove { category: "DINING".to_string() } }).unwrap();
+        assert!(app.check_budgets(date("2025-04-01"), date("2025-04-30")).unwrap().is_empty());
+        
+        let saved = Config::load(&config_path).unwrap();
+        assert_eq!(saved.budgets.into_iter().collect::<Vec<_>>(), vec![("Groceries".to_string(), dec!(200))]);
+    }
+    
+    #[test]
+    fn budget_status_prorates_by_days_elapsed() {
+        let config = Config { budgets: std::collections::BTreeMap::from([("Dining".to_string(), dec!(300))]), ..Config::default() };
+        let app = App::new(SqliteExpenseRepository::new_in_memory().unwrap(), config)
+            .with_clock(FixedClock(NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()));
+        app.add_expense(add_args("120", "Dining", Some("2025-04-05"))).unwrap();
+        app.add_expense(add_args("50", "Dining", Some("2025-03-30"))).unwrap();
+        
+        // On day 11 of 30 the budget of 300 is prorated to 110
+        let status = &app.budget_status(true).unwrap()[0];
+        assert_eq!((status.spent, status.budget, status.prorated), (dec!(120), dec!(300), Some(dec!(110))));
+        assert!(status.spent > status.target());
+        
+        let status = &app.budget_status(false).unwrap()[0];
+        assert_eq!(status.prorated, None);
+        assert!(status.spent <= status.target());
+    }
+    
+    #[test]
+    fn large_expense_is_added_with_assume_yes() {
+        let repository = SqliteExpenseRepository::new_in_memory().unwrap();
+        let config = Config {
+            large_expense_warning_threshold: Some(dec!(1000.0)),
+            ..Config::default()
+        };
+        let app = App::new(repository, config).with_assume_yes(true);
+        
+        let expense = app.try_add_expense(add_args("4250", "Groceries", Some("2025-04-11"))).unwrap();
+        assert_eq!(expense.amount(), dec!(4250.0));
+    }
+    
+    #[test]
+    fn future_dates_follow_allow_future_dates() {
+        let edit_date = |id, date: &str| EditArgs { id, amount: None, category: None, date: Some(date.to_string()), description: None };
+        
+        // Adding never checked the date; editing and doctor do unless future dates are allowed
+        let app = create_test_app();
+        let id = app.try_add_expense(add_args("80", "Healthcare", Some("2025-04-12"))).unwrap().id().unwrap();
+        assert!(matches!(app.edit_expense(edit_date(id, "2025-05-01")), Err(AppError::ExpenseError(_))));
+        assert!(app.doctor().is_err());
+        
+        let config = Config { allow_future_dates: true, ..Config::default() };
+        let app = App::new(SqliteExpenseRepository::new_in_memory().unwrap(), config)
+            .with_clock(FixedClock(NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()));
+        let id = app.try_add_expense(add_args("80", "Healthcare", Some("2025-04-12"))).unwrap().id().unwrap();
+        
+        app.edit_expense(edit_date(id, "2025-05-01")).unwrap();
+        assert_eq!(app.repository.get_by_id(id).unwrap().unwrap().date(), &NaiveDate::from_ymd_opt(2025, 5, 1).unwrap());
+        assert!(app.doctor().is_ok());
+    }
+    
+    #[test]
+    fn try_add_expense_normalizes_input() {
+        let app = create_test_app();
+        
+        let spaced = app.try_add_expense(AddArgs {
+            description: Some("  Coffee   to go  ".to_string()),
+            ..add_args("3.50", "Dining  ", None)
+        }).unwrap();
+        let plain = app.try_add_expense(AddArgs {
+            description: Some("Coffee to go".to_string()),
+            ..add_args("3.50", "dining", None)
+        }).unwrap();
+        
+        assert_eq!(spaced.category().name(), "Dining");
+        assert_eq!(spaced.category().name(), plain.category().name());
+        assert_eq!(spaced.description(), "Coffee to go");
+        assert_eq!(spaced.description(), plain.description());
+        
+        let app = App::new(SqliteExpenseRepository::new_in_memory().unwrap(), Config { normalize_input: false, ..Config::default() });
+        let raw = app.try_add_expense(AddArgs { description: Some("Coffee  ".to_string()), ..add_args("3.50", "Dining", None) }).unwrap();
+        assert_eq!(raw.description(), "Coffee  ");
+    }
+    
+    #[test]
+    fn read_only_rejects_writes_but_allows_reads() {
+        let app = create_test_app();
+        app.try_add_expense(add_args("12.50", "Dining", None)).unwrap();
+        let mut app = app.with_read_only(true);
+        
+        let result = app.try_add_expense(add_args("5", "Dining", None));
+        assert!(matches!(result, Err(AppError::ReadOnly(_))));
+        assert!(matches!(app.manage_trash(TrashArgs { command: TrashCommands::Empty }), Err(AppError::ReadOnly(_))));
+        let remove = CategoryArgs { command: CategoryCommands::Remove { name: "Hobbies".to_string() } };
+        assert!(matches!(app.manage_categories(remove), Err(AppError::ReadOnly(_))));
+        assert!(app.category_registry.category_exists("Hobbies"));
+        
+        assert_eq!(app.repository.get_all().unwrap().len(), 1);
+        assert!(app.manage_trash(TrashArgs { command: TrashCommands::List }).is_ok());
+    }
+    
+    #[test]
+    fn try_add_expense_rounds_to_step() {
+        let app = create_test_app();
+        
+        let expense = app.try_add_expense(AddArgs { round: Some(dec!(5.0)), ..add_args("12.40", "Dining", None) }).unwrap();
+        assert_eq!(expense.amount(), dec!(10.0));
+        assert_eq!(app.repository.get_by_id(expense.id().unwrap()).unwrap().unwrap().amount(), dec!(10.0));
+    }
+    
+    #[test]
+    fn try_add_expense_with_secondary_category() {
+        let app = create_test_app();
+        
+        let expense = app.try_add_expense(AddArgs {
+            secondary: Some("socializing".to_string()),
+            ..add_args("30", "Dining", None)
+        }).unwrap();
+        assert_eq!(expense.secondary_category(), Some("Socializing"));
+        
+        let unknown = app.try_add_expense(AddArgs {
+            secondary: Some("Yachts".to_string()),
+            ..add_args("30", "Dining", None)
+        });
+        assert!(matches!(unknown, Err(AppError::CliError(CliError::CategoryNotFound(_)))));
+    }
+    
+    #[test]
+    fn try_add_expense_rejects_invalid_input() {
+        let app = create_test_app();
+        
+        let negative = app.try_add_expense(add_args("-5", "Groceries", None));
+        assert!(matches!(negative, Err(AppError::CliError(CliError::InvalidAmount(_)))));
+        
+        let sub_cent = app.try_add_expense(add_args("10.999", "Groceries", None));
+        assert!(matches!(sub_cent, Err(AppError::CliError(CliError::InvalidAmount(_)))));
+        
+        let malformed = app.try_add_expense(add_args("12..5", "Groceries", None));
+        assert!(matches!(malformed, Err(AppError::CliError(CliError::InvalidAmount(_)))));
+        
+        let unknown = app.try_add_expense(add_args("12.50", "Yachts", None));
+        assert!(matches!(unknown, Err(AppError::CliError(CliError::CategoryNotFound(_)))));
+        
+        let bad_date = app.try_add_expense(add_args("12.50", "Groceries", Some("2025-13-01")));
+        assert!(matches!(bad_date, Err(AppError::CliError(CliError::InvalidDate(_)))));
+        
+        assert!(app.repository.get_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn bulk_edit_updates_only_matching_expenses() {
+        let app = create_test_app().with_assume_yes(true);
+        app.try_add_expense(add_args("10", "Miscellaneous", Some("2023-06-01"))).unwrap();
+        app.try_add_expense(add_args("20", "Miscellaneous", Some("2023-12-31"))).unwrap();
+        app.try_add_expense(add_args("30", "Miscellaneous", Some("2024-01-01"))).unwrap();
+        app.try_add_expense(add_args("40", "Dining", Some("2023-06-01"))).unwrap();
+        
+        app.bulk_edit(BulkEditArgs {
+            category: Some("miscellaneous".to_string()),
+            from: None,
+            to: Some("2023-12-31".to_string()),
+            set_category: Some("Household".to_string()),
+            set_description: Some("Cleanup".to_string()),
+        }).unwrap();
+        
+        let mut moved: Vec<Decimal> = app.repository.get_by_category("Household").unwrap()
+            .iter().map(|e| e.amount()).collect();
+        moved.sort();
+        assert_eq!(moved, vec![dec!(10.0), dec!(20.0)]);
+        assert!(app.repository.get_by_category("Household").unwrap().iter().all(|e| e.description() == "Cleanup"));
+        assert_eq!(app.repository.get_by_category("Miscellaneous").unwrap().len(), 1);
+        assert_eq!(app.repository.get_by_category("Dining").unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn evaluate_alerts_checks_each_month_in_rule_order() {
+        let config = Config {
+            alerts: vec![
+                AlertRule { category: Some("dining".to_string()), monthly_limit: dec!(50.0) },
+                AlertRule { category: None, monthly_limit: dec!(100.0) },
+            ],
+            ..Config::default()
+        };
+        let app = App::new(SqliteExpenseRepository::new_in_memory().unwrap(), config)
+            .with_clock(FixedClock(NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()));
+        app.try_add_expense(add_args("60", "Dining", Some("2025-03-05"))).unwrap();
+        app.try_add_expense(add_args("40", "Dining", Some("2025-04-05"))).unwrap();
+        app.try_add_expense(add_args("70", "Groceries", Some("2025-04-06"))).unwrap();
+        
+        let from = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        let alerts = app.evaluate_alerts(from, to).unwrap();
+        
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts[0].starts_with("Dining spent $ 60.00 in 2025-03"));
+        assert!(alerts[1].starts_with("Total spent $ 110.00 in 2025-04"));
+        
+        assert!(app.check_alerts(AlertsArgs { month: Some("2025-04".to_string()) }).is_err());
+        assert!(app.check_alerts(AlertsArgs { month: Some("2025-02".to_string()) }).is_ok());
+    }
+    
+    #[test]
+    fn monthly_sparkline_covers_every_month() {
+        let totals = vec![((2024, 11), dec!(40.0)), ((2025, 1), dec!(80.0)), ((2025, 2), dec!(20.0))];
+        let amounts = dense_monthly_amounts(&totals);
+        
+        assert_eq!(amounts, vec![dec!(40.0), dec!(0.0), dec!(80.0), dec!(20.0)]);
+        assert_eq!(sparkline(&amounts, dec!(80.0), &BLOCK_BARS), "▅ █▃");
+        assert_eq!(sparkline(&amounts, dec!(80.0), &ASCII_BARS), "= #:");
+        assert!(dense_monthly_amounts(&Vec::new()).is_empty());
+    }
+    
+    #[test]
+    fn locale_is_utf8_follows_precedence() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+        
+        assert!(locale_is_utf8(env(&[])));
+        assert!(locale_is_utf8(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(!locale_is_utf8(env(&[("LANG", "C")])));
+        assert!(!locale_is_utf8(env(&[("LC_ALL", "POSIX"), ("LANG", "en_US.utf8")])));
+        assert!(locale_is_utf8(env(&[("LC_ALL", ""), ("LC_CTYPE", "de_DE.UTF-8"), ("LANG", "C")])));
+    }
+    
+    #[test]
+    fn zero_runs_groups_consecutive_gaps() {
+        let series = [(1, dec!(0.0)), (2, dec!(0.0)), (3, dec!(5.0)), (4, dec!(0.0)), (5, dec!(2.0)), (6, dec!(0.0)), (7, dec!(0.0))];
+        
+        assert_eq!(zero_runs(&series), vec![(1, 2), (4, 4), (6, 7)]);
+        assert!(zero_runs(&[(1, dec!(1.0)), (2, dec!(3.0))]).is_empty());
+    }
+    
+    #[test]
+    fn distribute_percentages_sums_to_exactly_100() {
+        // Naive rounding gives 33.3 + 33.3 + 33.3 = 99.9
+        let shares = distribute_percentages(&[dec!(10.0), dec!(10.0), dec!(10.0)]);
+        assert_eq!(shares.iter().filter(|s| **s == 33.4).count(), 1);
+        assert_eq!(shares.iter().filter(|s| **s == 33.3).count(), 2);
+        
+        // Naive rounding gives 16.7 + 16.7 + 66.7 = 100.1
+        let shares = distribute_percentages(&[dec!(1.0), dec!(1.0), dec!(4.0)]);
+        assert!((shares.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+        
+        // The leftover tenth goes to the share with the largest remainder
+        assert_eq!(distribute_percentages(&[dec!(2.0), dec!(3.0), dec!(4.0)]), vec![22.2, 33.3, 44.5]);
+        
+        assert_eq!(distribute_percentages(&[dec!(0.0), dec!(0.0)]), vec![0.0, 0.0]);
+    }
+    
+    #[test]
+    fn escape_tsv_field_escapes_separators() {
+        assert_eq!(escape_tsv_field("Groceries"), "Groceries");
+        assert_eq!(escape_tsv_field("Food\tand drink"), "Food\\tand drink");
+        assert_eq!(escape_tsv_field("two\r\nlines"), "two\\r\\nlines");
+        assert_eq!(escape_tsv_field("C:\\temp"), "C:\\\\temp");
+    }
+    
+    #[test]
+    fn category_month_matrix_fills_gaps_with_zero() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let months = months_between(date("2024-12-15"), date("2025-02-10"));
+        assert_eq!(months, vec![(2024, 12), (2025, 1), (2025, 2)]);
+        
+        let totals = vec![
+            ((2024, 12), "Food".to_string(), dec!(10.1)),
+            ((2025, 2), "Food".to_string(), dec!(0.2)),
+            ((2025, 1), "Rent".to_string(), dec!(600.0)),
+        ];
+        
+        assert_eq!(category_month_matrix(&totals, &months), vec![
+            ("Rent".to_string(), vec![dec!(0), dec!(600.0), dec!(0)]),
+            ("Food".to_string(), vec![dec!(10.1), dec!(0), dec!(0.2)]),
+        ]);
+    }
+    
+    #[test]
+    fn sort_category_rows_by_each_key() {
+        let rows = vec![
+            ("Rent".to_string(), dec!(600.0)),
+            ("dining".to_string(), dec!(45.0)),
+            ("Groceries".to_string(), dec!(120.0)),
+            ("Books".to_string(), dec!(45.0)),
+        ];
+        let counts = vec![("Rent".to_string(), 1), ("dining".to_string(), 6), ("Groceries".to_string(), 4), ("Books".to_string(), 1)];
+        let names = |sort_by, order| {
+            let mut rows = rows.clone();
+            sort_category_rows(&mut rows, &counts, sort_by, order);
+            rows.into_iter().map(|(name, _)| name).collect::<Vec<_>>()
+        };
+        
+        assert_eq!(names(CategorySort::Amount, None), ["Rent", "Groceries", "Books", "dining"]);
+        assert_eq!(names(CategorySort::Amount, Some(SortOrder::Asc)), ["Books", "dining", "Groceries", "Rent"]);
+        assert_eq!(names(CategorySort::Name, None), ["Books", "dining", "Groceries", "Rent"]);
+        assert_eq!(names(CategorySort::Name, Some(SortOrder::Desc)), ["Rent", "Groceries", "dining", "Books"]);
+        // Equal counts fall back to the larger amount
+        assert_eq!(names(CategorySort::Count, None), ["dining", "Groceries", "Rent", "Books"]);
+    }
+    
+    #[test]
+    fn compare_amounts_desc_puts_largest_first() {
+        let mut amounts = [dec!(10.0), dec!(0.01), dec!(250.0), dec!(0.0)];
+        amounts.sort_by(|a, b| compare_amounts_desc(*a, *b));
+        
+        assert_eq!(amounts, [dec!(250.0), dec!(10.0), dec!(0.01), dec!(0.0)]);
+    }
+    
+    #[test]
+    fn exit_codes_by_error_kind() {
+        assert_eq!(AppError::from(CliError::InvalidAmount("abc".to_string())).exit_code(), EXIT_INVALID_INPUT);
+        assert_eq!(AppError::from(CliError::CategoryNotFound("Yachts".to_string())).exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(AppError::from(RepositoryError::NotFound("Expense with id 7".to_string())).exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(AppError::from(RepositoryError::Other("database is locked".to_string())).exit_code(), EXIT_DATABASE);
+        assert_eq!(AppError::from(crate::config::ConfigError::IoError(io::ErrorKind::NotFound.into())).exit_code(), EXIT_CONFIG);
+        assert_eq!(AppError::Other("2 alert(s) triggered".to_string()).exit_code(), EXIT_FAILURE);
+        
+        // Context keeps the code of the underlying error
+        let wrapped = AppError::from(RepositoryError::Conflict("Expense 3".to_string())).context("Failed to edit expense");
+        assert_eq!(wrapped.exit_code(), EXIT_DATABASE);
+    }
+    
+    #[test]
+    fn error_context_keeps_display_and_source_chain() {
+        use std::error::Error;
+        
+        let error = AppError::from(RepositoryError::NotFound("Expense with id 7".to_string()));
+        assert_eq!(error.to_string(), "Repository error: Entity not found: Expense with id 7");
+        assert!(error.source().is_some());
+        
+        let wrapped = error.context("Failed to restore expense");
+        assert_eq!(wrapped.to_string(), "Failed to restore expense: Repository error: Entity not found: Expense with id 7");
+        let source = wrapped.source().unwrap();
+        assert!(source.source().is_some());
+    }
+    
+    #[test]
+    fn category_names_are_sorted_for_completion() {
+        let mut app = create_test_app();
+        app.category_registry.add_category("Books", None).unwrap();
+        app.category_registry.add_category("zoo", None).unwrap();
+        
+        let names = app.category_names();
+        assert_eq!(names.len(), Config::default().categories.len() + 2);
+        assert_eq!(names.first().map(String::as_str), Some("Books"));
+        assert_eq!(names.last().map(String::as_str), Some("zoo"));
+        assert!(names.windows(2).all(|pair| pair[0].to_lowercase() <= pair[1].to_lowercase()));
+    }
 }
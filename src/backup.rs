@@ -0,0 +1,365 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::result::ZipError;
+
+use crate::cli::BackupFormat;
+use crate::config::Config;
+use crate::models::category::Category;
+use crate::models::expense::{self, Expense};
+
+/// Magic bytes identifying a binary backup file
+const BINARY_MAGIC: &[u8; 4] = b"EXLG";
+
+/// Version of the binary backup layout, bumped whenever `Expense` changes shape; older
+/// layouts stay readable through the `ExpenseV*` structs below
+const BINARY_VERSION: u32 = 3;
+
+/// Expense as written by version 1 binary backups, before secondary categories and accounts
+#[derive(Deserialize)]
+struct ExpenseV1 {
+    id: Option<i64>,
+    #[serde(with = "expense::amount_serde")]
+    amount: Decimal,
+    category: Category,
+    date: NaiveDate,
+    description: String,
+    currency: Option<String>,
+}
+
+/// Expense as written by version 2 binary backups, before accounts. Bincode lays out
+/// the nested version 1 fields exactly as if they were declared inline
+#[derive(Deserialize)]
+struct ExpenseV2 {
+    base: ExpenseV1,
+    secondary_category: Option<String>,
+}
+
+impl From<ExpenseV1> for Expense {
+    fn from(old: ExpenseV1) -> Self {
+        let mut expense = Expense::new(old.amount, old.category, old.date, old.description);
+        if let Some(id) = old.id {
+            expense.set_id(id);
+        }
+        expense.set_currency(old.currency);
+        expense
+    }
+}
+
+impl From<ExpenseV2> for Expense {
+    fn from(old: ExpenseV2) -> Self {
+        let mut expense = Expense::from(old.base);
+        expense.set_secondary_category(old.secondary_category);
+        expense
+    }
+}
+
+/// Name of the JSON expense dump inside a zip archive
+const ARCHIVE_EXPENSES: &str = "expenses.json";
+
+/// Name of the config file inside a zip archive
+const ARCHIVE_CONFIG: &str = "config.yaml";
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BackupError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    
+    #[error("Bincode error: {0}")]
+    BincodeError(#[from] bincode::Error),
+    
+    #[error("Zip error: {0}")]
+    ZipError(#[from] ZipError),
+    
+    #[error("YAML error: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+    
+    #[error("Invalid backup file: {0}")]
+    InvalidBackup(String),
+}
+
+/// Write expenses to a backup file in the given format
+pub fn write_backup(path: &Path, expenses: &[Expense], format: BackupFormat) -> Result<(), BackupError> {
+    let content = match format {
+        BackupFormat::Json => serde_json::to_vec_pretty(expenses)?,
+        BackupFormat::Bincode => {
+            let mut content = BINARY_MAGIC.to_vec();
+            content.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+            content.extend(bincode::serialize(expenses)?);
+            content
+        }
+        BackupFormat::Zip => archive_content(expenses, None)?,
+    };
+    
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Write expenses and the resolved config to a single zip archive, for moving
+/// a whole expense log to another machine
+pub fn write_archive(path: &Path, expenses: &[Expense], config: &Config) -> Result<(), BackupError> {
+    fs::write(path, archive_content(expenses, Some(config))?)?;
+    Ok(())
+}
+
+fn archive_content(expenses: &[Expense], config: Option<&Config>) -> Result<Vec<u8>, BackupError> {
+    let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    
+    writer.start_file(ARCHIVE_EXPENSES, options)?;
+    writer.write_all(&serde_json::to_vec_pretty(expenses)?)?;
+    
+    if let Some(config) = config {
+        writer.start_file(ARCHIVE_CONFIG, options)?;
+        writer.write_all(serde_yaml::to_string(config)?.as_bytes())?;
+    }
+    
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Read expenses and, if present, the config from a zip archive
+pub fn read_archive(path: &Path) -> Result<(Vec<Expense>, Option<Config>), BackupError> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    
+    let expenses = match archive.by_name(ARCHIVE_EXPENSES) {
+        Ok(file) => serde_json::from_reader(file)?,
+        Err(ZipError::FileNotFound) => {
+            return Err(BackupError::InvalidBackup(format!("archive has no {}", ARCHIVE_EXPENSES)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    
+    let config = match archive.by_name(ARCHIVE_CONFIG) {
+        Ok(mut file) => {
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            Some(serde_yaml::from_str(&content)?)
+        }
+        Err(ZipError::FileNotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+    
+    Ok((expenses, config))
+}
+
+/// Read expenses from a backup file in the given format
+pub fn read_backup(path: &Path, format: BackupFormat) -> Result<Vec<Expense>, BackupError> {
+    let content = fs::read(path)?;
+    
+    match format {
+        BackupFormat::Json => Ok(serde_json::from_slice(&content)?),
+        BackupFormat::Bincode => {
+            let header_len = BINARY_MAGIC.len() + 4;
+            if content.len() < header_len || &content[..BINARY_MAGIC.len()] != BINARY_MAGIC {
+                return Err(BackupError::InvalidBackup("missing binary backup header".to_string()));
+            }
+            
+            let version = u32::from_le_bytes(content[BINARY_MAGIC.len()..header_len].try_into().unwrap());
+            let body = &content[header_len..];
+            
+            // Older layouts are upgraded on read, leaving the new fields empty
+            match version {
+                1 => Ok(bincode::deserialize::<Vec<ExpenseV1>>(body)?.into_iter().map(Expense::from).collect()),
+                2 => Ok(bincode::deserialize::<Vec<ExpenseV2>>(body)?.into_iter().map(Expense::from).collect()),
+                BINARY_VERSION => Ok(bincode::deserialize(body)?),
+                _ => Err(BackupError::InvalidBackup(format!("unsupported backup version {}", version))),
+            }
+        }
+        BackupFormat::Zip => Ok(read_archive(path)?.0),
+    }
+}
+
+/// Path of the `n`th rolling backup of a database: `<database>.bak.<n>`, 1 being the newest
+pub fn rolling_backup_path(database: &Path, n: usize) -> PathBuf {
+    let mut path = database.as_os_str().to_owned();
+    path.push(format!(".bak.{}", n));
+    PathBuf::from(path)
+}
+
+/// Copy the database file to `<database>.bak.1`, shifting older copies up one number
+/// and removing those beyond `retention`. Does nothing when the database doesn't exist
+/// yet or `retention` is zero; returns the path of the new copy otherwise.
+pub fn rotate_database_backup(database: &Path, retention: usize) -> Result<Option<PathBuf>, BackupError> {
+    if retention == 0 || !database.exists() {
+        return Ok(None);
+    }
+    
+    // Drop copies past the retention limit, including any left over from a larger limit
+    let mut n = retention;
+    while rolling_backup_path(database, n).exists() {
+        fs::remove_file(rolling_backup_path(database, n))?;
+        n += 1;
+    }
+    
+    for n in (1..retention).rev() {
+        let older = rolling_backup_path(database, n);
+        if older.exists() {
+            fs::rename(&older, rolling_backup_path(database, n + 1))?;
+        }
+    }
+    
+    let newest = rolling_backup_path(database, 1);
+    fs::copy(database, &newest)?;
+    Ok(Some(newest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+    use rust_decimal_macros::dec;
+
+    fn sample_expenses() -> Vec<Expense> {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let mut subscription = Expense::new(
+            dec!(9.99),
+            Category::new("Subscriptions", None).unwrap(),
+            date,
+            "Streaming".to_string(),
+        ).with_id(2);
+        subscription.set_currency(Some("USD".to_string()));
+        
+        vec![
+            Expense::new(
+                dec!(42.50),
+                Category::new("Groceries", Some("Food and household items")).unwrap(),
+                date,
+                "Weekly shopping trip".to_string(),
+            ).with_id(1),
+            subscription,
+        ]
+    }
+    
+    #[test]
+    fn roundtrip_json_backup() {
+        let file = NamedTempFile::new().unwrap();
+        let expenses = sample_expenses();
+        
+        write_backup(file.path(), &expenses, BackupFormat::Json).unwrap();
+        let restored = read_backup(file.path(), BackupFormat::Json).unwrap();
+        
+        assert_eq!(restored, expenses);
+    }
+    
+    #[test]
+    fn roundtrip_bincode_backup() {
+        let file = NamedTempFile::new().unwrap();
+        let expenses = sample_expenses();
+        
+        write_backup(file.path(), &expenses, BackupFormat::Bincode).unwrap();
+        let restored = read_backup(file.path(), BackupFormat::Bincode).unwrap();
+        
+        assert_eq!(restored, expenses);
+    }
+    
+    #[test]
+    fn roundtrip_zip_archive() {
+        let file = NamedTempFile::new().unwrap();
+        let expenses = sample_expenses();
+        let config = Config {
+            currency_symbol: "kr".to_string(),
+            ..Config::default()
+        };
+        
+        write_archive(file.path(), &expenses, &config).unwrap();
+        let (restored, restored_config) = read_archive(file.path()).unwrap();
+        
+        assert_eq!(restored, expenses);
+        let restored_config = restored_config.unwrap();
+        assert_eq!(restored_config.currency_symbol, "kr");
+        assert_eq!(restored_config.categories.len(), config.categories.len());
+        
+        // A zip backup without a config reads back the same expenses
+        write_backup(file.path(), &expenses, BackupFormat::Zip).unwrap();
+        assert_eq!(read_backup(file.path(), BackupFormat::Zip).unwrap(), expenses);
+        assert!(read_archive(file.path()).unwrap().1.is_none());
+    }
+    
+    #[test]
+    fn restore_older_bincode_versions() {
+        let file = NamedTempFile::new().unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let category = Category::new("Subscriptions", None).unwrap();
+        
+        // Fields in the order older `Expense` layouts declared them
+        let v1 = vec![(Some(2i64), 9.99f64, category.clone(), date, "Streaming".to_string(), Some("USD".to_string()))];
+        let v2 = vec![(Some(2i64), 9.99f64, category.clone(), date, "Streaming".to_string(), Some("USD".to_string()),
+            Some("Entertainment".to_string()))];
+        
+        let mut expected = Expense::new(dec!(9.99), category, date, "Streaming".to_string()).with_id(2);
+        expected.set_currency(Some("USD".to_string()));
+        
+        for (version, body) in [(1u32, bincode::serialize(&v1).unwrap()), (2, bincode::serialize(&v2).unwrap())] {
+            let mut content = BINARY_MAGIC.to_vec();
+            content.extend_from_slice(&version.to_le_bytes());
+            content.extend(body);
+            fs::write(file.path(), content).unwrap();
+            
+            if version == 2 {
+                expected.set_secondary_category(Some("Entertainment".to_string()));
+            }
+            assert_eq!(read_backup(file.path(), BackupFormat::Bincode).unwrap(), vec![expected.clone()]);
+        }
+        
+        // Restored expenses write back as the current version
+        write_backup(file.path(), &[expected.clone()], BackupFormat::Bincode).unwrap();
+        assert_eq!(read_backup(file.path(), BackupFormat::Bincode).unwrap(), vec![expected]);
+    }
+    
+    #[test]
+    fn reject_bincode_without_header() {
+        let file = NamedTempFile::new().unwrap();
+        write_backup(file.path(), &sample_expenses(), BackupFormat::Json).unwrap();
+        
+        let result = read_backup(file.path(), BackupFormat::Bincode);
+        assert!(matches!(result, Err(BackupError::InvalidBackup(_))));
+    }
+    
+    #[test]
+    fn rolling_backup_copies_database_and_prunes_old_copies() {
+        use crate::repository::{ExpenseRepository, SqliteExpenseRepository};
+        
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("expenses.db");
+        
+        // No database yet, nothing to back up
+        assert!(rotate_database_backup(&database, 2).unwrap().is_none());
+        
+        let repository = SqliteExpenseRepository::new(database.to_str().unwrap()).unwrap();
+        let new_expense = || Expense::new(
+            dec!(12.0),
+            Category::new("Groceries", None).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 4, 11).unwrap(),
+            "Milk".to_string(),
+        );
+        repository.save(&mut new_expense()).unwrap();
+        
+        let backup = rotate_database_backup(&database, 2).unwrap().unwrap();
+        assert_eq!(backup, dir.path().join("expenses.db.bak.1"));
+        let copy = SqliteExpenseRepository::new(backup.to_str().unwrap()).unwrap();
+        assert_eq!(copy.get_all().unwrap().len(), 1);
+        drop(copy);
+        
+        repository.save(&mut new_expense()).unwrap();
+        rotate_database_backup(&database, 2).unwrap();
+        rotate_database_backup(&database, 2).unwrap();
+        
+        assert!(rolling_backup_path(&database, 1).exists());
+        assert!(rolling_backup_path(&database, 2).exists());
+        assert!(!rolling_backup_path(&database, 3).exists());
+        
+        // Lowering the retention removes the extra copies
+        rotate_database_backup(&database, 1).unwrap();
+        assert!(rolling_backup_path(&database, 1).exists());
+        assert!(!rolling_backup_path(&database, 2).exists());
+    }
+}
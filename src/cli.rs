@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand, Args};
+use clap::{Parser, Subcommand, Args, ArgGroup, ValueEnum};
+use rust_decimal::Decimal;
 use std::path::PathBuf;
 use crate::models::category::CategoryRegistry;
 
@@ -6,11 +7,38 @@ use crate::models::category::CategoryRegistry;
 #[command(name = "expense_log")]
 #[command(about = "A simple CLI tool for tracking non-recurring expenses")]
 #[command(version)]
+#[command(after_help = "Exit codes:
+  0  success
+  1  failure, such as exceeded alerts or problems found by doctor
+  2  invalid arguments or input, or a change refused in read-only mode
+  3  category or expense not found
+  4  database error, such as a locked database or an edit conflicting with another one
+  5  config file error")]
 pub struct Cli {
     /// Path to the config file
     #[arg(short, long, default_value = "expense_log.yaml")]
     pub config: PathBuf,
     
+    /// Format of the config file, instead of guessing from its extension
+    #[arg(long, global = true, value_enum)]
+    pub config_format: Option<ConfigFormat>,
+    
+    /// Print each SQL statement and its bound parameters to stderr before it runs
+    #[arg(long, global = true)]
+    pub explain: bool,
+    
+    /// Answer yes to all confirmation prompts
+    #[arg(short = 'y', long, global = true)]
+    pub assume_yes: bool,
+    
+    /// Open the database read-only and refuse commands that would change anything
+    #[arg(long, global = true)]
+    pub read_only: bool,
+    
+    /// Leave out reminders, such as the one about recurring expenses that are due
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -20,20 +48,114 @@ pub enum Commands {
     /// Add a new expense
     Add(AddArgs),
     
+    /// Quickly add an expense from a single "AMOUNT CATEGORY [DESCRIPTION]" string
+    Quick(QuickArgs),
+    
+    /// Change the amount, category, date or description of an expense
+    Edit(EditArgs),
+    
+    /// Move an expense to the trash
+    Delete(DeleteArgs),
+    
     /// List expenses with optional filtering
     List(ListArgs),
     
+    /// Show the most recent expenses, newest first
+    Tail(TailArgs),
+    
     /// Show summary and statistics
     Summary(SummaryArgs),
     
+    /// Print only the total spent, for use in scripts
+    Total(TotalArgs),
+    
     /// Manage expense categories
     Category(CategoryArgs),
+    
+    /// Show the recorded change history of an expense
+    History(HistoryArgs),
+    
+    /// Back up all expenses to a file
+    Backup(BackupArgs),
+    
+    /// Restore expenses from a backup file
+    Restore(RestoreArgs),
+    
+    /// Export expenses to a CSV file
+    Export(ExportArgs),
+    
+    /// Import expenses from a file
+    Import(ImportArgs),
+    
+    /// Change the category or description of every expense matching a filter
+    BulkEdit(BulkEditArgs),
+    
+    /// Project this month's spending from the run rate so far
+    Forecast,
+    
+    /// View, restore or permanently remove deleted expenses
+    Trash(TrashArgs),
+    
+    /// Show statistics over all recorded expenses
+    Stats(StatsArgs),
+    
+    /// Check spending against the `alerts` limits in the config; fails if any are exceeded
+    Alerts(AlertsArgs),
+    
+    /// Set, remove or list monthly budgets per category
+    Budget(BudgetArgs),
+    
+    /// Manage expenses that repeat every month, such as rent and subscriptions
+    Recurring(RecurringArgs),
+    
+    /// Maintain the config file
+    Config(ConfigArgs),
+    
+    /// Maintain the cached monthly totals used for month-aligned summaries
+    Rollup(RollupArgs),
+    
+    /// Check the database for problems such as future-dated expenses
+    Doctor,
+    
+    /// Print the JSON Schema of the crate's JSON output
+    #[command(hide = true)]
+    Schema,
+    
+    /// Print every category name, one per line, for shell completion scripts
+    //
+    // Category names are user data, so completion has to ask at runtime. In bash:
+    //
+    //   _expense_log_categories() {
+    //       COMPREPLY=($(compgen -W "$(expense_log __complete-categories)" -- "${COMP_WORDS[COMP_CWORD]}"))
+    //   }
+    //   complete -F _expense_log_categories expense_log
+    //
+    // and in zsh, from a completion function:
+    //
+    //   local -a categories=(${(f)"$(expense_log __complete-categories)"})
+    //   _describe 'category' categories
+    #[command(name = "__complete-categories", hide = true)]
+    CompleteCategories,
+}
+
+impl Commands {
+    /// Whether the command can change the expense database
+    pub fn writes_database(&self) -> bool {
+        match self {
+            Commands::Add(_) | Commands::Quick(_) | Commands::Edit(_) | Commands::Delete(_) | Commands::Restore(_) | Commands::BulkEdit(_)
+                | Commands::Rollup(_) => true,
+            Commands::Import(args) => !args.dry_run,
+            Commands::Trash(args) => !matches!(args.command, TrashCommands::List),
+            Commands::Recurring(args) => !matches!(args.command, RecurringCommands::List),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Args, Clone)]
 pub struct AddArgs {
-    /// Amount spent
-    pub amount: f64,
+    /// Amount spent; simple arithmetic such as "12.50+3.20" is evaluated
+    pub amount: String,
     
     /// Expense category
     pub category: String,
@@ -45,44 +167,542 @@ pub struct AddArgs {
     /// Description of the expense
     #[arg(short, long)]
     pub description: Option<String>,
+    
+    /// Currency of the amount (defaults to the category's currency, then the base currency)
+    #[arg(long)]
+    pub currency: Option<String>,
+    
+    /// A related category; the amount still counts only towards the main category
+    #[arg(long)]
+    pub secondary: Option<String>,
+    
+    /// Round the amount to the nearest multiple of this step before saving, e.g. 1 or 5
+    #[arg(long, value_name = "STEP")]
+    pub round: Option<Decimal>,
+    
+    /// Account or source the money came from
+    #[arg(long)]
+    pub account: Option<String>,
+    
+    /// Do nothing if an expense with the same amount, category, date and description
+    /// is already recorded, so the command can safely be repeated
+    #[arg(long)]
+    pub if_not_exists: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(group(ArgGroup::new("changes").required(true).multiple(true).args(["amount", "category", "date", "description"])))]
+pub struct EditArgs {
+    /// Expense ID
+    pub id: i64,
+    
+    /// New amount; simple arithmetic such as "12.50+3.20" is evaluated
+    #[arg(long)]
+    pub amount: Option<String>,
+    
+    /// New category
+    #[arg(short, long)]
+    pub category: Option<String>,
+    
+    /// New date (YYYY-MM-DD format)
+    #[arg(short = 't', long)]
+    pub date: Option<String>,
+    
+    /// New description
+    #[arg(short, long)]
+    pub description: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct DeleteArgs {
+    /// Expense ID
+    pub id: i64,
+    
+    /// Delete without showing the expense and asking for confirmation
+    #[arg(long)]
+    pub yes: bool,
+    
+    /// Remove the expense for good instead of moving it to the trash
+    #[arg(long)]
+    pub permanent: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct QuickArgs {
+    /// Expense in the form "AMOUNT CATEGORY [DESCRIPTION]", dated today
+    pub input: String,
 }
 
 #[derive(Args, Clone)]
 pub struct ListArgs {
-    /// Filter by category
+    /// Filter by category; accepts a comma-separated list, or `*` for all
     #[arg(short, long)]
     pub category: Option<String>,
     
-    /// Start date (YYYY-MM-DD format)
+    /// Start date (YYYY-MM-DD; a YYYY-MM or YYYY starts at its first day)
     #[arg(long)]
     pub from: Option<String>,
     
-    /// End date (YYYY-MM-DD format)
+    /// End date (YYYY-MM-DD; a YYYY-MM or YYYY ends at its last day)
     #[arg(long)]
     pub to: Option<String>,
     
+    /// Same as `--from`, for those used to `git log`
+    #[arg(long, hide = true, conflicts_with = "from")]
+    pub since: Option<String>,
+    
+    /// Same as `--to`, for those used to `git log`
+    #[arg(long, hide = true, conflicts_with = "to")]
+    pub until: Option<String>,
+    
+    /// Only show a single month (YYYY-MM format)
+    #[arg(long, conflicts_with_all = ["from", "to", "since", "until"])]
+    pub month: Option<String>,
+    
+    /// Only show expenses whose whole description matches a case-sensitive glob
+    /// (`*` for any run of characters, `?` for one), e.g. `coffee*` or `*uber*`
+    #[arg(long, value_name = "PATTERN", conflicts_with_all = ["category", "month", "from", "to", "since", "until"])]
+    pub description_glob: Option<String>,
+    
+    /// Only show expenses recorded against this account
+    #[arg(long, conflicts_with_all = ["category", "month", "from", "to", "since", "until", "description_glob"])]
+    pub account: Option<String>,
+    
     /// Limit number of results
     #[arg(short, long)]
     pub limit: Option<usize>,
+    
+    /// Show only this page of the results, counting from 1
+    #[arg(long, conflicts_with = "limit", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub page: Option<usize>,
+    
+    /// Number of expenses per page
+    #[arg(long, default_value_t = 50, requires = "page", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub page_size: usize,
+    
+    /// Keep running and print expenses as they are added; stop with Ctrl-C
+    #[arg(short, long, conflicts_with = "format")]
+    pub watch: bool,
+    
+    /// Seconds between checks for new expenses in watch mode
+    #[arg(long, default_value_t = 2, requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: u64,
+    
+    /// Output format; `json` and `csv` leave out the total so the output can be parsed
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Table followed by the total
+    Table,
+    
+    /// JSON array of expenses
+    Json,
+    
+    /// CSV with the columns id, date, category, amount and description
+    Csv,
+}
+
+#[derive(Args, Clone)]
+pub struct TailArgs {
+    /// Number of expenses to show
+    #[arg(default_value_t = 10)]
+    pub count: usize,
 }
 
 #[derive(Args, Clone)]
 pub struct SummaryArgs {
-    /// Start date (YYYY-MM-DD format)
+    /// Start date (YYYY-MM-DD; a YYYY-MM or YYYY starts at its first day)
     #[arg(long)]
     pub from: Option<String>,
     
-    /// End date (YYYY-MM-DD format)
+    /// End date (YYYY-MM-DD; a YYYY-MM or YYYY ends at its last day)
     #[arg(long)]
     pub to: Option<String>,
     
+    /// Same as `--from`, for those used to `git log`
+    #[arg(long, hide = true, conflicts_with = "from")]
+    pub since: Option<String>,
+    
+    /// Same as `--to`, for those used to `git log`
+    #[arg(long, hide = true, conflicts_with = "to")]
+    pub until: Option<String>,
+    
+    /// Summarize a single month (YYYY-MM format)
+    #[arg(long, conflicts_with_all = ["from", "to", "since", "until"])]
+    pub month: Option<String>,
+    
+    /// Summarize a calendar year (YYYY)
+    #[arg(long, conflicts_with_all = ["from", "to", "since", "until", "month"])]
+    pub year: Option<String>,
+    
+    /// Summarize a fiscal year (YYYY), starting in `fiscal_year_start_month` of that year
+    #[arg(long, conflicts_with_all = ["from", "to", "since", "until", "month", "year"])]
+    pub fiscal_year: Option<String>,
+    
+    /// Compare category totals with another period: `prev` for the period just before,
+    /// a single YYYY-MM or YYYY, or FROM..TO
+    #[arg(long)]
+    pub compare: Option<String>,
+    
     /// Group by category
     #[arg(long)]
     pub by_category: bool,
     
+    /// Add the number of expenses and the average expense to the category breakdown
+    #[arg(long)]
+    pub detailed: bool,
+    
+    /// Add the smallest, median and largest expense per category to the category breakdown
+    #[arg(long, requires = "by_category")]
+    pub stats: bool,
+    
     /// Group by month
     #[arg(long)]
     pub by_month: bool,
+    
+    /// Show the descriptions with the highest totals
+    #[arg(long)]
+    pub by_description: bool,
+    
+    /// Show totals by secondary category
+    #[arg(long)]
+    pub by_secondary: bool,
+    
+    /// Show totals by account
+    #[arg(long)]
+    pub by_account: bool,
+    
+    /// Show spending per day of the month (1-31), summed across the months in the range
+    #[arg(long)]
+    pub by_day_of_month: bool,
+    
+    /// Group descriptions case-insensitively, ignoring surrounding whitespace
+    #[arg(long, requires = "by_description")]
+    pub normalize: bool,
+    
+    /// Only show the most recent N months in the by-month section
+    #[arg(long, value_name = "N")]
+    pub last: Option<usize>,
+    
+    /// Show daily spending as a sparkline
+    #[arg(long)]
+    pub histogram: bool,
+    
+    /// List months in the range with no recorded expenses
+    #[arg(long)]
+    pub gaps: bool,
+    
+    /// With --gaps, list days instead of months
+    #[arg(long, requires = "gaps")]
+    pub by_day: bool,
+    
+    /// Show category totals as a percentage of income (see `income_category` in the config)
+    #[arg(long)]
+    pub percent_of_income: bool,
+    
+    /// Count partly covered first and last months by the fraction of their days in the
+    /// range when computing monthly averages, instead of as whole months
+    #[arg(long)]
+    pub weighted_averages: bool,
+    
+    /// Print a table of categories by month with row and column totals, as CSV or TSV with
+    /// `--format`. Every month in the range gets a column, with 0 where nothing was spent
+    #[arg(long, conflicts_with_all = [
+        "compare", "by_category", "by_month", "by_description", "by_secondary", "by_account",
+        "by_day_of_month", "histogram", "gaps", "percent_of_income",
+    ])]
+    pub matrix: bool,
+    
+    /// Only print the grand total, preceded by each category's total with --by-category
+    #[arg(long, conflicts_with_all = [
+        "compare", "by_month", "by_description", "by_secondary", "by_account", "by_day_of_month",
+        "histogram", "gaps", "percent_of_income", "weighted_averages", "detailed",
+        "stats", "matrix",
+    ])]
+    pub total_only: bool,
+    
+    /// Leave out the total lines under the category and month tables
+    #[arg(long, conflicts_with = "total_only")]
+    pub no_total: bool,
+    
+    /// Order of the categories in the by-category and monthly averages sections
+    #[arg(long, value_enum, default_value_t = CategorySort::Amount)]
+    pub sort_by: CategorySort,
+    
+    /// Sort direction; defaults to ascending for names and descending for amounts and counts
+    #[arg(long, value_enum)]
+    pub sort_order: Option<SortOrder>,
+    
+    /// Output format
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Text)]
+    pub format: SummaryFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CategorySort {
+    /// Alphabetically by category name, for stable positions across reports
+    Name,
+    
+    /// By amount spent
+    Amount,
+    
+    /// By number of expenses
+    Count,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Args, Clone)]
+pub struct TotalArgs {
+    /// Only count this category (defaults to all categories)
+    #[arg(short, long)]
+    pub category: Option<String>,
+    
+    /// Start date (YYYY-MM-DD; a YYYY-MM or YYYY starts at its first day)
+    #[arg(long)]
+    pub from: Option<String>,
+    
+    /// End date (YYYY-MM-DD; a YYYY-MM or YYYY ends at its last day)
+    #[arg(long)]
+    pub to: Option<String>,
+    
+    /// Include the currency symbol in the output
+    #[arg(long)]
+    pub pretty: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// Human-readable report
+    Text,
+    
+    /// Category breakdown as CSV (category,total,percentage)
+    Csv,
+    
+    /// Category breakdown as tab-separated values, with the same columns as CSV.
+    /// Tabs, newlines and backslashes in fields are escaped as `\t`, `\n` and `\\`
+    Tsv,
+    
+    /// Self-contained HTML report, suitable for email
+    Html,
+}
+
+#[derive(Args, Clone)]
+pub struct StatsArgs {
+    /// Print the statistics as JSON
+    #[arg(long)]
+    pub json: bool,
+    
+    /// Draw the monthly trend with ASCII characters instead of Unicode blocks
+    #[arg(long)]
+    pub ascii: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct AlertsArgs {
+    /// Month to check (YYYY-MM format); defaults to the current month
+    #[arg(long)]
+    pub month: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct HistoryArgs {
+    /// Expense ID
+    pub id: i64,
+}
+
+#[derive(Args, Clone)]
+pub struct BackupArgs {
+    /// Path of the backup file to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+    
+    /// Backup format
+    #[arg(long, value_enum, default_value_t = BackupFormat::Json)]
+    pub format: BackupFormat,
+}
+
+#[derive(Args, Clone)]
+pub struct ExportArgs {
+    /// Path of the CSV file to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+    
+    /// Start date (YYYY-MM-DD; a YYYY-MM or YYYY starts at its first day); no lower bound if omitted
+    #[arg(long)]
+    pub from: Option<String>,
+    
+    /// End date (YYYY-MM-DD; a YYYY-MM or YYYY ends at its last day)
+    #[arg(long)]
+    pub to: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct RestoreArgs {
+    /// Path of the backup file to read
+    #[arg(short, long)]
+    pub input: PathBuf,
+    
+    /// Backup format
+    #[arg(long, value_enum, default_value_t = BackupFormat::Json)]
+    pub format: BackupFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupFormat {
+    /// Human-readable JSON
+    Json,
+    
+    /// Compact binary format for fast machine round-trips
+    Bincode,
+    
+    /// Zip archive holding a JSON dump and the config, for moving to another machine
+    Zip,
+}
+
+#[derive(Args, Clone)]
+pub struct ImportArgs {
+    /// Path of the file to import
+    #[arg(short, long)]
+    pub input: PathBuf,
+    
+    /// Format of the input file
+    #[arg(long, value_enum, default_value_t = ImportFormat::Json)]
+    pub format: ImportFormat,
+    
+    /// CSV column mapping, e.g. "date=1,amount=3,category=none,description=4",
+    /// or the name of a mapping under `csv_mappings` in the config
+    #[arg(long)]
+    pub map: Option<String>,
+    
+    /// Date format of the CSV date column (chrono syntax)
+    #[arg(long, default_value = "%Y-%m-%d")]
+    pub date_format: String,
+    
+    /// The CSV file has no header row
+    #[arg(long)]
+    pub no_header: bool,
+    
+    /// Category for CSV rows without a mapped category column
+    #[arg(long, default_value = "Miscellaneous")]
+    pub default_category: String,
+    
+    /// Record every imported expense against this account, replacing any account in the file
+    #[arg(long)]
+    pub account: Option<String>,
+    
+    /// Register categories the registry doesn't know yet, saving them to the config
+    #[arg(long)]
+    pub create_missing_categories: bool,
+    
+    /// Record expenses in categories the registry doesn't know under this category instead
+    #[arg(long, value_name = "CATEGORY", conflicts_with = "create_missing_categories")]
+    pub map_unknown_to: Option<String>,
+    
+    /// Skip records that fail validation instead of aborting the whole import
+    #[arg(long)]
+    pub skip_invalid: bool,
+    
+    /// Import records that match an existing expense or an earlier record in the file
+    #[arg(long)]
+    pub allow_duplicates: bool,
+    
+    /// Validate every record and report the problems without importing anything
+    #[arg(long, visible_alias = "check")]
+    pub dry_run: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// JSON array of expenses, as written by the JSON export
+    Json,
+    
+    /// CSV file, such as a bank export; see `--map`
+    Csv,
+}
+
+#[derive(Args, Clone)]
+#[command(group(ArgGroup::new("filter").required(true).multiple(true).args(["category", "from", "to"])))]
+#[command(group(ArgGroup::new("change").required(true).multiple(true).args(["set_category", "set_description"])))]
+pub struct BulkEditArgs {
+    /// Only edit expenses in this category
+    #[arg(short, long)]
+    pub category: Option<String>,
+    
+    /// Start date (YYYY-MM-DD; a YYYY-MM or YYYY starts at its first day)
+    #[arg(long)]
+    pub from: Option<String>,
+    
+    /// End date (YYYY-MM-DD; a YYYY-MM or YYYY ends at its last day)
+    #[arg(long)]
+    pub to: Option<String>,
+    
+    /// Move the matching expenses to this category
+    #[arg(long, value_name = "CATEGORY")]
+    pub set_category: Option<String>,
+    
+    /// Replace the description of the matching expenses
+    #[arg(long, value_name = "DESCRIPTION")]
+    pub set_description: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub command: TrashCommands,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum TrashCommands {
+    /// List deleted expenses
+    List,
+    
+    /// Restore a deleted expense
+    Restore {
+        /// Expense ID
+        id: i64,
+    },
+    
+    /// Permanently remove all deleted expenses
+    Empty,
+}
+
+#[derive(Args, Clone)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ConfigCommands {
+    /// Rewrite the config file with every field set, filling in defaults for new fields
+    Migrate,
+}
+
+#[derive(Args, Clone)]
+pub struct RollupArgs {
+    #[command(subcommand)]
+    pub command: RollupCommands,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum RollupCommands {
+    /// Recompute the per-month, per-category totals and keep them updated from now on
+    Rebuild,
 }
 
 #[derive(Args, Clone)]
@@ -111,15 +731,105 @@ pub enum CategoryCommands {
         /// Category name
         name: String,
     },
+    
+    /// Remove categories that no expense uses. The built-in categories are kept unless
+    /// `use_system_categories` is off, the fallback category always
+    Prune {
+        /// Only list the categories that would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Add categories from a YAML list of `{name, description}` entries
+    Import {
+        /// Path of the YAML file
+        file: PathBuf,
+    },
+}
+
+#[derive(Args, Clone)]
+pub struct BudgetArgs {
+    #[command(subcommand)]
+    pub command: BudgetCommands,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum BudgetCommands {
+    /// List the monthly budgets
+    List,
+    
+    /// Show this month's spending so far against each budget
+    Status {
+        /// Hold spending against the budget scaled to the days elapsed in the month,
+        /// showing the full-month budget alongside
+        #[arg(long)]
+        prorate: bool,
+    },
+    
+    /// Set the monthly budget of a category
+    Set {
+        /// Category name
+        category: String,
+        
+        /// Most to spend in the category per month
+        amount: Decimal,
+    },
+    
+    /// Remove the budget of a category
+    Remove {
+        /// Category name
+        category: String,
+    },
+}
+
+#[derive(Args, Clone)]
+pub struct RecurringArgs {
+    #[command(subcommand)]
+    pub command: RecurringCommands,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum RecurringCommands {
+    /// List the recurring expenses
+    List,
+    
+    /// Add an expense that repeats every month
+    Add {
+        /// Amount spent each month
+        amount: String,
+        
+        /// Expense category
+        category: String,
+        
+        /// Day of the month the expense falls on; shorter months use their last day
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=31))]
+        day: u32,
+        
+        /// Description of the expense
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    
+    /// Remove a recurring expense; expenses already created from it are kept
+    Remove {
+        /// ID of the recurring expense
+        id: i64,
+    },
+    
+    /// Create this month's expenses from every recurring expense whose day has come.
+    /// Running it again in the same month adds nothing
+    Apply,
 }
 
 /// Helper functions for parsing and validating CLI arguments
 pub mod helpers {
     use super::*;
-    use chrono::{Local, NaiveDate};
+    use chrono::{Datelike, NaiveDate};
+    use rust_decimal::RoundingStrategy;
     use thiserror::Error;
     
     #[derive(Debug, Error)]
+    #[non_exhaustive]
     pub enum CliError {
         #[error("Invalid date format: {0}")]
         InvalidDate(String),
@@ -129,16 +839,19 @@ pub mod helpers {
         
         #[error("Invalid amount: {0}")]
         InvalidAmount(String),
+        
+        #[error("Invalid input: {0}")]
+        InvalidInput(String),
     }
     
     /// Parse a date string or use today's date
-    pub fn parse_date(date_str: Option<String>) -> Result<NaiveDate, CliError> {
+    pub fn parse_date(date_str: Option<String>, today: NaiveDate) -> Result<NaiveDate, CliError> {
         match date_str {
             Some(date_str) => {
                 NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
                     .map_err(|_| CliError::InvalidDate(format!("Could not parse date: {}", date_str)))
             },
-            None => Ok(Local::now().naive_local().date()),
+            None => Ok(today),
         }
     }
     
@@ -152,34 +865,282 @@ pub mod helpers {
     }
     
     /// Validate amount is positive
-    pub fn validate_amount(amount: f64) -> Result<(), CliError> {
-        if amount < 0.0 {
+    pub fn validate_amount(amount: Decimal) -> Result<(), CliError> {
+        if amount < Decimal::ZERO {
             return Err(CliError::InvalidAmount("Amount cannot be negative".to_string()));
         }
         
         Ok(())
     }
     
+    /// Validate amount has no more than `decimal_places` fractional digits
+    pub fn validate_precision(amount: Decimal, decimal_places: u32) -> Result<(), CliError> {
+        if amount.round_dp(decimal_places) != amount {
+            return Err(CliError::InvalidAmount(format!(
+                "{} has more than {} decimal places", amount, decimal_places
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Round an amount to the nearest multiple of `step`, which must be positive
+    pub fn round_to_nearest(amount: Decimal, step: Decimal) -> Result<Decimal, CliError> {
+        if step <= Decimal::ZERO {
+            return Err(CliError::InvalidAmount(format!("Rounding step must be positive, got {}", step)));
+        }
+        
+        Ok((amount / step).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero) * step)
+    }
+    
+    /// Split a comma-separated category filter into names; `None` means all categories
+    pub fn parse_category_filter(filter: &str) -> Option<Vec<String>> {
+        if filter.trim() == "*" {
+            return None;
+        }
+        
+        let names = filter.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+        
+        Some(names)
+    }
+    
     /// Get default description if none provided
     pub fn default_description(description: Option<String>, category: &str) -> String {
         description.unwrap_or_else(|| format!("Expense in {}", category))
     }
     
-    /// Parse a date range or use reasonable defaults
-    pub fn parse_date_range(from: Option<String>, to: Option<String>) -> Result<(NaiveDate, NaiveDate), CliError> {
-        let today = Local::now().naive_local().date();
+    /// Trim surrounding whitespace and collapse internal runs of whitespace to a single space
+    pub fn normalize_whitespace(text: &str) -> String {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+    
+    /// Evaluate an amount that may be a simple arithmetic expression.
+    /// Only numbers, `+ - * /` and parentheses are accepted.
+    pub fn parse_amount_expr(expr: &str) -> Result<Decimal, CliError> {
+        let invalid = || CliError::InvalidAmount(format!("Could not evaluate amount: {}", expr));
+        
+        let mut parser = AmountParser {
+            chars: expr.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+        };
+        
+        let value = parser.expression().ok_or_else(invalid)?;
+        if parser.pos != parser.chars.len() {
+            return Err(invalid());
+        }
+        
+        Ok(value)
+    }
+    
+    /// Recursive descent parser for amount expressions, in exact decimal arithmetic.
+    /// Overflow and division by zero fail the parse
+    struct AmountParser {
+        chars: Vec<char>,
+        pos: usize,
+    }
+    
+    impl AmountParser {
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.pos).copied()
+        }
+        
+        // expression := term (('+' | '-') term)*
+        fn expression(&mut self) -> Option<Decimal> {
+            let mut value = self.term()?;
+            
+            while let Some(op @ ('+' | '-')) = self.peek() {
+                self.pos += 1;
+                let rhs = self.term()?;
+                value = if op == '+' { value.checked_add(rhs)? } else { value.checked_sub(rhs)? };
+            }
+            
+            Some(value)
+        }
+        
+        // term := factor (('*' | '/') factor)*
+        fn term(&mut self) -> Option<Decimal> {
+            let mut value = self.factor()?;
+            
+            while let Some(op @ ('*' | '/')) = self.peek() {
+                self.pos += 1;
+                let rhs = self.factor()?;
+                value = if op == '*' { value.checked_mul(rhs)? } else { value.checked_div(rhs)? };
+            }
+            
+            Some(value)
+        }
+        
+        // factor := '-' factor | '(' expression ')' | number
+        fn factor(&mut self) -> Option<Decimal> {
+            match self.peek()? {
+                '-' => {
+                    self.pos += 1;
+                    Some(-self.factor()?)
+                },
+                '(' => {
+                    self.pos += 1;
+                    let value = self.expression()?;
+                    if self.peek()? != ')' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    Some(value)
+                },
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                        self.pos += 1;
+                    }
+                    self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+                }
+            }
+        }
+    }
+    
+    /// Split quick-add input into amount, category and optional description
+    pub fn parse_quick_input(input: &str) -> Result<(Decimal, String, Option<String>), CliError> {
+        let mut parts = input.trim().splitn(3, char::is_whitespace);
+        
+        let amount_str = parts.next().filter(|s| !s.is_empty())
+            .ok_or_else(|| CliError::InvalidInput("Expected \"AMOUNT CATEGORY [DESCRIPTION]\"".to_string()))?;
+        let amount = parse_amount_expr(amount_str)?;
+        
+        let category = parts.next()
+            .ok_or_else(|| CliError::InvalidInput("Missing category after amount".to_string()))?
+            .to_string();
+        
+        let description = parts.next()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+        
+        Ok((amount, category, description))
+    }
+    
+    /// Parse a month in YYYY-MM format into year and month
+    pub fn parse_month(month_str: &str) -> Result<(i32, u32), CliError> {
+        let invalid = || CliError::InvalidDate(format!("Could not parse month (expected YYYY-MM): {}", month_str));
+        
+        let (year, month) = month_str.split_once('-').ok_or_else(invalid)?;
+        if year.len() != 4 || month.len() != 2 {
+            return Err(invalid());
+        }
         
+        let year = year.parse::<i32>().map_err(|_| invalid())?;
+        let month = month.parse::<u32>().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) {
+            return Err(invalid());
+        }
+        
+        Ok((year, month))
+    }
+    
+    /// Get the first and last day of a month given in YYYY-MM format
+    pub fn parse_month_range(month_str: &str) -> Result<(NaiveDate, NaiveDate), CliError> {
+        let (year, month) = parse_month(month_str)?;
+        
+        let first = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| CliError::InvalidDate(format!("Invalid month: {}", month_str)))?;
+        let last = first.checked_add_months(chrono::Months::new(1))
+            .and_then(|next_month| next_month.pred_opt())
+            .ok_or_else(|| CliError::InvalidDate(format!("Invalid month: {}", month_str)))?;
+        
+        Ok((first, last))
+    }
+    
+    /// Get the first and last day of the year starting in `start_month` of the given year (YYYY).
+    /// A start month of 1 gives the calendar year.
+    pub fn parse_year_range(year_str: &str, start_month: u32) -> Result<(NaiveDate, NaiveDate), CliError> {
+        if !(1..=12).contains(&start_month) {
+            return Err(CliError::InvalidInput(format!("Fiscal year start month must be between 1 and 12, got {}", start_month)));
+        }
+        
+        let invalid = || CliError::InvalidDate(format!("Invalid year: {} (expected YYYY)", year_str));
+        if year_str.len() != 4 {
+            return Err(invalid());
+        }
+        let year = year_str.parse::<i32>().map_err(|_| invalid())?;
+        
+        let first = NaiveDate::from_ymd_opt(year, start_month, 1).ok_or_else(invalid)?;
+        let last = first.checked_add_months(chrono::Months::new(12))
+            .and_then(|next_year| next_year.pred_opt())
+            .ok_or_else(invalid)?;
+        
+        Ok((first, last))
+    }
+    
+    /// Parse one end of a date range. Besides YYYY-MM-DD, a year-month (YYYY-MM) or a year (YYYY)
+    /// is accepted and expands to its first day, or its last day when `end` is set.
+    fn parse_range_bound(date_str: &str, end: bool) -> Option<NaiveDate> {
+        if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+            return Some(date);
+        }
+        
+        if let Ok((first, last)) = parse_month_range(date_str) {
+            return Some(if end { last } else { first });
+        }
+        
+        if date_str.len() == 4 && date_str.chars().all(|c| c.is_ascii_digit()) {
+            let year = date_str.parse().ok()?;
+            return if end {
+                NaiveDate::from_ymd_opt(year, 12, 31)
+            } else {
+                NaiveDate::from_ymd_opt(year, 1, 1)
+            };
+        }
+        
+        None
+    }
+    
+    /// Resolve the period to compare a summary range against. `prev` is the period just before
+    /// the range: the same number of whole months when the range covers whole months, otherwise
+    /// the same number of days. A single YYYY-MM or YYYY covers that month or year, and FROM..TO
+    /// gives both ends explicitly.
+    pub fn parse_compare_range(spec: &str, from: NaiveDate, to: NaiveDate) -> Result<(NaiveDate, NaiveDate), CliError> {
+        let invalid = || CliError::InvalidDate(format!("Could not parse comparison period: {}", spec));
+        
+        if spec.eq_ignore_ascii_case("prev") {
+            let whole_months = from.day() == 1 && to.succ_opt().is_some_and(|next| next.day() == 1);
+            if whole_months {
+                let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32 + 1;
+                let start = from.checked_sub_months(chrono::Months::new(months as u32)).ok_or_else(invalid)?;
+                return Ok((start, from.pred_opt().ok_or_else(invalid)?));
+            }
+            
+            let days = (to - from).num_days() + 1;
+            let end = from.pred_opt().ok_or_else(invalid)?;
+            return Ok((end - chrono::Duration::days(days - 1), end));
+        }
+        
+        let (start, end) = spec.split_once("..").unwrap_or((spec, spec));
+        let start = parse_range_bound(start.trim(), false).ok_or_else(invalid)?;
+        let end = parse_range_bound(end.trim(), true).ok_or_else(invalid)?;
+        
+        if start > end {
+            return Err(CliError::InvalidDate("comparison period must start before it ends".to_string()));
+        }
+        
+        Ok((start, end))
+    }
+    
+    /// Parse a date range or use reasonable defaults. Either side may be a full date,
+    /// a year-month or a year, e.g. `--from 2024 --to 2024` covers all of 2024.
+    pub fn parse_date_range(from: Option<String>, to: Option<String>, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), CliError> {
         // Default "from" is 1 year ago
         let from_date = match from {
-            Some(date_str) => NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .map_err(|_| CliError::InvalidDate(format!("Could not parse 'from' date: {}", date_str)))?,
+            Some(date_str) => parse_range_bound(&date_str, false)
+                .ok_or_else(|| CliError::InvalidDate(format!("Could not parse 'from' date: {}", date_str)))?,
             None => today - chrono::Duration::days(365),
         };
         
         // Default "to" is today
         let to_date = match to {
-            Some(date_str) => NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .map_err(|_| CliError::InvalidDate(format!("Could not parse 'to' date: {}", date_str)))?,
+            Some(date_str) => parse_range_bound(&date_str, true)
+                .ok_or_else(|| CliError::InvalidDate(format!("Could not parse 'to' date: {}", date_str)))?,
             None => today,
         };
         
@@ -191,3 +1152,230 @@ pub mod helpers {
         Ok((from_date, to_date))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+    
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()
+    }
+    
+    #[test]
+    fn parse_date_defaults_to_today() {
+        assert_eq!(parse_date(None, today()).unwrap(), today());
+        assert_eq!(parse_date(Some("2025-01-31".to_string()), today()).unwrap().to_string(), "2025-01-31");
+        assert!(parse_date(Some("31/01/2025".to_string()), today()).is_err());
+    }
+    
+    #[test]
+    fn parse_date_range_defaults_to_last_year() {
+        let (from, to) = parse_date_range(None, None, today()).unwrap();
+        
+        assert_eq!(to, today());
+        assert_eq!(from.to_string(), "2024-04-11");
+        
+        let result = parse_date_range(Some("2025-05-01".to_string()), None, today());
+        assert!(result.is_err());
+    }
+    
+    #[test]
+    fn parse_date_range_expands_years_and_months() {
+        let range = |from: &str, to: &str| {
+            let (from, to) = parse_date_range(Some(from.to_string()), Some(to.to_string()), today()).unwrap();
+            (from.to_string(), to.to_string())
+        };
+        
+        assert_eq!(range("2024", "2024"), ("2024-01-01".to_string(), "2024-12-31".to_string()));
+        assert_eq!(range("2024-02", "2024-02"), ("2024-02-01".to_string(), "2024-02-29".to_string()));
+        assert_eq!(range("2023-02", "2023-02"), ("2023-02-01".to_string(), "2023-02-28".to_string()));
+        assert_eq!(range("2024-12", "2025"), ("2024-12-01".to_string(), "2025-12-31".to_string()));
+        assert_eq!(range("2024-03-15", "2024-12"), ("2024-03-15".to_string(), "2024-12-31".to_string()));
+        
+        for bad in ["24", "2024-13", "2024-1", "twenty"] {
+            assert!(parse_date_range(Some(bad.to_string()), None, today()).is_err(), "expected '{}' to be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn normalize_whitespace_trims_and_collapses() {
+        assert_eq!(normalize_whitespace("Coffee  "), "Coffee");
+        assert_eq!(normalize_whitespace("  flat \t  white "), "flat white");
+        assert_eq!(normalize_whitespace("coffee"), "coffee");
+        assert_eq!(normalize_whitespace("   "), "");
+    }
+    
+    #[test]
+    fn round_to_nearest_step() {
+        assert_eq!(round_to_nearest(dec!(12.40), dec!(1.0)).unwrap(), dec!(12.0));
+        assert_eq!(round_to_nearest(dec!(12.40), dec!(5.0)).unwrap(), dec!(10.0));
+        assert_eq!(round_to_nearest(dec!(12.60), dec!(5.0)).unwrap(), dec!(15.0));
+        assert_eq!(round_to_nearest(dec!(12.30), dec!(0.5)).unwrap(), dec!(12.5));
+        assert!(round_to_nearest(dec!(12.40), dec!(0.0)).is_err());
+        assert!(round_to_nearest(dec!(12.40), dec!(-1.0)).is_err());
+    }
+    
+    #[test]
+    fn parse_year_range_respects_fiscal_start() {
+        let range = |year: &str, start: u32| {
+            let (from, to) = parse_year_range(year, start).unwrap();
+            (from.to_string(), to.to_string())
+        };
+        
+        assert_eq!(range("2024", 1), ("2024-01-01".to_string(), "2024-12-31".to_string()));
+        assert_eq!(range("2024", 4), ("2024-04-01".to_string(), "2025-03-31".to_string()));
+        assert_eq!(range("2023", 3), ("2023-03-01".to_string(), "2024-02-29".to_string()));
+        
+        assert!(matches!(parse_year_range("2024", 13), Err(CliError::InvalidInput(_))));
+        assert!(matches!(parse_year_range("2024", 0), Err(CliError::InvalidInput(_))));
+        assert!(matches!(parse_year_range("24", 1), Err(CliError::InvalidDate(_))));
+    }
+
+    #[test]
+    fn parse_compare_range_presets_and_ranges() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        
+        // Whole months compare with the same number of preceding months
+        assert_eq!(parse_compare_range("prev", date("2025-04-01"), date("2025-04-30")).unwrap(),
+            (date("2025-03-01"), date("2025-03-31")));
+        assert_eq!(parse_compare_range("prev", date("2025-01-01"), date("2025-06-30")).unwrap(),
+            (date("2024-07-01"), date("2024-12-31")));
+        
+        // Other ranges compare with the same number of preceding days
+        assert_eq!(parse_compare_range("prev", date("2025-04-11"), date("2025-04-20")).unwrap(),
+            (date("2025-04-01"), date("2025-04-10")));
+        
+        assert_eq!(parse_compare_range("2024-02", date("2025-02-01"), date("2025-02-28")).unwrap(),
+            (date("2024-02-01"), date("2024-02-29")));
+        assert_eq!(parse_compare_range("2024-01-15..2024-03", date("2025-02-01"), date("2025-02-28")).unwrap(),
+            (date("2024-01-15"), date("2024-03-31")));
+        assert!(parse_compare_range("last", date("2025-02-01"), date("2025-02-28")).is_err());
+        assert!(parse_compare_range("2024..2023", date("2025-02-01"), date("2025-02-28")).is_err());
+    }
+    
+    #[test]
+    fn validate_precision_at_two_decimal_places() {
+        assert!(validate_precision(dec!(10.99), 2).is_ok());
+        assert!(validate_precision(dec!(10.0), 2).is_ok());
+        assert!(validate_precision(dec!(12.50) + dec!(3.20), 2).is_ok());
+        assert!(matches!(validate_precision(dec!(10.999), 2), Err(CliError::InvalidAmount(_))));
+        assert!(matches!(validate_precision(dec!(10.5), 0), Err(CliError::InvalidAmount(_))));
+    }
+    
+    #[test]
+    fn parse_amount_expr_plain_numbers() {
+        assert_eq!(parse_amount_expr("42.50").unwrap(), dec!(42.50));
+        assert_eq!(parse_amount_expr(" 7 ").unwrap(), dec!(7.0));
+    }
+    
+    #[test]
+    fn parse_amount_expr_arithmetic() {
+        assert_eq!(parse_amount_expr("12.50+3.20").unwrap(), dec!(15.70));
+        assert_eq!(parse_amount_expr("2+3*4").unwrap(), dec!(14.0));
+        assert_eq!(parse_amount_expr("(2+3)*4").unwrap(), dec!(20.0));
+        assert_eq!(parse_amount_expr("10-4-3").unwrap(), dec!(3.0));
+        assert_eq!(parse_amount_expr("10/4").unwrap(), dec!(2.5));
+        assert_eq!(parse_amount_expr("90 / 3 / 2").unwrap(), dec!(15.0));
+        assert_eq!(parse_amount_expr("-(2+3)+10").unwrap(), dec!(5.0));
+    }
+    
+    #[test]
+    fn parse_amount_expr_rejects_invalid_input() {
+        for expr in ["", "abc", "2+", "(2+3", "2+3)", "1/0", "1e5", "2**3", "1.2.3", "sqrt(4)"] {
+            assert!(
+                matches!(parse_amount_expr(expr), Err(CliError::InvalidAmount(_))),
+                "expected '{}' to be rejected", expr
+            );
+        }
+    }
+    
+    #[test]
+    fn parse_month_values() {
+        assert_eq!(parse_month("2025-04").unwrap(), (2025, 4));
+        assert!(parse_month("2025-13").is_err());
+        assert!(parse_month("2025-00").is_err());
+        assert!(parse_month("2025-4").is_err());
+        assert!(parse_month("April").is_err());
+        
+        let (first, last) = parse_month_range("2024-02").unwrap();
+        assert_eq!(first.to_string(), "2024-02-01");
+        assert_eq!(last.to_string(), "2024-02-29");
+    }
+    
+    #[test]
+    fn parse_category_filter_list() {
+        assert_eq!(
+            parse_category_filter("Food, Transportation,"),
+            Some(vec!["Food".to_string(), "Transportation".to_string()])
+        );
+        assert_eq!(parse_category_filter("*"), None);
+    }
+    
+    #[test]
+    fn parse_quick_input_with_description() {
+        let (amount, category, description) = parse_quick_input("12.50 Food lunch at cafe").unwrap();
+        
+        assert_eq!(amount, dec!(12.50));
+        assert_eq!(category, "Food");
+        assert_eq!(description.as_deref(), Some("lunch at cafe"));
+    }
+    
+    #[test]
+    fn parse_quick_input_without_description() {
+        let (amount, category, description) = parse_quick_input("  7 Transportation ").unwrap();
+        
+        assert_eq!(amount, dec!(7));
+        assert_eq!(category, "Transportation");
+        assert_eq!(description, None);
+    }
+    
+    #[test]
+    fn parse_quick_input_rejects_bad_input() {
+        assert!(matches!(parse_quick_input(""), Err(CliError::InvalidInput(_))));
+        assert!(matches!(parse_quick_input("12.50"), Err(CliError::InvalidInput(_))));
+        assert!(matches!(parse_quick_input("lunch Food"), Err(CliError::InvalidAmount(_))));
+    }
+    
+    #[test]
+    fn writes_database_covers_changing_commands() {
+        use clap::Parser;
+        use super::Cli;
+        
+        let writes = |args: &[&str]| Cli::try_parse_from(["expense_log"].iter().chain(args)).unwrap()
+            .command.is_some_and(|command| command.writes_database());
+        
+        assert!(writes(&["add", "12.50", "Groceries"]));
+        assert!(writes(&["rollup", "rebuild"]));
+        assert!(writes(&["trash", "empty"]));
+        assert!(!writes(&["trash", "list"]));
+        assert!(!writes(&["list"]));
+        assert!(!writes(&["import", "--input", "expenses.csv", "--dry-run"]));
+    }
+    
+    #[test]
+    fn since_and_until_stand_in_for_from_and_to() {
+        use clap::{Parser, error::ErrorKind};
+        use super::{Cli, Commands};
+        
+        let parse = |args: &[&str]| Cli::try_parse_from(["expense_log"].iter().chain(args));
+        
+        match parse(&["list", "--since", "2025-01", "--until", "2025-02"]).unwrap().command {
+            Some(Commands::List(args)) => {
+                assert_eq!((args.from, args.since.as_deref()), (None, Some("2025-01")));
+                assert_eq!(args.until.as_deref(), Some("2025-02"));
+            }
+            _ => panic!("expected the list command"),
+        }
+        
+        for command in ["list", "summary"] {
+            let both = parse(&[command, "--from", "2025-01", "--since", "2025-02"]);
+            assert_eq!(both.err().map(|e| e.kind()), Some(ErrorKind::ArgumentConflict));
+            let both = parse(&[command, "--to", "2025-01", "--until", "2025-02"]);
+            assert_eq!(both.err().map(|e| e.kind()), Some(ErrorKind::ArgumentConflict));
+            let with_month = parse(&[command, "--month", "2025-01", "--since", "2025-02"]);
+            assert_eq!(with_month.err().map(|e| e.kind()), Some(ErrorKind::ArgumentConflict));
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{Local, NaiveDate};
+
+/// Source of the current date, so date-sensitive logic can be tested deterministically
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+}
+
+/// Clock reading the local system date
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        Local::now().naive_local().date()
+    }
+}
+
+/// Clock that always returns the same date
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
@@ -1,12 +1,31 @@
+use rust_decimal::Decimal;
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::fs;
 use std::io;
 use thiserror::Error;
 
+use crate::cli::ConfigFormat;
+use crate::locale::{self, Locale};
 use crate::models::category::{Category, CategoryRegistry, CategoryError};
+use crate::table::TableStyle;
+
+/// Most fractional digits `decimal_places` allows, as monthly totals are kept in whole cents
+pub const MAX_DECIMAL_PLACES: u32 = 2;
+
+/// Category kept when `use_system_categories` is off and none are configured, so there is
+/// always one to record expenses in
+pub const FALLBACK_CATEGORY: &str = "Miscellaneous";
+
+/// Environment variable overriding `currency_symbol`
+pub const ENV_CURRENCY: &str = "EXPENSE_LOG_CURRENCY";
+
+/// Environment variable overriding `database_path`
+pub const ENV_DATABASE: &str = "EXPENSE_LOG_DATABASE";
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ConfigError {
     #[error("IO error: {0}")]
     IoError(#[from] io::Error),
@@ -14,71 +33,503 @@ pub enum ConfigError {
     #[error("YAML error: {0}")]
     YamlError(#[from] serde_yaml::Error),
     
+    #[error("TOML error: {0}")]
+    TomlError(#[from] toml::de::Error),
+    
+    #[error("TOML error: {0}")]
+    TomlSerializeError(#[from] toml::ser::Error),
+    
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    
     #[error("Category error: {0}")]
     CategoryError(#[from] CategoryError),
+    
+    #[error("Unknown locale: {0}")]
+    UnknownLocale(String),
+    
+    #[error("Invalid date format: {0}")]
+    InvalidDateFormat(String),
+    
+    #[error("decimal_places is {0}, at most {MAX_DECIMAL_PLACES} is supported")]
+    InvalidDecimalPlaces(u32),
 }
 
+/// Fields missing from a config file take their value from `Config::default()`;
+/// `config migrate` writes them out explicitly.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub database_path: String,
+    /// Base currency. Categories may override it with their own `currency`; there is
+    /// no exchange-rate conversion, so summaries add amounts up as recorded.
     pub currency_symbol: String,
+    /// Whether the currency symbol goes before or after amounts
+    pub currency_position: CurrencyPosition,
+    /// Put a space between the currency symbol and the amount
+    pub currency_spacing: bool,
+    /// Separator between whole units and cents in displayed amounts
+    pub decimal_separator: String,
+    /// Separator between groups of thousands in displayed amounts; empty for none
+    pub thousands_separator: String,
+    /// strftime pattern for dates in expense tables
+    pub date_format: String,
+    /// Country conventions such as `en-US`, `en-GB`, `de-DE`, `fr-FR` or `da-DK`. The currency
+    /// position and spacing, separators and date format the config file doesn't set are taken
+    /// from the locale, and months are named in its language
+    pub locale: Option<String>,
     pub categories: Vec<Category>,
+    /// Start from the built-in categories when the file lists none, and keep them from
+    /// `category prune`. When off, only `FALLBACK_CATEGORY` is added to an empty list
+    pub use_system_categories: bool,
+    /// Record every change to an expense in the audit log
+    pub audit_log: bool,
+    /// Category whose entries are income, used by `summary --percent-of-income`
+    pub income_category: Option<String>,
+    /// How list and summary tables are drawn
+    pub table_style: TableStyle,
+    /// Maximum fractional digits accepted in amounts, up to `MAX_DECIMAL_PLACES`
+    pub decimal_places: u32,
+    /// Allow moving an expense to a date after today, such as a bill logged before it is due,
+    /// and stop `doctor` reporting future-dated expenses. Adding one is always allowed
+    pub allow_future_dates: bool,
+    /// Ask for confirmation before adding an expense above this amount; unset disables the check
+    pub large_expense_warning_threshold: Option<Decimal>,
+    /// Named CSV column mappings for `import --format csv --map <name>`
+    pub csv_mappings: BTreeMap<String, String>,
+    /// Month (1-12) in which the fiscal year starts, used by `summary --fiscal-year`
+    pub fiscal_year_start_month: u32,
+    /// Monthly spending limits checked by `summary` and `alerts`, in the order listed
+    pub alerts: Vec<AlertRule>,
+    /// Monthly budget per category name, shown by `summary` and checked when adding expenses
+    pub budgets: BTreeMap<String, Decimal>,
+    /// Trim and collapse whitespace in descriptions and use the registered spelling of
+    /// category names when saving expenses
+    pub normalize_input: bool,
+    /// Copy the database to `<database_path>.bak.N` before every command that changes it
+    pub auto_backup: bool,
+    /// Number of rolling `auto_backup` copies to keep
+    pub backup_retention: usize,
 }
 
-impl Config {
-    pub fn default() -> Result<Self, ConfigError> {
-        let default_categories = vec![
-            Category::new("Clothes", Some("Apparel, footwear, accessories, outerwear"))?,
-            Category::new("Dining", Some("Restaurants, cafes, takeaway, grab-and-go food and coffee"))?,
-            Category::new("Groceries", Some("Food, household essentials, pantry items"))?,
-            Category::new("Healthcare", Some("Medical visits, treatments, occasional medications"))?,
-            Category::new("Hobbies", Some("Books, games, equipment, collecables, classes"))?,
-            Category::new("Household", Some("Furniture, kitchenware, office supplies, tools"))?,
-            Category::new("Indulgences", Some("Cigarettes, drugs, gambling"))?,
-            Category::new("Miscellaneous", Some("One-off expenses, unclassified items"))?,
-            Category::new("Socializing", Some("Events, bars, gifts, parties, group activities"))?,
-            Category::new("Transportation", Some("Train, bus, taxi, car rentals, fuel, fares"))?,
-            Category::new("Upkeep", Some("Repairs, replacement parts, haircuts, laundry"))?,
-        ];
-        
-        Ok(Self {
+/// Where the currency symbol is placed relative to an amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyPosition {
+    /// `$ 42.50`
+    #[default]
+    Prefix,
+    /// `42,50 €`
+    Suffix,
+}
+
+/// Warn when a month's spending goes over a limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Category to watch; unset means total spending
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub monthly_limit: Decimal,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
             database_path: "expense_log.db".to_string(),
             currency_symbol: "$".to_string(),
-            categories: default_categories,
-        })
+            currency_position: CurrencyPosition::Prefix,
+            currency_spacing: true,
+            decimal_separator: ".".to_string(),
+            thousands_separator: String::new(),
+            date_format: "%Y-%m-%d".to_string(),
+            locale: None,
+            categories: Self::system_categories(),
+            use_system_categories: true,
+            audit_log: false,
+            income_category: None,
+            table_style: TableStyle::Plain,
+            decimal_places: 2,
+            allow_future_dates: false,
+            large_expense_warning_threshold: None,
+            csv_mappings: BTreeMap::new(),
+            fiscal_year_start_month: 1,
+            alerts: Vec::new(),
+            budgets: BTreeMap::new(),
+            normalize_input: true,
+            auto_backup: false,
+            backup_retention: 3,
+        }
+    }
+}
+
+impl Config {
+    /// The built-in categories, see `use_system_categories`
+    pub fn system_categories() -> Vec<Category> {
+        let category = |name: &str, description: &str| {
+            Category::new(name, Some(description)).expect("default category names are valid")
+        };
+        
+        vec![
+            category("Clothes", "Apparel, footwear, accessories, outerwear"),
+            category("Dining", "Restaurants, cafes, takeaway, grab-and-go food and coffee"),
+            category("Groceries", "Food, household essentials, pantry items"),
+            category("Healthcare", "Medical visits, treatments, occasional medications"),
+            category("Hobbies", "Books, games, equipment, collecables, classes"),
+            category("Household", "Furniture, kitchenware, office supplies, tools"),
+            category("Indulgences", "Cigarettes, drugs, gambling"),
+            category("Miscellaneous", "One-off expenses, unclassified items"),
+            category("Socializing", "Events, bars, gifts, parties, group activities"),
+            category("Transportation", "Train, bus, taxi, car rentals, fuel, fares"),
+            category("Upkeep", "Repairs, replacement parts, haircuts, laundry"),
+        ]
+    }
+    
+    /// Monthly budget of a category, matching the name case-insensitively
+    pub fn budget_for(&self, category: &str) -> Option<Decimal> {
+        self.budgets.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(category))
+            .map(|(_, budget)| *budget)
+    }
+    
+    /// Format an amount in the base currency, following the currency and separator settings
+    pub fn format_money(&self, amount: Decimal) -> String {
+        self.format_money_in(amount, &self.currency_symbol)
+    }
+    
+    /// Format an amount rounded to two decimals with the given currency symbol, following the
+    /// position, spacing and separator settings
+    pub fn format_money_in(&self, amount: Decimal, symbol: &str) -> String {
+        let (sign, number) = self.format_unsigned(amount, 2);
+        let space = if self.currency_spacing { " " } else { "" };
+        
+        match self.currency_position {
+            CurrencyPosition::Prefix => format!("{}{}{}{}", sign, symbol, space, number),
+            CurrencyPosition::Suffix => format!("{}{}{}{}", sign, number, space, symbol),
+        }
+    }
+    
+    /// Format an amount rounded to `places` decimals with the separator settings, without
+    /// a currency symbol
+    pub fn format_number(&self, amount: Decimal, places: u32) -> String {
+        let (sign, number) = self.format_unsigned(amount, places);
+        format!("{}{}", sign, number)
     }
     
+    /// The sign of a rounded amount, empty unless it is below zero, and its grouped digits
+    fn format_unsigned(&self, amount: Decimal, places: u32) -> (&'static str, String) {
+        let formatted = format!("{:.*}", places as usize, amount.abs().round_dp(places));
+        let (whole, fraction) = match formatted.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+        
+        let mut grouped = String::new();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                grouped.push_str(&self.thousands_separator);
+            }
+            grouped.push(digit);
+        }
+        
+        if let Some(fraction) = fraction {
+            grouped.push_str(&self.decimal_separator);
+            grouped.push_str(fraction);
+        }
+        
+        let sign = if amount < Decimal::ZERO && formatted.bytes().any(|b| b.is_ascii_digit() && b != b'0') { "-" } else { "" };
+        (sign, grouped)
+    }
+    
+    /// Format a date for display following `date_format`
+    pub fn format_date(&self, date: chrono::NaiveDate) -> String {
+        date.format(&self.date_format).to_string()
+    }
+    
+    /// Name of a month (1-12) in the language of the configured locale, English by default
+    pub fn month_name(&self, month: u32) -> &'static str {
+        let name = match self.locale.as_deref().and_then(Locale::resolve) {
+            Some(locale) => locale.month_name(month),
+            None => locale::english_month_name(month),
+        };
+        
+        name.unwrap_or("Unknown")
+    }
+    
+    /// Take the formatting settings `is_set` reports as unset from the configured locale
+    pub fn apply_locale(&mut self, is_set: impl Fn(&str) -> bool) -> Result<(), ConfigError> {
+        let Some(tag) = &self.locale else {
+            return Ok(());
+        };
+        let locale = Locale::resolve(tag).ok_or_else(|| ConfigError::UnknownLocale(tag.clone()))?;
+        
+        if !is_set("decimal_separator") {
+            self.decimal_separator = locale.decimal_separator.to_string();
+        }
+        if !is_set("thousands_separator") {
+            self.thousands_separator = locale.thousands_separator.to_string();
+        }
+        if !is_set("currency_position") {
+            self.currency_position = locale.currency_position;
+        }
+        if !is_set("currency_spacing") {
+            self.currency_spacing = locale.currency_spacing;
+        }
+        if !is_set("date_format") {
+            self.date_format = locale.date_format.to_string();
+        }
+        
+        Ok(())
+    }
+    
+    /// Give a config whose file lists no categories the built-in ones, or only the fallback
+    /// with `use_system_categories` off
+    fn fill_categories(&mut self, listed: bool) -> Result<(), ConfigError> {
+        if self.categories.is_empty() || !listed {
+            self.categories = if self.use_system_categories {
+                Self::system_categories()
+            } else {
+                vec![Category::new(FALLBACK_CATEGORY, None)?]
+            };
+        }
+        
+        Ok(())
+    }
+    
+    /// Load the config file (or the defaults if it doesn't exist) and apply environment overrides.
+    /// The format is taken from the file extension, see `detect_format`
     pub fn load(path: &Path) -> Result<Self, ConfigError> {
-        if !path.exists() {
-            return Self::default();
+        Self::load_as(path, detect_format(path))
+    }
+    
+    /// Like `load`, but parsing the file in the given format whatever its extension
+    pub fn load_as(path: &Path, format: ConfigFormat) -> Result<Self, ConfigError> {
+        let mut config = if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let mut config: Config = parse(&content, format)?;
+            let fields: serde_json::Value = parse(&content, format)?;
+            let is_set = |field: &str| fields.get(field).is_some_and(|value| !value.is_null());
+            
+            // Settings written in the file win over the locale's
+            if config.locale.is_some() {
+                config.apply_locale(is_set)?;
+            }
+            
+            config.fill_categories(is_set("categories"))?;
+            
+            config
+        } else {
+            Self::default()
+        };
+        
+        // An invalid pattern would only fail once a date is formatted with it
+        if chrono::format::StrftimeItems::new(&config.date_format).any(|item| item == chrono::format::Item::Error) {
+            return Err(ConfigError::InvalidDateFormat(config.date_format));
         }
         
-        let content = fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
+        if config.decimal_places > MAX_DECIMAL_PLACES {
+            return Err(ConfigError::InvalidDecimalPlaces(config.decimal_places));
+        }
+        
+        config.apply_env_overrides();
         Ok(config)
     }
     
+    /// Override config values from `EXPENSE_LOG_CURRENCY` and `EXPENSE_LOG_DATABASE`.
+    /// Precedence is: command-line flags > environment variables > config file.
+    /// `database_path` then has a leading `~` and any `$VAR`/`${VAR}` expanded; this
+    /// happens once, at load time.
+    pub fn apply_env_overrides(&mut self) {
+        self.apply_overrides(|name| std::env::var(name).ok());
+    }
+    
+    fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        if let Some(currency) = lookup(ENV_CURRENCY).filter(|v| !v.is_empty()) {
+            self.currency_symbol = currency;
+        }
+        
+        if let Some(database_path) = lookup(ENV_DATABASE).filter(|v| !v.is_empty()) {
+            self.database_path = database_path;
+        }
+        
+        self.database_path = expand_path(&self.database_path, &lookup);
+    }
+    
+    /// Write the config in the format matching the file extension, see `detect_format`
     pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
-        let content = serde_yaml::to_string(self)?;
-        fs::write(path, content)?;
-        Ok(())
+        self.save_as(path, detect_format(path))
+    }
+    
+    pub fn save_as(&self, path: &Path, format: ConfigFormat) -> Result<(), ConfigError> {
+        write(path, format, self)
+    }
+    
+    /// Set one field in the config file, leaving the rest of the file as written, so neither
+    /// the locale's formatting nor the environment overrides `load` applied end up in it.
+    /// A missing file is created with just that field
+    pub fn save_field_as(path: &Path, format: ConfigFormat, field: &str, value: impl Serialize) -> Result<(), ConfigError> {
+        let mut fields = if path.exists() {
+            parse(&fs::read_to_string(path)?, format)?
+        } else {
+            serde_json::Value::Null
+        };
+        
+        let mut value = serde_json::to_value(value)?;
+        if format == ConfigFormat::Toml {
+            remove_nulls(&mut value);
+        }
+        
+        match &mut fields {
+            serde_json::Value::Object(fields) => {
+                fields.insert(field.to_string(), value);
+            }
+            _ => fields = serde_json::json!({ field: value }),
+        }
+        
+        write(path, format, &fields)
+    }
+    
+    /// Rewrite a config file in canonical form, filling in every field the file doesn't set
+    /// with its default. Environment overrides are not applied. Returns the names of the
+    /// fields that were added. Comments in the file are not preserved.
+    pub fn migrate(path: &Path) -> Result<Vec<String>, ConfigError> {
+        Self::migrate_as(path, detect_format(path))
+    }
+    
+    /// Like `migrate`, for a file in the given format
+    pub fn migrate_as(path: &Path, format: ConfigFormat) -> Result<Vec<String>, ConfigError> {
+        let original: serde_json::Value = parse(&fs::read_to_string(path)?, format)?;
+        let mut config: Config = serde_json::from_value(original.clone())?;
+        
+        // Write out the locale's settings rather than defaults that would then override it
+        let is_set = |field: &str| original.get(field).is_some_and(|value| !value.is_null());
+        config.apply_locale(is_set)?;
+        config.fill_categories(is_set("categories"))?;
+        let migrated = serde_json::to_value(&config)?;
+        
+        // TOML has no null, so unset optional fields can't be written out there
+        let written = |value: &serde_json::Value| !(value.is_null() && format == ConfigFormat::Toml);
+        
+        let added = match (&migrated, &original) {
+            (serde_json::Value::Object(migrated), serde_json::Value::Object(original)) => migrated.iter()
+                .filter(|(key, value)| !original.contains_key(*key) && written(value))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            (serde_json::Value::Object(migrated), _) => migrated.iter()
+                .filter(|(_, value)| written(value))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+        
+        config.save_as(path, format)?;
+        Ok(added)
     }
     
     pub fn configure_category_registry(&self, registry: &mut CategoryRegistry) {
-        registry.load_categories(self.categories.clone());
+        registry.merge_categories(self.categories.clone());
+        
+        // Never leave the registry without a category to record expenses in
+        if registry.all_categories().is_empty()
+            && let Ok(fallback) = Category::new(FALLBACK_CATEGORY, None)
+        {
+            registry.seed_categories(vec![fallback]);
+        }
     }
 }
 
+/// Guess a config file's format from its extension: `.toml` and `.json` files are
+/// read as such, anything else as YAML
+pub fn detect_format(path: &Path) -> ConfigFormat {
+    match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+        Some("toml") => ConfigFormat::Toml,
+        Some("json") => ConfigFormat::Json,
+        _ => ConfigFormat::Yaml,
+    }
+}
+
+/// TOML has no null, so unset optional fields are left out instead
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            fields.retain(|_, value| !value.is_null());
+            fields.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+fn write(path: &Path, format: ConfigFormat, value: &impl Serialize) -> Result<(), ConfigError> {
+    let content = match format {
+        ConfigFormat::Yaml => serde_yaml::to_string(value)?,
+        ConfigFormat::Toml => toml::to_string_pretty(value)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn parse<T: serde::de::DeserializeOwned>(content: &str, format: ConfigFormat) -> Result<T, ConfigError> {
+    Ok(match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+        ConfigFormat::Toml => toml::from_str(content)?,
+        ConfigFormat::Json => serde_json::from_str(content)?,
+    })
+}
+
+/// Expand a leading `~` to `$HOME`, and `$VAR` or `${VAR}` to the variable's value.
+/// Unset variables are left as written.
+fn expand_path(path: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+    
+    if (rest == "~" || rest.starts_with("~/")) && let Some(home) = lookup("HOME") {
+        expanded.push_str(&home);
+        rest = &rest[1..];
+    }
+    
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        
+        match lookup(name).filter(|_| !name.is_empty()) {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = remainder;
+            },
+            None => {
+                // Keep the `$` literally and carry on after it
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+    
+    expanded.push_str(rest);
+    expanded
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn test_default_config() {
-        let config = Config::default().unwrap();
+        let config = Config::default();
         
         // Verify default values
         assert_eq!(config.database_path, "expense_log.db");
@@ -108,23 +559,42 @@ categories:
         assert_eq!(config.database_path, "test.db");
         assert_eq!(config.currency_symbol, "€");
         assert_eq!(config.categories.len(), 2);
+        assert_eq!(config.decimal_places, 2);
         
         let category_names: Vec<_> = config.categories.iter()
             .map(|c| c.name())
             .collect();
         assert!(category_names.contains(&"Test Category"));
         assert!(category_names.contains(&"Another Category"));
+        
+        // An empty category list is seeded with the defaults
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "categories: []").unwrap();
+        let config = Config::load(file.path()).unwrap();
+        let default_names: Vec<_> = Config::default().categories.iter().map(|c| c.name().to_string()).collect();
+        assert_eq!(config.categories.iter().map(|c| c.name()).collect::<Vec<_>>(), default_names);
+        
+        // Without the built-in categories only the listed ones, or the fallback, are used
+        let names = |content: &str| {
+            let mut file = NamedTempFile::new().unwrap();
+            write!(file, "{}", content).unwrap();
+            Config::load(file.path()).unwrap().categories.iter().map(|c| c.name().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(names("use_system_categories: false\n"), vec![FALLBACK_CATEGORY]);
+        assert_eq!(names("use_system_categories: false\ncategories: []\n"), vec![FALLBACK_CATEGORY]);
+        assert_eq!(names("use_system_categories: false\ncategories:\n  - name: Pets\n"), vec!["Pets"]);
     }
     
     #[test]
     fn test_save_config() -> Result<(), ConfigError> {
-        let mut config = Config::default()?;
-
-        config.database_path = "custom.db".to_string();
-        config.currency_symbol = "£".to_string();
-        config.categories = vec![
-            Category::new("Custom Category", Some("A custom category"))?,
-        ];
+        let config = Config {
+            database_path: "custom.db".to_string(),
+            currency_symbol: "£".to_string(),
+            categories: vec![
+                Category::new("Custom Category", Some("A custom category"))?,
+            ],
+            ..Config::default()
+        };
         
         // Create a temporary file for saving
         let file = NamedTempFile::new().unwrap();
@@ -143,6 +613,85 @@ categories:
         Ok(())
     }
     
+    #[test]
+    fn test_config_roundtrip_in_each_format() -> Result<(), ConfigError> {
+        let config = Config {
+            database_path: "custom.db".to_string(),
+            currency_symbol: "€".to_string(),
+            large_expense_warning_threshold: Some(dec!(500)),
+            alerts: vec![AlertRule { category: None, monthly_limit: dec!(2000) }],
+            csv_mappings: BTreeMap::from([("bank".to_string(), "date=1,amount=3".to_string())]),
+            ..Config::default()
+        };
+        
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            // No extension, so the format has to be given explicitly
+            let file = NamedTempFile::new().unwrap();
+            config.save_as(file.path(), format)?;
+            let loaded = Config::load_as(file.path(), format)?;
+            
+            assert_eq!(loaded.database_path, "custom.db", "{:?}", format);
+            assert_eq!(loaded.currency_symbol, "€");
+            assert_eq!(loaded.large_expense_warning_threshold, Some(dec!(500)));
+            assert_eq!(loaded.alerts, config.alerts);
+            assert_eq!(loaded.csv_mappings, config.csv_mappings);
+            assert_eq!(loaded.categories.len(), config.categories.len());
+            assert_eq!(loaded.categories[0].description(), config.categories[0].description());
+            
+            assert!(Config::migrate_as(file.path(), format)?.is_empty());
+        }
+        
+        // Forcing the wrong format fails instead of silently loading defaults
+        let file = NamedTempFile::new().unwrap();
+        config.save_as(file.path(), ConfigFormat::Toml)?;
+        assert!(Config::load_as(file.path(), ConfigFormat::Json).is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_save_field_keeps_the_file_as_written() -> Result<(), ConfigError> {
+        let files = [
+            (ConfigFormat::Yaml, "database_path: ~/expenses.db\nlocale: de-DE\n"),
+            (ConfigFormat::Toml, "database_path = \"~/expenses.db\"\nlocale = \"de-DE\"\n"),
+            (ConfigFormat::Json, "{\"database_path\": \"~/expenses.db\", \"locale\": \"de-DE\"}"),
+        ];
+        let categories = vec![Category::new("Hobbies", None)?];
+        
+        for (format, content) in files {
+            let file = NamedTempFile::new().unwrap();
+            fs::write(file.path(), content)?;
+            Config::save_field_as(file.path(), format, "categories", &categories)?;
+            
+            // Neither the expanded path nor the locale's separators are written out
+            let written: serde_json::Value = parse(&fs::read_to_string(file.path())?, format)?;
+            assert_eq!(written["database_path"], "~/expenses.db", "{:?}", format);
+            assert!(written.get("decimal_separator").is_none());
+            
+            let loaded = Config::load_as(file.path(), format)?;
+            assert_eq!(loaded.decimal_separator, ",");
+            assert_eq!(loaded.categories.len(), 1);
+            assert_eq!(loaded.categories[0].name(), "Hobbies");
+        }
+        
+        // A missing file is created with only the saved field
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("expense_log.yaml");
+        Config::save_field_as(&path, ConfigFormat::Yaml, "categories", &categories)?;
+        let written: serde_json::Value = parse(&fs::read_to_string(&path)?, ConfigFormat::Yaml)?;
+        assert_eq!(written.as_object().map(|fields| fields.len()), Some(1));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_detect_format_from_extension() {
+        assert_eq!(detect_format(Path::new("expense_log.toml")), ConfigFormat::Toml);
+        assert_eq!(detect_format(Path::new("/etc/expense_log/config.JSON")), ConfigFormat::Json);
+        assert_eq!(detect_format(Path::new("expense_log.yml")), ConfigFormat::Yaml);
+        assert_eq!(detect_format(Path::new("/config/expenses")), ConfigFormat::Yaml);
+    }
+    
     #[test]
     fn test_load_nonexistent_config() {
         // Try to load a non-existent file
@@ -154,15 +703,189 @@ categories:
         assert_eq!(config.database_path, "expense_log.db");
     }
     
+    #[test]
+    fn test_env_overrides() -> Result<(), ConfigError> {
+        let mut config = Config::default();
+        let env: std::collections::HashMap<&str, &str> = [
+            (ENV_CURRENCY, "kr"),
+            (ENV_DATABASE, "/tmp/override.db"),
+        ].into_iter().collect();
+        
+        config.apply_overrides(|name| env.get(name).map(|v| v.to_string()));
+        
+        assert_eq!(config.currency_symbol, "kr");
+        assert_eq!(config.database_path, "/tmp/override.db");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_expand_database_path() {
+        let home = tempfile::tempdir().unwrap();
+        let home_str = home.path().to_str().unwrap().to_string();
+        let lookup = |name: &str| match name {
+            "HOME" => Some(home_str.clone()),
+            "DATA" => Some(format!("{}/data", home_str)),
+            _ => None,
+        };
+        
+        assert_eq!(expand_path("~/expenses.db", lookup), format!("{}/expenses.db", home_str));
+        assert_eq!(expand_path("$DATA/expenses.db", lookup), format!("{}/data/expenses.db", home_str));
+        assert_eq!(expand_path("${DATA}-old.db", lookup), format!("{}/data-old.db", home_str));
+        assert_eq!(expand_path("$MISSING/x.db", lookup), "$MISSING/x.db");
+        assert_eq!(expand_path("cost$.db", lookup), "cost$.db");
+        assert_eq!(expand_path("${DATA.db", lookup), "${DATA.db");
+        assert_eq!(expand_path("~user/x.db", lookup), "~user/x.db");
+        assert_eq!(expand_path("expense_log.db", lookup), "expense_log.db");
+        
+        // The environment override is expanded too
+        let mut config = Config::default();
+        config.apply_overrides(|name| match name {
+            ENV_DATABASE => Some("~/override.db".to_string()),
+            _ => lookup(name),
+        });
+        assert_eq!(config.database_path, format!("{}/override.db", home_str));
+    }
+    
+    #[test]
+    fn test_env_overrides_ignore_unset_and_empty() -> Result<(), ConfigError> {
+        let mut config = Config::default();
+        
+        config.apply_overrides(|name| (name == ENV_CURRENCY).then(String::new));
+        
+        assert_eq!(config.currency_symbol, "$");
+        assert_eq!(config.database_path, "expense_log.db");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_migrate_fills_missing_fields() -> Result<(), ConfigError> {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, r#"
+database_path: "test.db"
+currency_symbol: "€"
+categories:
+  - name: "Food"
+    description: null
+audit_log: true
+"#).unwrap();
+        
+        let added = Config::migrate(file.path())?;
+        
+        assert!(added.contains(&"decimal_places".to_string()));
+        assert!(added.contains(&"table_style".to_string()));
+        assert!(!added.contains(&"audit_log".to_string()));
+        assert!(!added.contains(&"categories".to_string()));
+        
+        // The rewritten file keeps the existing values and now sets every field
+        let content = fs::read_to_string(file.path())?;
+        assert!(content.contains("decimal_places: 2"));
+        let config = Config::load(file.path())?;
+        assert_eq!(config.categories.len(), 1);
+        assert!(config.audit_log);
+        assert!(Config::migrate(file.path())?.is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn format_money_follows_locale_settings() {
+        let us = Config { thousands_separator: ",".to_string(), currency_spacing: false, ..Config::default() };
+        assert_eq!(us.format_money(dec!(1234567.5)), "$1,234,567.50");
+        assert_eq!(us.format_money(dec!(42.5)), "$42.50");
+        assert_eq!(us.format_money(dec!(-1000.0)), "-$1,000.00");
+        assert_eq!(us.format_money(dec!(66.6667)), "$66.67");
+        
+        let german = Config {
+            currency_symbol: "€".to_string(),
+            currency_position: CurrencyPosition::Suffix,
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(german.format_money(dec!(1234.5)), "1.234,50 €");
+        assert_eq!(german.format_money(dec!(0.0)), "0,00 €");
+        assert_eq!(german.format_money_in(dec!(12.0), "CHF"), "12,00 CHF");
+        
+        // The defaults keep the original "$ 42.50" layout
+        assert_eq!(Config::default().format_money(dec!(1234.5)), "$ 1234.50");
+    }
+    
+    #[test]
+    fn locale_fills_in_unset_formatting() -> Result<(), ConfigError> {
+        let load = |content: &str| -> Result<Config, ConfigError> {
+            let file = tempfile::Builder::new().suffix(".yaml").tempfile()?;
+            fs::write(file.path(), content)?;
+            Config::load(file.path())
+        };
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        
+        let us = load("locale: en-US\ncurrency_symbol: $\n")?;
+        assert_eq!(us.format_money(dec!(1234.5)), "$1,234.50");
+        assert_eq!(us.format_date(date), "03/09/2025");
+        assert_eq!(us.month_name(3), "March");
+        
+        let german = load("locale: de-DE\ncurrency_symbol: €\n")?;
+        assert_eq!(german.format_money(dec!(1234.5)), "1.234,50 €");
+        assert_eq!(german.format_date(date), "09.03.2025");
+        assert_eq!(german.month_name(3), "März");
+        
+        let french = load("locale: fr_FR\ncurrency_symbol: €\n")?;
+        assert_eq!(french.format_money(dec!(1234567.5)), "1 234 567,50 €");
+        assert_eq!(french.month_name(8), "août");
+        
+        // Migrating writes out the locale's settings, so the output doesn't change
+        let file = tempfile::Builder::new().suffix(".yaml").tempfile()?;
+        fs::write(file.path(), "locale: de-DE\ncurrency_symbol: €\n")?;
+        Config::migrate(file.path())?;
+        assert_eq!(Config::load(file.path())?.format_money(dec!(1234.5)), "1.234,50 €");
+        
+        // Settings in the file still win over the locale
+        let mixed = load("locale: de-DE\ncurrency_symbol: EUR\nthousands_separator: ''\ndate_format: '%Y-%m-%d'\n")?;
+        assert_eq!(mixed.format_money(dec!(1234.5)), "1234,50 EUR");
+        assert_eq!(mixed.format_date(date), "2025-03-09");
+        
+        // Without a locale nothing changes
+        assert_eq!(load("audit_log: true\n")?.format_money(dec!(1234.5)), "$ 1234.50");
+        
+        assert!(matches!(load("locale: xx-YY\n"), Err(ConfigError::UnknownLocale(_))));
+        assert!(matches!(load("date_format: '%Q'\n"), Err(ConfigError::InvalidDateFormat(_))));
+        assert!(matches!(load("decimal_places: 3\n"), Err(ConfigError::InvalidDecimalPlaces(3))));
+        assert_eq!(load("decimal_places: 0\n")?.decimal_places, 0);
+        
+        Ok(())
+    }
+    
     #[test]
     fn test_configure_category_registry() -> Result<(), ConfigError> {
         let config = Config {
             database_path: "test.db".to_string(),
             currency_symbol: "$".to_string(),
+            currency_position: CurrencyPosition::Prefix,
+            currency_spacing: true,
+            decimal_separator: ".".to_string(),
+            thousands_separator: String::new(),
+            date_format: "%Y-%m-%d".to_string(),
+            locale: None,
             categories: vec![
                 Category::new("Food", Some("Groceries"))?,
                 Category::new("Housing", None)?,
             ],
+            use_system_categories: true,
+            audit_log: false,
+            income_category: None,
+            table_style: TableStyle::Plain,
+            decimal_places: 2,
+            allow_future_dates: false,
+            large_expense_warning_threshold: None,
+            csv_mappings: BTreeMap::new(),
+            fiscal_year_start_month: 1,
+            alerts: Vec::new(),
+            budgets: BTreeMap::new(),
+            normalize_input: true,
+            auto_backup: false,
+            backup_retention: 3,
         };
         
         let mut registry = crate::models::category::CategoryRegistry::new();
@@ -172,6 +895,12 @@ categories:
         assert!(registry.category_exists("Housing"));
         assert_eq!(registry.all_categories().len(), 2);
         
+        // An empty list still leaves the fallback category
+        let mut registry = crate::models::category::CategoryRegistry::new();
+        Config { categories: Vec::new(), ..config }.configure_category_registry(&mut registry);
+        assert_eq!(registry.all_categories().len(), 1);
+        assert!(registry.category_exists(FALLBACK_CATEGORY));
+        
         Ok(())
     }
 }
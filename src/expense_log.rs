@@ -0,0 +1,162 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use crate::app::{App, AppError};
+use crate::cli::{AddArgs, ListArgs, ListFormat};
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::export::SummaryReport;
+use crate::models::expense::Expense;
+use crate::repository::ExpenseRepository;
+
+/// An expense to record through `ExpenseLog::add`
+#[derive(Debug, Clone, Default)]
+pub struct NewExpense {
+    pub amount: Decimal,
+    pub category: String,
+    /// Defaults to today
+    pub date: Option<NaiveDate>,
+    /// Defaults to "Expense in <category>"
+    pub description: Option<String>,
+    /// Defaults to the category's currency, then the base currency
+    pub currency: Option<String>,
+    pub secondary_category: Option<String>,
+    /// Account or source the money came from
+    pub account: Option<String>,
+}
+
+/// Which expenses `ExpenseLog::list` returns; unset fields don't filter
+#[derive(Debug, Clone, Default)]
+pub struct ExpenseFilter {
+    /// Category names, matched case-insensitively
+    pub categories: Vec<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<usize>,
+}
+
+/// Library entry point for embedding the expense log in other programs.
+/// Wraps `App` without printing anything or taking CLI argument types, and never prompts:
+/// confirmations such as the large expense check are answered yes.
+pub struct ExpenseLog<R: ExpenseRepository> {
+    app: App<R>,
+}
+
+impl<R: ExpenseRepository> ExpenseLog<R> {
+    pub fn new(repository: R, config: Config) -> Self {
+        Self {
+            app: App::new(repository, config).with_assume_yes(true),
+        }
+    }
+    
+    /// Use the given clock to determine today's date
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.app = self.app.with_clock(clock);
+        self
+    }
+    
+    /// Validate and save a new expense, returning it with its assigned id
+    pub fn add(&self, expense: NewExpense) -> Result<Expense, AppError> {
+        self.app.try_add_expense(AddArgs {
+            amount: expense.amount.to_string(),
+            category: expense.category,
+            date: expense.date.map(|date| date.to_string()),
+            description: expense.description,
+            currency: expense.currency,
+            secondary: expense.secondary_category,
+            round: None,
+            account: expense.account,
+            if_not_exists: false,
+        })
+    }
+    
+    /// Expenses matching the filter, newest first
+    pub fn list(&self, filter: ExpenseFilter) -> Result<Vec<Expense>, AppError> {
+        let mut expenses = self.app.find_expenses(ListArgs {
+            category: (!filter.categories.is_empty()).then(|| filter.categories.join(",")),
+            from: None,
+            to: None,
+            since: None,
+            until: None,
+            month: None,
+            description_glob: None,
+            account: None,
+            limit: None,
+            page: None,
+            page_size: 50,
+            watch: false,
+            interval: 2,
+            format: ListFormat::Table,
+        })?;
+        
+        expenses.retain(|expense| {
+            filter.from.is_none_or(|from| *expense.date() >= from) && filter.to.is_none_or(|to| *expense.date() <= to)
+        });
+        
+        if let Some(limit) = filter.limit {
+            expenses.truncate(limit);
+        }
+        
+        Ok(expenses)
+    }
+    
+    /// Category and monthly totals for a date range (inclusive)
+    pub fn summary(&self, from: NaiveDate, to: NaiveDate) -> Result<SummaryReport, AppError> {
+        self.app.summary_report(from, to, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::repository::sqlite::SqliteExpenseRepository;
+    use rust_decimal_macros::dec;
+    
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+    
+    fn create_test_log() -> ExpenseLog<SqliteExpenseRepository> {
+        let repository = SqliteExpenseRepository::new_in_memory().unwrap();
+        ExpenseLog::new(repository, Config::default()).with_clock(FixedClock(date("2025-04-11")))
+    }
+    
+    #[test]
+    fn add_list_and_summarize() {
+        let log = create_test_log();
+        
+        let added = log.add(NewExpense { amount: dec!(12.5), category: "groceries".to_string(), ..NewExpense::default() }).unwrap();
+        assert_eq!(added.category().name(), "Groceries");
+        assert_eq!(*added.date(), date("2025-04-11"));
+        
+        log.add(NewExpense {
+            amount: dec!(30),
+            category: "Dining".to_string(),
+            date: Some(date("2023-01-15")),
+            description: Some("Birthday dinner".to_string()),
+            ..NewExpense::default()
+        }).unwrap();
+        
+        assert_eq!(log.list(ExpenseFilter::default()).unwrap().len(), 2);
+        
+        let old = log.list(ExpenseFilter { to: Some(date("2024-01-01")), ..ExpenseFilter::default() }).unwrap();
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].description(), "Birthday dinner");
+        
+        let dining = log.list(ExpenseFilter { categories: vec!["dining".to_string()], ..ExpenseFilter::default() }).unwrap();
+        assert_eq!(dining.len(), 1);
+        
+        let report = log.summary(date("2025-04-01"), date("2025-04-30")).unwrap();
+        assert_eq!(report.total, dec!(12.5));
+        assert_eq!(report.category_totals, vec![("Groceries".to_string(), dec!(12.5))]);
+    }
+    
+    #[test]
+    fn add_rejects_unknown_category() {
+        let log = create_test_log();
+        
+        let result = log.add(NewExpense { amount: dec!(5), category: "Yachts".to_string(), ..NewExpense::default() });
+        assert!(result.is_err());
+        assert!(log.list(ExpenseFilter::default()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use crate::models::expense::Expense;
+
+/// Columns of the expense CSV, in order
+pub const COLUMNS: [&str; 5] = ["id", "date", "category", "amount", "description"];
+
+/// Write expenses as CSV with a header row. Amounts have two decimal places and dates are
+/// always ISO 8601, whatever the display settings, so the file reads back with `import`
+pub fn write_expenses<W: Write>(writer: W, expenses: &[Expense]) -> Result<(), ::csv::Error> {
+    let mut writer = ::csv::Writer::from_writer(writer);
+    writer.write_record(COLUMNS)?;
+    
+    for expense in expenses {
+        writer.write_record([
+            expense.id().map_or_else(String::new, |id| id.to_string()),
+            expense.date().to_string(),
+            expense.category().name().to_string(),
+            format!("{:.2}", expense.amount().round_dp(2)),
+            expense.description().to_string(),
+        ])?;
+    }
+    
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+    use crate::models::category::Category;
+
+    #[test]
+    fn write_quotes_descriptions() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let expenses = vec![
+            Expense::new(dec!(42.5), Category::new("Groceries", None).unwrap(), date, "Weekly shop, big".to_string()).with_id(1),
+            Expense::new(dec!(3.0), Category::new("Dining", None).unwrap(), date, "The \"good\" coffee".to_string()).with_id(2),
+        ];
+        
+        let mut output = Vec::new();
+        write_expenses(&mut output, &expenses).unwrap();
+        
+        assert_eq!(String::from_utf8(output).unwrap(), concat!(
+            "id,date,category,amount,description\n",
+            "1,2025-04-11,Groceries,42.50,\"Weekly shop, big\"\n",
+            "2,2025-04-11,Dining,3.00,\"The \"\"good\"\" coffee\"\n",
+        ));
+    }
+}
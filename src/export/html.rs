@@ -0,0 +1,168 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::SummaryReport;
+
+/// Bar colour for the category breakdown
+const BAR_COLOR: &str = "#4a90d9";
+
+/// Render a summary as a self-contained HTML document.
+///
+/// Everything is laid out with tables and inline styles, with no scripts or external
+/// assets, so the report also renders in email clients that strip `<style>` blocks.
+pub fn render(report: &SummaryReport) -> String {
+    let currency = escape(&report.currency_symbol);
+    let money = |amount: Decimal| format!("{}&nbsp;{:.2}", currency, amount.round_dp(2));
+    
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>Expense Summary {} to {}</title>\n", report.from, report.to));
+    html.push_str("</head>\n<body style=\"font-family: Arial, Helvetica, sans-serif; color: #222; margin: 24px;\">\n");
+    html.push_str(&format!(
+        "<h1 style=\"font-size: 20px;\">Expense Summary</h1>\n<p style=\"color: #666;\">{} to {}</p>\n",
+        report.from, report.to
+    ));
+    
+    // Category breakdown with proportional bars
+    html.push_str(&section_heading("Expenses by Category"));
+    if report.category_totals.is_empty() {
+        html.push_str(&empty_notice());
+    } else {
+        html.push_str(&table_open());
+        for (category, amount) in &report.category_totals {
+            let share = if report.total > Decimal::ZERO { (amount / report.total).to_f64().unwrap_or(0.0) * 100.0 } else { 0.0 };
+            html.push_str(&format!(
+                "<tr><td style=\"{cell}\">{}</td><td style=\"{cell} text-align: right;\">{}</td>\
+                 <td style=\"{cell} width: 200px;\"><table role=\"presentation\" cellpadding=\"0\" cellspacing=\"0\" width=\"100%\"><tr>\
+                 <td width=\"{share:.0}%\" style=\"background: {BAR_COLOR}; height: 12px; font-size: 0;\">&nbsp;</td><td></td>\
+                 </tr></table></td><td style=\"{cell} text-align: right;\">{share:.1}%</td></tr>\n",
+                escape(category),
+                money(*amount),
+                cell = CELL_STYLE,
+            ));
+        }
+        html.push_str(&total_row(&money(report.total), 4));
+        html.push_str("</table>\n");
+    }
+    
+    html.push_str(&section_heading("Expenses by Month"));
+    if report.monthly_totals.is_empty() {
+        html.push_str(&empty_notice());
+    } else {
+        html.push_str(&table_open());
+        for ((year, month), amount) in &report.monthly_totals {
+            let month_name = NaiveDate::from_ymd_opt(*year, *month, 1)
+                .map_or_else(|| format!("{}-{:02}", year, month), |d| d.format("%B %Y").to_string());
+            html.push_str(&format!(
+                "<tr><td style=\"{cell}\">{}</td><td style=\"{cell} text-align: right;\">{}</td></tr>\n",
+                month_name,
+                money(*amount),
+                cell = CELL_STYLE,
+            ));
+        }
+        let monthly_total: Decimal = report.monthly_totals.iter().map(|(_, amount)| amount).sum();
+        html.push_str(&total_row(&money(monthly_total), 2));
+        html.push_str("</table>\n");
+    }
+    
+    html.push_str(&section_heading("Monthly Averages by Category"));
+    if report.monthly_averages.is_empty() {
+        html.push_str(&empty_notice());
+    } else {
+        html.push_str(&table_open());
+        for (category, average) in &report.monthly_averages {
+            html.push_str(&format!(
+                "<tr><td style=\"{cell}\">{}</td><td style=\"{cell} text-align: right;\">{}/month</td></tr>\n",
+                escape(category),
+                money(*average),
+                cell = CELL_STYLE,
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+const CELL_STYLE: &str = "padding: 4px 8px; border-bottom: 1px solid #eee;";
+
+fn section_heading(title: &str) -> String {
+    format!("<h2 style=\"font-size: 16px; margin-top: 24px;\">{}</h2>\n", title)
+}
+
+fn table_open() -> String {
+    "<table cellpadding=\"0\" cellspacing=\"0\" style=\"border-collapse: collapse; font-size: 14px;\">\n".to_string()
+}
+
+fn total_row(amount: &str, columns: usize) -> String {
+    format!(
+        "<tr><td style=\"{cell} font-weight: bold;\">Total</td><td style=\"{cell} text-align: right; font-weight: bold;\">{}</td>{}</tr>\n",
+        amount,
+        "<td></td>".repeat(columns.saturating_sub(2)),
+        cell = CELL_STYLE,
+    )
+}
+
+fn empty_notice() -> String {
+    "<p style=\"color: #666;\">No data available for the selected period.</p>\n".to_string()
+}
+
+/// Escape text for use in HTML element content
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn sample_report() -> SummaryReport {
+        SummaryReport {
+            from: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2025, 4, 30).unwrap(),
+            currency_symbol: "$".to_string(),
+            category_totals: vec![
+                ("Groceries".to_string(), dec!(75)),
+                ("Food & <Drink>".to_string(), dec!(25)),
+            ],
+            total: dec!(100),
+            monthly_totals: vec![((2025, 3), dec!(40)), ((2025, 4), dec!(60))],
+            monthly_averages: vec![("Groceries".to_string(), dec!(37.5))],
+        }
+    }
+
+    #[test]
+    fn render_summary_document() {
+        let html = render(&sample_report());
+        
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(html.contains("$&nbsp;75.00"));
+        assert!(html.contains("width=\"75%\""));
+        assert!(html.contains("March 2025"));
+        assert!(html.contains("$&nbsp;37.50/month"));
+        assert!(!html.contains("<script") && !html.contains("<link"));
+    }
+    
+    #[test]
+    fn render_escapes_category_names() {
+        let html = render(&sample_report());
+        
+        assert!(html.contains("Food &amp; &lt;Drink&gt;"));
+        assert!(!html.contains("<Drink>"));
+    }
+}
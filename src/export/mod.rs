@@ -0,0 +1,29 @@
+pub mod csv;
+pub mod html;
+pub mod schema;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub use crate::repository::MonthlyTotals;
+
+/// Summary data for a date range, independent of how it is rendered
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SummaryReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub currency_symbol: String,
+    /// Spending per category, largest first
+    #[schemars(with = "Vec<(String, f64)>")]
+    pub category_totals: Vec<(String, Decimal)>,
+    #[schemars(with = "f64")]
+    pub total: Decimal,
+    /// Spending per `(year, month)`, oldest first
+    #[schemars(with = "Vec<((i32, u32), f64)>")]
+    pub monthly_totals: MonthlyTotals,
+    /// Average monthly spending per category, largest first
+    #[schemars(with = "Vec<(String, f64)>")]
+    pub monthly_averages: Vec<(String, Decimal)>,
+}
@@ -0,0 +1,48 @@
+use schemars::r#gen::SchemaSettings;
+use serde_json::{Value, json};
+
+use super::SummaryReport;
+use crate::models::expense::Expense;
+use crate::models::stats::LifetimeStats;
+
+/// JSON Schema (draft-07) describing the JSON the crate writes: expenses as exported by
+/// `backup --format json`, summary reports and `stats --json`. The shapes share one
+/// `definitions` section and are listed by name under `properties`.
+pub fn json_schema() -> Value {
+    let mut generator = SchemaSettings::draft07().into_generator();
+    
+    let expense = generator.subschema_for::<Expense>();
+    let summary = generator.subschema_for::<SummaryReport>();
+    let stats = generator.subschema_for::<LifetimeStats>();
+    
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "expense_log JSON output",
+        "type": "object",
+        "properties": {
+            "Expense": expense,
+            "SummaryReport": summary,
+            "LifetimeStats": stats,
+        },
+        "definitions": generator.take_definitions(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn schema_defines_every_output_shape() {
+        let schema = json_schema();
+        
+        for name in ["Expense", "SummaryReport", "LifetimeStats", "Category"] {
+            assert!(schema["definitions"][name].is_object(), "missing definition for {}", name);
+        }
+        assert_eq!(schema["properties"]["Expense"]["$ref"], "#/definitions/Expense");
+        
+        let expense = &schema["definitions"]["Expense"]["properties"];
+        assert_eq!(expense["date"]["format"], "date");
+        assert!(expense["secondary_category"].is_object());
+    }
+}
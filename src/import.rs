@@ -0,0 +1,221 @@
+use std::io::Read;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ImportError {
+    #[error("Invalid column mapping: {0}")]
+    InvalidMapping(String),
+    
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+    
+    #[error("{0} invalid records; nothing was imported (use --skip-invalid to import the rest)")]
+    InvalidRecords(usize),
+}
+
+/// Reference to a CSV column, either by position or by header name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnRef {
+    /// Zero-based column position
+    Index(usize),
+    /// Header name, matched case-insensitively
+    Header(String),
+    /// The field is not present in the file
+    Unmapped,
+}
+
+/// Which CSV columns hold each expense field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub date: ColumnRef,
+    pub amount: ColumnRef,
+    pub category: ColumnRef,
+    pub description: ColumnRef,
+}
+
+impl Default for ColumnMapping {
+    /// Columns named `date`, `amount`, `category` and `description` in the header
+    fn default() -> Self {
+        Self {
+            date: ColumnRef::Header("date".to_string()),
+            amount: ColumnRef::Header("amount".to_string()),
+            category: ColumnRef::Header("category".to_string()),
+            description: ColumnRef::Header("description".to_string()),
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// Parse a mapping such as `date=1,amount=3,category=none,description=4`.
+    /// Positions are 1-based, `none` marks a field as absent, anything else is a header name.
+    /// Fields that aren't mentioned keep their default header-name mapping.
+    pub fn parse(spec: &str) -> Result<Self, ImportError> {
+        let mut mapping = Self::default();
+        
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (field, column) = part.split_once('=')
+                .ok_or_else(|| ImportError::InvalidMapping(format!("expected FIELD=COLUMN, got '{}'", part)))?;
+            
+            let column = match column.trim() {
+                c if c.eq_ignore_ascii_case("none") => ColumnRef::Unmapped,
+                c => match c.parse::<usize>() {
+                    Ok(0) => return Err(ImportError::InvalidMapping("column positions start at 1".to_string())),
+                    Ok(position) => ColumnRef::Index(position - 1),
+                    Err(_) => ColumnRef::Header(c.to_string()),
+                },
+            };
+            
+            match field.trim().to_ascii_lowercase().as_str() {
+                "date" => mapping.date = column,
+                "amount" => mapping.amount = column,
+                "category" => mapping.category = column,
+                "description" => mapping.description = column,
+                other => return Err(ImportError::InvalidMapping(format!("unknown field '{}'", other))),
+            }
+        }
+        
+        if mapping.date == ColumnRef::Unmapped || mapping.amount == ColumnRef::Unmapped {
+            return Err(ImportError::InvalidMapping("date and amount must be mapped".to_string()));
+        }
+        
+        Ok(mapping)
+    }
+    
+    fn resolve(column: &ColumnRef, headers: Option<&csv::StringRecord>) -> Result<Option<usize>, ImportError> {
+        match column {
+            ColumnRef::Index(index) => Ok(Some(*index)),
+            ColumnRef::Unmapped => Ok(None),
+            ColumnRef::Header(name) => {
+                let headers = headers.ok_or_else(|| ImportError::InvalidMapping(
+                    format!("column '{}' is referenced by name but the file has no header", name)
+                ))?;
+                
+                headers.iter()
+                    .position(|header| header.trim().eq_ignore_ascii_case(name))
+                    .map(Some)
+                    .ok_or_else(|| ImportError::InvalidMapping(format!("column '{}' not found in header", name)))
+            }
+        }
+    }
+}
+
+/// A CSV row mapped to expense fields, not yet validated against the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRecord {
+    pub amount: Decimal,
+    pub category: Option<String>,
+    pub date: NaiveDate,
+    pub description: Option<String>,
+}
+
+/// Read CSV rows using a column mapping. Each row yields either a record or a
+/// message describing why it couldn't be parsed, so callers can report failures per row.
+pub fn read_csv<R: Read>(
+    reader: R,
+    mapping: &ColumnMapping,
+    date_format: &str,
+    has_header: bool,
+) -> Result<Vec<Result<CsvRecord, String>>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    
+    let headers = if has_header { Some(reader.headers()?.clone()) } else { None };
+    let date_column = ColumnMapping::resolve(&mapping.date, headers.as_ref())?;
+    let amount_column = ColumnMapping::resolve(&mapping.amount, headers.as_ref())?;
+    let category_column = ColumnMapping::resolve(&mapping.category, headers.as_ref())?;
+    let description_column = ColumnMapping::resolve(&mapping.description, headers.as_ref())?;
+    
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let field = |column: Option<usize>| column.and_then(|i| row.get(i)).filter(|v| !v.is_empty());
+        
+        let record = (|| {
+            let date_str = field(date_column).ok_or("missing date")?;
+            let date = NaiveDate::parse_from_str(date_str, date_format)
+                .map_err(|_| format!("could not parse date '{}' with format '{}'", date_str, date_format))?;
+            
+            let amount_str = field(amount_column).ok_or("missing amount")?;
+            let amount = amount_str.parse::<Decimal>()
+                .map_err(|_| format!("could not parse amount '{}'", amount_str))?;
+            
+            Ok(CsvRecord {
+                amount,
+                category: field(category_column).map(String::from),
+                date,
+                description: field(description_column).map(String::from),
+            })
+        })();
+        
+        records.push(record);
+    }
+    
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_mapping_spec() {
+        let mapping = ColumnMapping::parse("date=1,amount=3,category=none,description=Memo").unwrap();
+        
+        assert_eq!(mapping.date, ColumnRef::Index(0));
+        assert_eq!(mapping.amount, ColumnRef::Index(2));
+        assert_eq!(mapping.category, ColumnRef::Unmapped);
+        assert_eq!(mapping.description, ColumnRef::Header("Memo".to_string()));
+    }
+    
+    #[test]
+    fn reject_invalid_mapping_spec() {
+        assert!(ColumnMapping::parse("date=0,amount=1").is_err());
+        assert!(ColumnMapping::parse("date=1,price=2").is_err());
+        assert!(ColumnMapping::parse("date=1,amount").is_err());
+        assert!(ColumnMapping::parse("date=1,amount=none").is_err());
+    }
+    
+    #[test]
+    fn read_csv_by_header_name() {
+        let data = "Date,Amount,Category,Description\n2025-04-11,42.50,Groceries,\"Weekly shop, big\"\n";
+        
+        let records = read_csv(data.as_bytes(), &ColumnMapping::default(), "%Y-%m-%d", true).unwrap();
+        
+        assert_eq!(records.len(), 1);
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(record.amount, dec!(42.50));
+        assert_eq!(record.category.as_deref(), Some("Groceries"));
+        assert_eq!(record.date, NaiveDate::from_ymd_opt(2025, 4, 11).unwrap());
+        assert_eq!(record.description.as_deref(), Some("Weekly shop, big"));
+    }
+    
+    #[test]
+    fn read_csv_by_position_with_date_format() {
+        let data = "11/04/2025,ignored,12.30,Bakery\n12/04/2025,ignored,oops,Bakery\n";
+        let mapping = ColumnMapping::parse("date=1,amount=3,category=none,description=4").unwrap();
+
+        let records = read_csv(data.as_bytes(), &mapping, "%d/%m/%Y", false).unwrap();
+        
+        assert_eq!(records.len(), 2);
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(record.date, NaiveDate::from_ymd_opt(2025, 4, 11).unwrap());
+        assert_eq!(record.amount, dec!(12.30));
+        assert_eq!(record.category, None);
+        assert!(records[1].as_ref().unwrap_err().contains("could not parse amount"));
+    }
+    
+    #[test]
+    fn read_csv_reports_missing_header_column() {
+        let data = "when,how much\n2025-04-11,1\n";
+        
+        let result = read_csv(data.as_bytes(), &ColumnMapping::default(), "%Y-%m-%d", true);
+        assert!(matches!(result, Err(ImportError::InvalidMapping(_))));
+    }
+}
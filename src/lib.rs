@@ -1,5 +1,14 @@
 pub mod app;
+pub mod backup;
 pub mod cli;
+pub mod clock;
 pub mod config;
+pub mod expense_log;
+pub mod export;
+pub mod import;
+pub mod locale;
 pub mod models;
 pub mod repository;
+pub mod table;
+
+pub use expense_log::{ExpenseFilter, ExpenseLog, NewExpense};
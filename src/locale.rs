@@ -0,0 +1,128 @@
+use crate::config::CurrencyPosition;
+
+/// Number and date conventions of a country, used by the `locale` config setting to fill
+/// in the formatting settings a config file leaves out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    /// Tag the locale is selected by, such as `de-DE`
+    pub tag: &'static str,
+    pub decimal_separator: &'static str,
+    pub thousands_separator: &'static str,
+    pub currency_position: CurrencyPosition,
+    pub currency_spacing: bool,
+    /// strftime pattern for dates in tables
+    pub date_format: &'static str,
+    /// Month names, January first
+    pub month_names: [&'static str; 12],
+}
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// The built-in locales; the first one for each language is used for a bare language tag
+pub const LOCALES: &[Locale] = &[
+    Locale {
+        tag: "en-US",
+        decimal_separator: ".",
+        thousands_separator: ",",
+        currency_position: CurrencyPosition::Prefix,
+        currency_spacing: false,
+        date_format: "%m/%d/%Y",
+        month_names: ENGLISH_MONTHS,
+    },
+    Locale {
+        tag: "en-GB",
+        decimal_separator: ".",
+        thousands_separator: ",",
+        currency_position: CurrencyPosition::Prefix,
+        currency_spacing: false,
+        date_format: "%d/%m/%Y",
+        month_names: ENGLISH_MONTHS,
+    },
+    Locale {
+        tag: "de-DE",
+        decimal_separator: ",",
+        thousands_separator: ".",
+        currency_position: CurrencyPosition::Suffix,
+        currency_spacing: true,
+        date_format: "%d.%m.%Y",
+        month_names: [
+            "Januar", "Februar", "März", "April", "Mai", "Juni",
+            "Juli", "August", "September", "Oktober", "November", "Dezember",
+        ],
+    },
+    Locale {
+        tag: "fr-FR",
+        decimal_separator: ",",
+        thousands_separator: " ",
+        currency_position: CurrencyPosition::Suffix,
+        currency_spacing: true,
+        date_format: "%d/%m/%Y",
+        month_names: [
+            "janvier", "février", "mars", "avril", "mai", "juin",
+            "juillet", "août", "septembre", "octobre", "novembre", "décembre",
+        ],
+    },
+    Locale {
+        tag: "da-DK",
+        decimal_separator: ",",
+        thousands_separator: ".",
+        currency_position: CurrencyPosition::Suffix,
+        currency_spacing: true,
+        date_format: "%d-%m-%Y",
+        month_names: [
+            "januar", "februar", "marts", "april", "maj", "juni",
+            "juli", "august", "september", "oktober", "november", "december",
+        ],
+    },
+];
+
+impl Locale {
+    /// Find a built-in locale by tag, ignoring case and accepting `_` for `-`. A bare
+    /// language such as `de` selects the first locale for that language
+    pub fn resolve(tag: &str) -> Option<&'static Locale> {
+        let tag = tag.trim().replace('_', "-");
+        
+        LOCALES.iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(&tag))
+            .or_else(|| LOCALES.iter().find(|locale| {
+                locale.tag.split('-').next().is_some_and(|language| language.eq_ignore_ascii_case(&tag))
+            }))
+    }
+    
+    /// Name of a month (1-12), or `None` for any other number
+    pub fn month_name(&self, month: u32) -> Option<&'static str> {
+        let index = usize::try_from(month).ok()?.checked_sub(1)?;
+        self.month_names.get(index).copied()
+    }
+}
+
+/// English month name (1-12), as used when no locale is configured
+pub fn english_month_name(month: u32) -> Option<&'static str> {
+    let index = usize::try_from(month).ok()?.checked_sub(1)?;
+    ENGLISH_MONTHS.get(index).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn resolve_locale_tags() {
+        assert_eq!(Locale::resolve("de-DE").map(|l| l.tag), Some("de-DE"));
+        assert_eq!(Locale::resolve("fr_fr").map(|l| l.tag), Some("fr-FR"));
+        assert_eq!(Locale::resolve("en").map(|l| l.tag), Some("en-US"));
+        assert_eq!(Locale::resolve("xx-YY"), None);
+    }
+    
+    #[test]
+    fn month_names_by_number() {
+        let german = Locale::resolve("de-DE").unwrap();
+        assert_eq!(german.month_name(3), Some("März"));
+        assert_eq!(german.month_name(0), None);
+        assert_eq!(german.month_name(13), None);
+        assert_eq!(english_month_name(12), Some("December"));
+    }
+}
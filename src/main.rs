@@ -1,46 +1,94 @@
+use std::path::Path;
 use std::process;
 use clap::Parser;
 
-use expense_log::app::App;
+use expense_log::app::{self, App};
+use expense_log::backup;
 use expense_log::cli::{Cli, Commands};
-use expense_log::config::Config;
+use expense_log::config::{self, Config};
 use expense_log::repository::SqliteExpenseRepository;
 
 fn main() {
     let cli = Cli::parse();
     
     // Load config
-    let config = match Config::load(&cli.config) {
+    let config_format = cli.config_format.unwrap_or_else(|| config::detect_format(&cli.config));
+    let config = match Config::load_as(&cli.config, config_format) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Failed to load config: {}", e);
-            process::exit(1);
+            process::exit(app::EXIT_CONFIG);
         }
     };
     
+    // Keep a rolling copy of the database before changing it
+    if config.auto_backup && !cli.read_only && cli.command.as_ref().is_some_and(|c| c.writes_database())
+        && let Err(e) = backup::rotate_database_backup(Path::new(&config.database_path), config.backup_retention)
+    {
+        eprintln!("Failed to back up database: {}", e);
+        process::exit(app::EXIT_FAILURE);
+    }
+    
     // Initialize repository
-    let repository = match SqliteExpenseRepository::new(&config.database_path) {
-        Ok(repo) => repo,
+    let repository = if cli.read_only {
+        SqliteExpenseRepository::open_read_only(&config.database_path)
+    } else {
+        SqliteExpenseRepository::new(&config.database_path)
+    };
+    let repository = match repository {
+        Ok(repo) => repo.with_audit(config.audit_log).with_explain(cli.explain),
         Err(e) => {
             eprintln!("Failed to initialize database: {}", e);
-            process::exit(1);
+            process::exit(app::EXIT_DATABASE);
         }
     };
     
     // Create app instance
-    let mut app = App::new(repository, config);
+    let mut app = App::new(repository, config)
+        .with_config_path(&cli.config)
+        .with_config_format(cli.config_format)
+        .with_assume_yes(cli.assume_yes)
+        .with_read_only(cli.read_only);
+    
+    // Remind about due recurring expenses, unless the command deals with them itself
+    if !cli.quiet && !matches!(cli.command, Some(Commands::Recurring(_))) {
+        app.remind_due_recurring();
+    }
     
     // Process commands
     let result = match &cli.command {
         Some(Commands::Add(args)) => app.add_expense(args.clone()),
+        Some(Commands::Quick(args)) => app.quick_add(args.clone()),
+        Some(Commands::Edit(args)) => app.edit_expense(args.clone()),
+        Some(Commands::Delete(args)) => app.delete_expense(args.clone()),
         Some(Commands::List(args)) => app.list_expenses(args.clone()),
+        Some(Commands::Tail(args)) => app.tail(args.clone()),
         Some(Commands::Summary(args)) => app.generate_summary(args.clone()),
+        Some(Commands::Total(args)) => app.print_total(args.clone()),
         Some(Commands::Category(args)) => app.manage_categories(args.clone()),
+        Some(Commands::History(args)) => app.show_history(args.clone()),
+        Some(Commands::Backup(args)) => app.backup(args.clone()),
+        Some(Commands::Restore(args)) => app.restore(args.clone()),
+        Some(Commands::Export(args)) => app.export_expenses(args.clone()),
+        Some(Commands::Import(args)) => app.import_expenses(args.clone()),
+        Some(Commands::BulkEdit(args)) => app.bulk_edit(args.clone()),
+        Some(Commands::Forecast) => app.forecast(),
+        Some(Commands::Trash(args)) => app.manage_trash(args.clone()),
+        Some(Commands::Stats(args)) => app.show_stats(args.clone()),
+        Some(Commands::Alerts(args)) => app.check_alerts(args.clone()),
+        Some(Commands::Budget(args)) => app.manage_budgets(args.clone()),
+        Some(Commands::Recurring(args)) => app.manage_recurring(args.clone()),
+        Some(Commands::Config(args)) => app.manage_config(args.clone()),
+        Some(Commands::Rollup(args)) => app.manage_rollups(args.clone()),
+        Some(Commands::Doctor) => app.doctor(),
+        Some(Commands::Schema) => app.print_schema(),
+        Some(Commands::CompleteCategories) => app.complete_categories(),
         None => {
             // No command specified, show usage
             println!("expense_log - A simple CLI tool for tracking non-recurring expenses");
             println!("\nUsage examples:");
             println!("  expense_log add 42.50 Food --date 2025-04-15 --description \"Groceries\"");
+            println!("  expense_log quick \"12.50 Food lunch at cafe\"");
             println!("  expense_log list --category Food");
             println!("  expense_log summary --from 2025-01-01 --to 2025-03-31 --by-category");
             println!("  expense_log category list");
@@ -49,9 +97,9 @@ fn main() {
         }
     };
     
-    // Handle any errors
+    // Handle any errors, exiting with a code scripts can tell apart
     if let Err(e) = result {
         eprintln!("Error: {}", e);
-        process::exit(1);
+        process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,13 @@
+use serde::{Serialize, Deserialize};
+use chrono::NaiveDateTime;
+
+/// A single recorded change to one field of an expense
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub expense_id: i64,
+    pub operation: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: NaiveDateTime,
+}
@@ -1,18 +1,23 @@
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
 use std::fmt::{self, Display};
 use std::collections::HashSet;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CategoryError {
     #[error("Invalid category: {0}")]
     InvalidCategory(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Category {
     name: String,
     description: Option<String>,
+    /// Currency used by default for expenses in this category, overriding the base currency
+    #[serde(default)]
+    currency: Option<String>,
 }
 
 // Manual implementations for equality and hashing based only on name
@@ -41,8 +46,15 @@ impl Category {
         Ok(Self {
             name: name.to_string(),
             description: description.map(String::from),
+            currency: None,
         })
     }
+    
+    /// Sets the default currency for expenses in this category
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.currency = Some(currency.to_string());
+        self
+    }
 
     pub fn name(&self) -> &str {
         &self.name
@@ -52,6 +64,10 @@ impl Category {
         self.description.as_deref()
     }
     
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+    
     pub fn set_description(&mut self, description: &str) {
         self.description = if description.trim().is_empty() {
             None
@@ -72,6 +88,12 @@ pub struct CategoryRegistry {
     categories: HashSet<Category>,
 }
 
+impl Default for CategoryRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CategoryRegistry {
     pub fn new() -> Self {
         Self {
@@ -88,6 +110,30 @@ impl CategoryRegistry {
         }
     }
     
+    /// Add the categories whose names aren't registered yet, leaving existing ones and their
+    /// descriptions untouched, so seeding can be repeated safely. Returns the names added
+    pub fn seed_categories(&mut self, defaults: Vec<Category>) -> Vec<String> {
+        let mut added = Vec::new();
+        
+        for category in defaults {
+            if !self.category_exists(&category.name) {
+                added.push(category.name.clone());
+                self.categories.insert(category);
+            }
+        }
+        
+        added
+    }
+    
+    /// Add or replace categories by name (case-insensitively), so their descriptions and
+    /// currencies win over whatever was registered before
+    pub fn merge_categories(&mut self, categories: Vec<Category>) {
+        for category in categories {
+            self.categories.retain(|existing| !existing.name.eq_ignore_ascii_case(&category.name));
+            self.categories.insert(category);
+        }
+    }
+    
     /// Get all available categories
     pub fn all_categories(&self) -> Vec<&Category> {
         self.categories.iter().collect()
@@ -133,6 +179,7 @@ impl CategoryRegistry {
         let temp_category = Category {
             name: name.to_string(),
             description: None,
+            currency: None,
         };
         
         self.categories.remove(&temp_category);
@@ -296,5 +343,40 @@ mod tests {
         
         assert_eq!(category.name(), "Food");
         assert_eq!(category.description(), Some("Groceries and restaurants"));
+        assert_eq!(category.currency(), None);
+    }
+    
+    #[test]
+    fn category_currency_override() {
+        let category = Category::new("Subscriptions", None).unwrap().with_currency("USD");
+        assert_eq!(category.currency(), Some("USD"));
+        
+        let json = r#"{"name":"Streaming","description":null,"currency":"€"}"#;
+        let category: Category = serde_json::from_str(json).unwrap();
+        assert_eq!(category.currency(), Some("€"));
+    }
+    
+    #[test]
+    fn seeding_keeps_existing_descriptions_and_config_wins() {
+        let defaults = || vec![
+            Category::new("Food", Some("System default")).unwrap(),
+            Category::new("Transport", Some("Bus, train")).unwrap(),
+        ];
+        
+        let mut registry = CategoryRegistry::new();
+        registry.seed_categories(defaults());
+        registry.merge_categories(vec![Category::new("food", Some("Groceries and takeaway")).unwrap()]);
+        
+        // Seeding again adds nothing and doesn't clobber the config description
+        let added = registry.seed_categories(defaults());
+        assert!(added.is_empty());
+        
+        assert_eq!(registry.all_categories().len(), 2);
+        let food = registry.get_category("Food").unwrap();
+        assert_eq!(food.name(), "food");
+        assert_eq!(food.description(), Some("Groceries and takeaway"));
+        
+        let added = registry.seed_categories(vec![Category::new("Hobbies", None).unwrap()]);
+        assert_eq!(added, vec!["Hobbies".to_string()]);
     }
 }
@@ -1,9 +1,15 @@
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{RoundingStrategy, ToPrimitive};
 use thiserror::Error;
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
 use crate::models::category::{Category, CategoryError};
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ExpenseError {
     #[error("Invalid expense amount: {0}")]
     InvalidAmount(String),
@@ -13,25 +19,48 @@ pub enum ExpenseError {
     
     #[error("Invalid expense date: {0}")]
     InvalidDate(String),
+    
+    #[error("Missing expense field: {0}")]
+    MissingField(&'static str),
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Expense {
     id: Option<i64>,
-    amount: f64,
+    /// Written as a JSON number, as amounts were before they became decimals
+    #[serde(with = "amount_serde")]
+    #[schemars(with = "f64")]
+    amount: Decimal,
     category: Category,
     date: NaiveDate,
     description: String,
+    /// Currency of the amount; `None` means the configured base currency
+    #[serde(default)]
+    currency: Option<String>,
+    /// Name of a related category; the amount still counts only towards `category`
+    #[serde(default)]
+    secondary_category: Option<String>,
+    /// Account or source the money came from, e.g. the bank account an import was taken from
+    #[serde(default)]
+    account: Option<String>,
+    /// When the stored row was last written, as read from the database. Saving an expense
+    /// whose row has been written since it was loaded fails instead of overwriting the change.
+    #[serde(skip)]
+    updated_at: Option<NaiveDateTime>,
 }
 
 impl Expense {
-    pub fn new(amount: f64, category: Category, date: NaiveDate, description: String) -> Self {
+    pub fn new(amount: Decimal, category: Category, date: NaiveDate, description: String) -> Self {
         Self {
             id: None,
             amount,
             category,
             date,
             description,
+            currency: None,
+            secondary_category: None,
+            account: None,
+            updated_at: None,
         }
     }
 
@@ -41,60 +70,69 @@ impl Expense {
         self
     }
 
+    /// Start building an expense field by field, validated once by `ExpenseBuilder::build`
+    pub fn builder() -> ExpenseBuilder {
+        ExpenseBuilder::default()
+    }
+    
     pub fn new_validated(
-        amount: f64, 
+        amount: Decimal, 
         category: Category, 
         date: NaiveDate, 
         description: String
     ) -> Result<Self, ExpenseError> {
-        // Validate amount
-        if amount < 0.0 {
-            return Err(ExpenseError::InvalidAmount("amount cannot be negative".to_string()));
-        }
-        
-        // Category is already validated by the Category::new method
-        
-        // Validate date (example: don't allow future dates)
-        let today = chrono::Local::now().naive_local().date();
-        if date > today {
-            return Err(ExpenseError::InvalidDate("date cannot be in the future".to_string()));
-        }
-        
-        Ok(Self {
-            id: None,
-            amount,
-            category,
-            date,
-            description,
-        })
+        Self::new_validated_as_of(amount, category, date, description, SystemClock.today())
+    }
+    
+    /// Like `new_validated`, but judging future dates relative to the given `today`
+    pub fn new_validated_as_of(
+        amount: Decimal, 
+        category: Category, 
+        date: NaiveDate, 
+        description: String,
+        today: NaiveDate
+    ) -> Result<Self, ExpenseError> {
+        Self::builder()
+            .amount(amount)
+            .category(category)
+            .date(date)
+            .description(description)
+            .as_of(today)
+            .build()
     }
     
     // Helper method that creates a Category and then an Expense in one step
     pub fn with_category_name(
-        amount: f64,
+        amount: Decimal,
         category_name: &str,
         category_description: Option<&str>,
         date: NaiveDate,
         description: String
     ) -> Result<Self, ExpenseError> {
-        let category = Category::new(category_name, category_description)?;
-        
-        Self::new_validated(
-            amount,
-            category,
-            date,
-            description
-        )
+        Self::builder()
+            .amount(amount)
+            .category_name(category_name, category_description)
+            .date(date)
+            .description(description)
+            .build()
     }
     
     pub fn id(&self) -> Option<i64> {
         self.id
     }
     
-    pub fn amount(&self) -> f64 {
+    pub fn amount(&self) -> Decimal {
         self.amount
     }
     
+    /// The amount in whole cents, rounding half a cent away from zero
+    pub fn amount_cents(&self) -> i64 {
+        (self.amount * Decimal::ONE_HUNDRED)
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+            .to_i64()
+            .unwrap_or(i64::MAX)
+    }
+    
     pub fn category(&self) -> &Category {
         &self.category
     }
@@ -106,13 +144,33 @@ impl Expense {
     pub fn description(&self) -> &str {
         &self.description
     }
+    
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+    
+    pub fn secondary_category(&self) -> Option<&str> {
+        self.secondary_category.as_deref()
+    }
+    
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+    
+    pub fn updated_at(&self) -> Option<NaiveDateTime> {
+        self.updated_at
+    }
 
     pub fn set_id(&mut self, id: i64) {
         self.id = Some(id);
     }
     
-    pub fn set_amount(&mut self, amount: f64) -> Result<(), ExpenseError> {
-        if amount < 0.0 {
+    pub fn clear_id(&mut self) {
+        self.id = None;
+    }
+    
+    pub fn set_amount(&mut self, amount: Decimal) -> Result<(), ExpenseError> {
+        if amount < Decimal::ZERO {
             return Err(ExpenseError::InvalidAmount("amount cannot be negative".to_string()));
         }
 
@@ -125,8 +183,11 @@ impl Expense {
     }
     
     pub fn set_date(&mut self, date: NaiveDate) -> Result<(), ExpenseError> {
-        let today = chrono::Local::now().naive_local().date();
-
+        self.set_date_as_of(date, SystemClock.today())
+    }
+    
+    /// Like `set_date`, but judging future dates relative to the given `today`
+    pub fn set_date_as_of(&mut self, date: NaiveDate, today: NaiveDate) -> Result<(), ExpenseError> {
         if date > today {
             return Err(ExpenseError::InvalidDate("date cannot be in the future".to_string()));
         }
@@ -138,13 +199,165 @@ impl Expense {
     pub fn set_description(&mut self, description: String) {
         self.description = description;
     }
+    
+    pub fn set_currency(&mut self, currency: Option<String>) {
+        self.currency = currency;
+    }
+    
+    pub fn set_secondary_category(&mut self, secondary_category: Option<String>) {
+        self.secondary_category = secondary_category;
+    }
+    
+    pub fn set_account(&mut self, account: Option<String>) {
+        self.account = account;
+    }
+    
+    pub fn set_updated_at(&mut self, updated_at: Option<NaiveDateTime>) {
+        self.updated_at = updated_at;
+    }
+    
+    /// Headers and widths of the table list-like commands print expenses in,
+    /// matching the cells of `to_table_row`
+    pub fn table_header() -> [(&'static str, usize); 5] {
+        [("ID", 5), ("Date", 10), ("Category", 15), ("Amount", 10), ("Description", 30)]
+    }
+    
+    /// Cells for this expense in the expense table. The amount shows `decimal_places` digits with
+    /// the configured separators, and a secondary category follows the main one as "Main +Secondary".
+    pub fn to_table_row(&self, config: &Config) -> Vec<String> {
+        vec![
+            self.id.unwrap_or(0).to_string(),
+            config.format_date(self.date),
+            match &self.secondary_category {
+                Some(secondary) => format!("{} +{}", self.category.name(), secondary),
+                None => self.category.name().to_string(),
+            },
+            config.format_number(self.amount, config.decimal_places),
+            self.description.clone(),
+        ]
+    }
 }
 
 
+/// Serde adapter writing amounts as floats. Decimal's own `Deserialize` needs a self-describing
+/// format, which the bincode backups are not
+pub(crate) mod amount_serde {
+    use rust_decimal::Decimal;
+    use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+    
+    pub fn serialize<S: Serializer>(amount: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(amount.to_f64().unwrap_or(f64::NAN))
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        let amount = f64::deserialize(deserializer)?;
+        Decimal::from_f64(amount).ok_or_else(|| D::Error::custom(format!("invalid amount {}", amount)))
+    }
+}
+
+/// The category an `ExpenseBuilder` was given, either ready-made or to be created on `build`
+#[derive(Debug, Clone)]
+enum CategorySource {
+    Category(Category),
+    Name(String, Option<String>),
+}
+
+/// Builds an `Expense` from chained setters. Amount, category, date and description are
+/// required; `build` reports the first one missing, then validates the whole expense
+#[derive(Debug, Clone, Default)]
+pub struct ExpenseBuilder {
+    amount: Option<Decimal>,
+    category: Option<CategorySource>,
+    date: Option<NaiveDate>,
+    description: Option<String>,
+    currency: Option<String>,
+    secondary_category: Option<String>,
+    account: Option<String>,
+    today: Option<NaiveDate>,
+}
+
+impl ExpenseBuilder {
+    pub fn amount(mut self, amount: Decimal) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+    
+    pub fn category(mut self, category: Category) -> Self {
+        self.category = Some(CategorySource::Category(category));
+        self
+    }
+    
+    /// Use a new category with this name, which is validated by `build`
+    pub fn category_name(mut self, name: &str, description: Option<&str>) -> Self {
+        self.category = Some(CategorySource::Name(name.to_string(), description.map(String::from)));
+        self
+    }
+    
+    pub fn date(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+    
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    
+    pub fn currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+    
+    pub fn secondary_category(mut self, secondary_category: impl Into<String>) -> Self {
+        self.secondary_category = Some(secondary_category.into());
+        self
+    }
+    
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+    
+    /// Judge future dates relative to `today` instead of the system date
+    pub fn as_of(mut self, today: NaiveDate) -> Self {
+        self.today = Some(today);
+        self
+    }
+    
+    pub fn build(self) -> Result<Expense, ExpenseError> {
+        let amount = self.amount.ok_or(ExpenseError::MissingField("amount"))?;
+        let category = self.category.ok_or(ExpenseError::MissingField("category"))?;
+        let date = self.date.ok_or(ExpenseError::MissingField("date"))?;
+        let description = self.description.ok_or(ExpenseError::MissingField("description"))?;
+        
+        if amount < Decimal::ZERO {
+            return Err(ExpenseError::InvalidAmount("amount cannot be negative".to_string()));
+        }
+        
+        let category = match category {
+            CategorySource::Category(category) => category,
+            CategorySource::Name(name, description) => Category::new(&name, description.as_deref())?,
+        };
+        
+        if date > self.today.unwrap_or_else(|| SystemClock.today()) {
+            return Err(ExpenseError::InvalidDate("date cannot be in the future".to_string()));
+        }
+        
+        let mut expense = Expense::new(amount, category, date, description);
+        expense.currency = self.currency;
+        expense.secondary_category = self.secondary_category;
+        expense.account = self.account;
+        
+        Ok(expense)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
     use serde_json;
 
     #[test]
@@ -153,31 +366,78 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
 
         let expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             date, 
             "Weekly shopping trip".to_string()
         );
         
-        assert_eq!(expense.amount(), 42.50);
+        assert_eq!(expense.amount(), dec!(42.50));
         assert_eq!(expense.category().name(), "Groceries");
         assert_eq!(expense.date(), &date);
         assert_eq!(expense.description(), "Weekly shopping trip");
     }
     
+    #[test]
+    fn build_expense() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        
+        let expense = Expense::builder()
+            .amount(dec!(12.5))
+            .category_name("Dining", None)
+            .date(date)
+            .description("Lunch")
+            .account("Visa")
+            .currency("EUR")
+            .as_of(date)
+            .build()
+            .unwrap();
+        
+        assert_eq!(expense.amount(), dec!(12.5));
+        assert_eq!(expense.category().name(), "Dining");
+        assert_eq!(expense.account(), Some("Visa"));
+        assert_eq!(expense.currency(), Some("EUR"));
+        assert_eq!(expense.secondary_category(), None);
+        assert_eq!(expense.id(), None);
+    }
+    
+    #[test]
+    fn builder_reports_missing_and_invalid_fields() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let complete = || Expense::builder()
+            .amount(dec!(12.5))
+            .category_name("Dining", None)
+            .date(date)
+            .description("Lunch")
+            .as_of(date);
+        
+        let missing = |builder: ExpenseBuilder| match builder.build() {
+            Err(ExpenseError::MissingField(field)) => field,
+            other => panic!("expected a missing field, got {:?}", other),
+        };
+        assert_eq!(missing(Expense::builder()), "amount");
+        assert_eq!(missing(Expense::builder().amount(dec!(1.0)).date(date).description("x")), "category");
+        assert_eq!(missing(Expense::builder().amount(dec!(1.0)).category_name("Dining", None).description("x")), "date");
+        assert_eq!(missing(Expense::builder().amount(dec!(1.0)).category_name("Dining", None).date(date)), "description");
+        
+        assert!(matches!(complete().amount(dec!(-1.0)).build(), Err(ExpenseError::InvalidAmount(_))));
+        assert!(matches!(complete().category_name(" ", None).build(), Err(ExpenseError::InvalidCategory(_))));
+        assert!(matches!(complete().date(date.succ_opt().unwrap()).build(), Err(ExpenseError::InvalidDate(_))));
+    }
+    
     #[test]
     fn create_expense_with_category_name() {
         let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
 
         let expense = Expense::with_category_name(
-            42.50, 
+            dec!(42.50), 
             "Groceries",
             Some("Food and household items"),
             date, 
             "Weekly shopping trip".to_string()
         ).unwrap();
         
-        assert_eq!(expense.amount(), 42.50);
+        assert_eq!(expense.amount(), dec!(42.50));
         assert_eq!(expense.category().name(), "Groceries");
         assert_eq!(expense.category().description(), Some("Food and household items"));
         assert_eq!(expense.date(), &date);
@@ -189,7 +449,7 @@ mod tests {
         let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
 
         let result = Expense::with_category_name(
-            42.50, 
+            dec!(42.50), 
             "",
             None,
             date, 
@@ -206,14 +466,14 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             date, 
             "Weekly shopping trip".to_string()
         ).with_id(123);
         
         assert_eq!(expense.id(), Some(123));
-        assert_eq!(expense.amount(), 42.50);
+        assert_eq!(expense.amount(), dec!(42.50));
     }
     
     #[test]
@@ -222,7 +482,7 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let mut expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             date, 
             "Weekly shopping trip".to_string()
@@ -239,19 +499,33 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let mut expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             date, 
             "Weekly shopping trip".to_string()
         );
         
-        expense.set_amount(55.75).unwrap();
-        assert_eq!(expense.amount(), 55.75);
+        expense.set_amount(dec!(55.75)).unwrap();
+        assert_eq!(expense.amount(), dec!(55.75));
         
         // Test validation
-        let result = expense.set_amount(-10.0);
+        let result = expense.set_amount(dec!(-10.0));
         assert!(result.is_err());
-        assert_eq!(expense.amount(), 55.75); // Amount shouldn't change
+        assert_eq!(expense.amount(), dec!(55.75)); // Amount shouldn't change
+    }
+    
+    #[test]
+    fn test_amount_cents() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let expense = |amount: Decimal| Expense::new(amount, Category::new("Groceries", None).unwrap(), date, "Milk".to_string());
+        
+        assert_eq!(expense(dec!(0.1)).amount_cents(), 10);
+        assert_eq!(expense(dec!(0.2)).amount_cents(), 20);
+        assert_eq!(expense(dec!(19.99)).amount_cents(), 1999);
+        assert_eq!(expense(dec!(1.005)).amount_cents(), 101);
+        
+        // 0.1 + 0.2 would be 0.30000000000000004 as floats
+        assert_eq!(expense(dec!(0.1)).amount() + expense(dec!(0.2)).amount(), dec!(0.3));
     }
     
     #[test]
@@ -261,7 +535,7 @@ mod tests {
         let restaurant_category = Category::new("Restaurant", Some("Eating out")).unwrap();
         
         let mut expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             grocery_category, 
             date, 
             "Weekly shopping trip".to_string()
@@ -280,7 +554,7 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let mut expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             initial_date, 
             "Weekly shopping trip".to_string()
@@ -303,7 +577,7 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let mut expense = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category, 
             date, 
             "Weekly shopping trip".to_string()
@@ -320,14 +594,14 @@ mod tests {
         let category2 = Category::new("Groceries", None).unwrap();
 
         let expense1 = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category1, 
             date, 
             "Weekly shopping trip".to_string()
         );
         
         let expense2 = Expense::new(
-            42.50, 
+            dec!(42.50), 
             category2, 
             date, 
             "Weekly shopping trip".to_string()
@@ -343,7 +617,7 @@ mod tests {
         
         // Test that negative amounts are rejected
         let result = Expense::new_validated(
-            -50.0,
+            dec!(-50.0),
             category.clone(),
             date,
             "Weekly shopping".to_string()
@@ -357,7 +631,7 @@ mod tests {
         
         // Test that zero amount is allowed
         let result = Expense::new_validated(
-            0.0,
+            dec!(0.0),
             category,
             date,
             "Free item".to_string()
@@ -373,7 +647,7 @@ mod tests {
         let category = Category::new("Groceries", None).unwrap();
         
         let result = Expense::new_validated(
-            50.0,
+            dec!(50.0),
             category,
             future_date,
             "Future shopping".to_string()
@@ -386,13 +660,36 @@ mod tests {
         );
     }
     
+    #[test]
+    fn validate_expense_date_as_of_fixed_day() {
+        let today = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let category = Category::new("Groceries", None).unwrap();
+        
+        // Today itself is allowed, tomorrow is not
+        let result = Expense::new_validated_as_of(dec!(50.0), category.clone(), today, "Today".to_string(), today);
+        assert!(result.is_ok());
+        
+        let tomorrow = today.succ_opt().unwrap();
+        let result = Expense::new_validated_as_of(dec!(50.0), category, tomorrow, "Tomorrow".to_string(), today);
+        assert!(matches!(result, Err(ExpenseError::InvalidDate(_))));
+        
+        let mut expense = Expense::new(
+            dec!(50.0),
+            Category::new("Groceries", None).unwrap(),
+            today,
+            "Today".to_string()
+        );
+        assert!(expense.set_date_as_of(tomorrow, today).is_err());
+        assert!(expense.set_date_as_of(tomorrow, tomorrow).is_ok());
+    }
+    
     #[test]
     fn serialize_expense() {
         let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
         let category = Category::new("Groceries", Some("Food and household items")).unwrap();
 
         let expense = Expense::new(
-            42.50,
+            dec!(42.50),
             category,
             date,
             "Weekly shopping trip".to_string()
@@ -424,7 +721,7 @@ mod tests {
         
         let expense: Expense = serde_json::from_str(json).unwrap();
         
-        assert_eq!(expense.amount(), 42.50);
+        assert_eq!(expense.amount(), dec!(42.50));
         assert_eq!(expense.category().name(), "Groceries");
         assert_eq!(expense.category().description(), Some("Food and household items"));
         assert_eq!(
@@ -440,7 +737,7 @@ mod tests {
         let category = Category::new("Groceries", Some("Food and household items")).unwrap();
 
         let original = Expense::new(
-            42.50,
+            dec!(42.50),
             category,
             date,
             "Weekly shopping trip".to_string()
@@ -455,4 +752,24 @@ mod tests {
         // Original and deserialized should be equal
         assert_eq!(original, deserialized);
     }
+    
+    #[test]
+    fn to_table_row_matches_header() {
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let mut expense = Expense::new(dec!(12.5), Category::new("Dining", None).unwrap(), date, "Lunch".to_string())
+            .with_id(7);
+        
+        let row = expense.to_table_row(&Config::default());
+        assert_eq!(row.len(), Expense::table_header().len());
+        assert_eq!(row, vec!["7", "2025-04-11", "Dining", "12.50", "Lunch"]);
+        
+        expense.set_secondary_category(Some("Socializing".to_string()));
+        let config = Config { decimal_places: 0, ..Config::default() };
+        assert_eq!(expense.to_table_row(&config)[2..4], ["Dining +Socializing".to_string(), "12".to_string()]);
+        
+        // The amount follows the separator settings
+        expense.set_amount(dec!(1234.5)).unwrap();
+        let config = Config { decimal_separator: ",".to_string(), thousands_separator: ".".to_string(), ..Config::default() };
+        assert_eq!(expense.to_table_row(&config)[3], "1.234,50");
+    }
 }
@@ -1,2 +1,5 @@
+pub mod audit;
 pub mod category;
 pub mod expense;
+pub mod recurring;
+pub mod stats;
@@ -0,0 +1,67 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Serialize, Deserialize};
+
+/// Template for an expense that repeats every month on the same day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringExpense {
+    pub id: Option<i64>,
+    pub amount: Decimal,
+    pub category: String,
+    /// Day of the month (1-31) the expense falls on; shorter months use their last day
+    pub day_of_month: u32,
+    pub description: String,
+    /// Last month (`YYYY-MM`) an expense was created from the template
+    pub last_applied: Option<String>,
+}
+
+impl RecurringExpense {
+    pub fn new(amount: Decimal, category: String, day_of_month: u32, description: String) -> Self {
+        Self { id: None, amount, category, day_of_month, description, last_applied: None }
+    }
+    
+    /// Date the expense falls on in a month, or `None` for an invalid month
+    pub fn date_in(&self, year: i32, month: u32) -> Option<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let last = first.checked_add_months(chrono::Months::new(1))?.pred_opt()?;
+        
+        Some(NaiveDate::from_ymd_opt(year, month, self.day_of_month).unwrap_or(last))
+    }
+    
+    /// Whether this month's expense is due by `today` and hasn't been created yet
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        let month = today.format("%Y-%m").to_string();
+        self.date_in(today.year(), today.month()).is_some_and(|date| date <= today)
+            && self.last_applied.as_deref() < Some(month.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    
+    #[test]
+    fn date_in_clamps_to_the_last_day() {
+        let rent = RecurringExpense::new(dec!(950), "Housing".to_string(), 31, "Rent".to_string());
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        
+        assert_eq!(rent.date_in(2025, 1), Some(date("2025-01-31")));
+        assert_eq!(rent.date_in(2025, 4), Some(date("2025-04-30")));
+        assert_eq!(rent.date_in(2024, 2), Some(date("2024-02-29")));
+        assert_eq!(rent.date_in(2025, 13), None);
+    }
+    
+    #[test]
+    fn is_due_once_its_day_has_come() {
+        let mut rent = RecurringExpense::new(dec!(950), "Housing".to_string(), 15, "Rent".to_string());
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        
+        assert!(!rent.is_due(date("2025-04-14")));
+        assert!(rent.is_due(date("2025-04-15")));
+        
+        rent.last_applied = Some("2025-04".to_string());
+        assert!(!rent.is_due(date("2025-04-30")));
+        assert!(rent.is_due(date("2025-05-15")));
+    }
+}
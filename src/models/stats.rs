@@ -0,0 +1,133 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use crate::models::expense::Expense;
+
+/// Statistics over every recorded expense
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LifetimeStats {
+    pub total_count: usize,
+    #[schemars(with = "f64")]
+    pub total_amount: Decimal,
+    /// Date of the earliest expense, the first day counted in `average_per_day`
+    pub first_date: NaiveDate,
+    /// Date of the latest expense, the last day counted in `average_per_day`
+    pub last_date: NaiveDate,
+    /// Days from `first_date` to `last_date` inclusive
+    pub days: i64,
+    /// `total_amount / days`
+    #[schemars(with = "f64")]
+    pub average_per_day: Decimal,
+    /// Category with the most expenses; ties go to the higher total, then the name
+    pub busiest_category: String,
+    pub busiest_category_count: usize,
+    pub largest_expense: Expense,
+}
+
+/// Spread of the individual expense amounts in a group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmountStats {
+    pub min: Decimal,
+    pub max: Decimal,
+    pub median: Decimal,
+}
+
+/// Median of amounts sorted in ascending order, averaging the middle two for an even count.
+/// `None` if there are no amounts
+pub fn median(sorted_amounts: &[Decimal]) -> Option<Decimal> {
+    let middle = sorted_amounts.len() / 2;
+    
+    match sorted_amounts.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted_amounts[middle]),
+        _ => Some((sorted_amounts[middle - 1] + sorted_amounts[middle]) / Decimal::TWO),
+    }
+}
+
+impl LifetimeStats {
+    /// Compute statistics for a set of expenses, or `None` if there are none
+    pub fn from_expenses(expenses: &[Expense]) -> Option<Self> {
+        let first_date = expenses.iter().map(|e| *e.date()).min()?;
+        let last_date = expenses.iter().map(|e| *e.date()).max()?;
+        let days = (last_date - first_date).num_days() + 1;
+        let total_amount: Decimal = expenses.iter().map(Expense::amount).sum();
+        
+        let mut categories: HashMap<&str, (usize, Decimal)> = HashMap::new();
+        for expense in expenses {
+            let entry = categories.entry(expense.category().name()).or_insert((0, Decimal::ZERO));
+            entry.0 += 1;
+            entry.1 += expense.amount();
+        }
+        let (busiest_category, (busiest_category_count, _)) = categories.into_iter()
+            .max_by(|(a_name, (a_count, a_total)), (b_name, (b_count, b_total))| {
+                a_count.cmp(b_count)
+                    .then(a_total.cmp(b_total))
+                    .then(b_name.cmp(a_name))
+            })?;
+        
+        let largest_expense = expenses.iter()
+            .max_by_key(|e| e.amount())?
+            .clone();
+        
+        Some(Self {
+            total_count: expenses.len(),
+            total_amount,
+            first_date,
+            last_date,
+            days,
+            average_per_day: total_amount / Decimal::from(days),
+            busiest_category: busiest_category.to_string(),
+            busiest_category_count,
+            largest_expense,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::category::Category;
+    use rust_decimal_macros::dec;
+
+    fn expense(amount: Decimal, category: &str, date: &str) -> Expense {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Expense::new(amount, Category::new(category, None).unwrap(), date, "Test".to_string())
+    }
+
+    #[test]
+    fn compute_lifetime_stats() {
+        let expenses = vec![
+            expense(dec!(10), "Dining", "2025-04-01"),
+            expense(dec!(250), "Household", "2025-04-05"),
+            expense(dec!(5), "Dining", "2025-04-10"),
+        ];
+        
+        let stats = LifetimeStats::from_expenses(&expenses).unwrap();
+        
+        assert_eq!(stats.total_count, 3);
+        assert_eq!(stats.total_amount, dec!(265));
+        assert_eq!(stats.first_date.to_string(), "2025-04-01");
+        assert_eq!(stats.last_date.to_string(), "2025-04-10");
+        assert_eq!(stats.days, 10);
+        assert_eq!(stats.average_per_day, dec!(26.5));
+        assert_eq!(stats.busiest_category, "Dining");
+        assert_eq!(stats.busiest_category_count, 2);
+        assert_eq!(stats.largest_expense.amount(), dec!(250));
+    }
+    
+    #[test]
+    fn median_of_odd_even_and_empty() {
+        assert_eq!(median(&[dec!(4)]), Some(dec!(4)));
+        assert_eq!(median(&[dec!(1), dec!(2), dec!(10)]), Some(dec!(2)));
+        assert_eq!(median(&[dec!(1), dec!(2.5), dec!(4), dec!(10)]), Some(dec!(3.25)));
+        assert_eq!(median(&[]), None);
+    }
+    
+    #[test]
+    fn no_stats_without_expenses() {
+        assert!(LifetimeStats::from_expenses(&[]).is_none());
+    }
+}
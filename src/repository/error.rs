@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum RepositoryError {
     #[error("Database error: {0}")]
     DatabaseError(#[from] rusqlite::Error),
@@ -8,9 +9,15 @@ pub enum RepositoryError {
     #[error("Entity not found: {0}")]
     NotFound(String),
     
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    
+    #[error("{0}")]
+    Other(String),
 }
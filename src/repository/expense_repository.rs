@@ -1,33 +1,248 @@
+use crate::models::audit::AuditEntry;
 use crate::models::expense::Expense;
-use chrono::NaiveDate;
+use crate::models::recurring::RecurringExpense;
+use crate::models::stats::AmountStats;
+use chrono::{Datelike, Months, NaiveDate};
+use rust_decimal::Decimal;
 use super::error::RepositoryError;
 
+/// Spending per `(year, month)`
+pub type MonthlyTotals = Vec<((i32, u32), Decimal)>;
+
+/// Spending per `(year, month)` and category
+pub type MonthlyCategoryTotals = Vec<((i32, u32), String, Decimal)>;
+
 /// Defines the interface for expense storage operations
 pub trait ExpenseRepository {
     /// Save a new expense or update an existing one
     /// If expense.id() is None, a new expense is created
-    /// Otherwise, the expense with the given ID is updated
+    /// Otherwise, the expense with the given ID is updated, failing with
+    /// `RepositoryError::Conflict` if it was changed since this copy was loaded
     fn save(&self, expense: &mut Expense) -> Result<(), RepositoryError>;
     
+    /// Save several expenses at once, as a single unit of work where supported. If any of them
+    /// fails, none is saved and every expense keeps the ID it had before
+    fn save_batch(&self, expenses: &mut [Expense]) -> Result<(), RepositoryError>;
+    
+    /// Same as `save_batch`
+    fn save_all(&self, expenses: &mut [Expense]) -> Result<(), RepositoryError> {
+        self.save_batch(expenses)
+    }
+    
     /// Get an expense by its ID
     fn get_by_id(&self, id: i64) -> Result<Option<Expense>, RepositoryError>;
     
+    /// Get an expense by its ID, failing with `RepositoryError::NotFound` if it doesn't exist
+    fn get_by_id_required(&self, id: i64) -> Result<Expense, RepositoryError> {
+        self.get_by_id(id)?
+            .ok_or_else(|| RepositoryError::NotFound(format!("No expense found with ID {}", id)))
+    }
+    
+    /// Check whether an expense with this amount, category, date and description is recorded,
+    /// comparing amounts in whole cents
+    fn exists(&self, amount: Decimal, category: &str, date: NaiveDate, description: &str) -> Result<bool, RepositoryError>;
+    
     /// Get all expenses
     fn get_all(&self) -> Result<Vec<Expense>, RepositoryError>;
     
+    /// Get at most `limit` expenses after skipping the first `offset`, newest first by date
+    /// and then by ID
+    fn get_paged(&self, limit: usize, offset: usize) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Count the expenses outside the trash
+    fn count(&self) -> Result<usize, RepositoryError>;
+    
+    /// Get expenses with an ID greater than `after_id`, oldest first
+    fn get_added_after(&self, after_id: i64) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Get the `limit` most recent expenses, newest first by date and then by ID
+    fn get_recent(&self, limit: usize) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Get the expenses just before and just after an expense, ordered by date and then by ID,
+    /// for stepping through entries one at a time. Either is `None` at the first or last
+    /// expense; fails with `RepositoryError::NotFound` if the expense doesn't exist
+    fn get_adjacent(&self, id: i64) -> Result<(Option<Expense>, Option<Expense>), RepositoryError>;
+    
+    /// Get expenses dated after `today`, earliest first. `save` doesn't reject these, so
+    /// imports or direct saves can leave typos such as 2052 instead of 2025 behind
+    fn get_future_dated(&self, today: NaiveDate) -> Result<Vec<Expense>, RepositoryError>;
+    
     /// Get expenses by category name
     fn get_by_category(&self, category_name: &str) -> Result<Vec<Expense>, RepositoryError>;
     
+    /// Get expenses belonging to any of the given category names
+    fn get_by_categories(&self, category_names: &[String]) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Get the category names used by any expense, including those in the trash so
+    /// restoring them can't leave expenses in a category that no longer exists
+    fn get_distinct_categories(&self) -> Result<Vec<String>, RepositoryError>;
+    
+    /// Get expenses recorded against an account
+    fn get_by_account(&self, account: &str) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Get expenses whose description matches a glob pattern, where `*` matches any run of
+    /// characters and `?` a single character. Unlike a substring search, the whole description
+    /// must match and the comparison is case-sensitive
+    fn get_by_description_pattern(&self, pattern: &str) -> Result<Vec<Expense>, RepositoryError>;
+    
     /// Get expenses within a date range (inclusive)
     fn get_by_date_range(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Expense>, RepositoryError>;
     
-    /// Delete an expense by ID
-    /// Returns true if an expense was deleted, false if no expense with that ID was found
+    /// Get expenses within a calendar month
+    fn get_by_month(&self, year: i32, month: u32) -> Result<Vec<Expense>, RepositoryError> {
+        let start = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| RepositoryError::InvalidOperation(format!("Invalid month: {}-{:02}", year, month)))?;
+        let end = start.checked_add_months(Months::new(1))
+            .and_then(|next_month| next_month.pred_opt())
+            .ok_or_else(|| RepositoryError::InvalidOperation(format!("Invalid month: {}-{:02}", year, month)))?;
+        
+        self.get_by_date_range(start, end)
+    }
+    
+    /// Move an expense to the trash by ID. Trashed expenses are left out of every other query
+    /// until restored. Returns true if an expense was deleted, false if no expense with that ID was found
     fn delete(&self, id: i64) -> Result<bool, RepositoryError>;
     
+    /// Delete an expense by ID, failing with `RepositoryError::NotFound` if it doesn't exist
+    fn delete_required(&self, id: i64) -> Result<(), RepositoryError> {
+        if !self.delete(id)? {
+            return Err(RepositoryError::NotFound(format!("No expense found with ID {}", id)));
+        }
+        
+        Ok(())
+    }
+    
+    /// Get the expenses in the trash, most recently deleted first
+    fn get_deleted(&self) -> Result<Vec<Expense>, RepositoryError>;
+    
+    /// Take an expense out of the trash. Returns false if no trashed expense has that ID
+    fn restore_deleted(&self, id: i64) -> Result<bool, RepositoryError>;
+    
+    /// Permanently remove an expense, whether or not it is in the trash.
+    /// Returns false if no expense with that ID was found
+    fn purge(&self, id: i64) -> Result<bool, RepositoryError>;
+    
+    /// Permanently remove every expense in the trash, returning how many were removed
+    fn empty_trash(&self) -> Result<usize, RepositoryError>;
+    
+    /// Get total expenses across all categories within a date range
+    fn get_total(&self, start: NaiveDate, end: NaiveDate) -> Result<Decimal, RepositoryError>;
+    
     /// Get total expenses for a specific category within a date range
-    fn get_category_total(&self, category_name: &str, start: NaiveDate, end: NaiveDate) -> Result<f64, RepositoryError>;
+    fn get_category_total(&self, category_name: &str, start: NaiveDate, end: NaiveDate) -> Result<Decimal, RepositoryError>;
+    
+    /// Get totals for every category with expenses within a date range, in a single query
+    fn get_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError>;
+    
+    /// Get the total and number of expenses per category within a date range, in a single query
+    fn get_category_transaction_stats(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal, usize)>, RepositoryError>;
+    
+    /// Get the smallest, largest and median expense amount per category within a date range.
+    /// Categories without expenses in the range are left out
+    fn get_category_amount_stats(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, AmountStats)>, RepositoryError>;
+    
+    /// Get totals per secondary category within a date range, for expenses that have one.
+    /// These amounts are also part of their primary category's totals.
+    fn get_secondary_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError>;
+    
+    /// Get total spending per `(year, month)` within a date range, oldest month first.
+    /// Months without expenses are left out
+    fn get_monthly_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<MonthlyTotals, RepositoryError>;
+    
+    /// Get total spending per `(year, month)` and category within a date range, in a single
+    /// query, oldest month first. Month and category pairs without expenses are left out
+    fn get_monthly_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<MonthlyCategoryTotals, RepositoryError>;
+    
+    /// Get total spending per day of the month (1-31) within a date range, summed across
+    /// every month in it, lowest day first. Days without expenses are left out
+    fn get_day_of_month_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(u32, Decimal)>, RepositoryError>;
+    
+    /// Get totals per account within a date range, for expenses that have one
+    fn get_account_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError>;
     
     /// Get monthly averages by category for a given date range
-    fn get_monthly_category_averages(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, f64)>, RepositoryError>;
+    fn get_monthly_category_averages(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError>;
+    
+    /// Like `get_monthly_category_averages`, but a month the range only partly covers counts
+    /// as the fraction of its days that are covered, so the divisor is
+    /// `sum over months of (days in range) / (days in month)`. A range from the 1st of
+    /// January to the 15th of February divides by 1 + 15/28.
+    fn get_weighted_monthly_category_averages(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        let months = covered_months(start, end);
+        if months <= Decimal::ZERO {
+            return Ok(Vec::new());
+        }
+        
+        Ok(self.get_category_totals(start, end)?
+            .into_iter()
+            .map(|(category, total)| (category, total / months))
+            .collect())
+    }
+    
+    /// Get total and count of expenses per description within a date range, largest total first.
+    /// With `normalize`, descriptions are grouped case-insensitively and ignoring surrounding whitespace.
+    fn get_description_totals(&self, start: NaiveDate, end: NaiveDate, normalize: bool) -> Result<Vec<(String, Decimal, usize)>, RepositoryError>;
+    
+    /// Get daily totals within a date range (inclusive), with an entry for every day including zero-spend days
+    fn get_date_histogram(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(NaiveDate, Decimal)>, RepositoryError>;
+    
+    /// Recompute the cached per-month, per-category totals from scratch and keep them up to
+    /// date on every later change. Month-aligned category and monthly totals are read from
+    /// the cache from then on. Returns the number of cached totals
+    fn rebuild_monthly_rollups(&self) -> Result<usize, RepositoryError>;
+    
+    /// Get the recorded change history of an expense, oldest first
+    fn get_history(&self, expense_id: i64) -> Result<Vec<AuditEntry>, RepositoryError>;
+    
+    /// Save a new recurring template, assigning its ID, or update an existing one
+    fn save_recurring(&self, recurring: &mut RecurringExpense) -> Result<(), RepositoryError>;
+    
+    /// Get all recurring templates, ordered by day of the month and then by ID
+    fn get_recurring(&self) -> Result<Vec<RecurringExpense>, RepositoryError>;
+    
+    /// Delete a recurring template. Expenses already created from it are kept
+    fn delete_recurring(&self, id: i64) -> Result<bool, RepositoryError>;
+    
+    /// Save the expense created from a recurring template for a month (`YYYY-MM`) and mark the
+    /// template as applied for that month, both or neither. Returns `false` without saving
+    /// anything if the template was already applied for that month or a later one
+    fn apply_recurring(&self, recurring_id: i64, month: &str, expense: &mut Expense) -> Result<bool, RepositoryError>;
+}
+
+/// Number of months between two dates (inclusive), counting each partly covered month
+/// as the fraction of its days in the range
+fn covered_months(start: NaiveDate, end: NaiveDate) -> Decimal {
+    let mut months = Decimal::ZERO;
+    let mut month_start = start.with_day(1).unwrap_or(start);
+    
+    while month_start <= end {
+        let Some(next_month) = month_start.checked_add_months(Months::new(1)) else {
+            break;
+        };
+        let days_in_month = Decimal::from((next_month - month_start).num_days());
+        let first = month_start.max(start);
+        let last = next_month.pred_opt().unwrap_or(next_month).min(end);
+        
+        months += Decimal::from((last - first).num_days() + 1) / days_in_month;
+        month_start = next_month;
+    }
+    
+    months
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    
+    #[test]
+    fn covered_months_weights_partial_months() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        
+        assert_eq!(covered_months(date("2025-01-01"), date("2025-03-31")), dec!(3));
+        assert_eq!(covered_months(date("2025-01-01"), date("2025-02-14")), dec!(1.5));
+        assert_eq!(covered_months(date("2025-04-16"), date("2025-04-30")), dec!(0.5));
+        assert!((covered_months(date("2024-12-17"), date("2025-01-31")) - (dec!(15) / dec!(31) + Decimal::ONE)).abs() < dec!(0.000000001));
+        assert_eq!(covered_months(date("2025-02-01"), date("2025-01-31")), Decimal::ZERO);
+    }
 }
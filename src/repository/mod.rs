@@ -4,5 +4,5 @@ pub mod sqlite;
 
 // Re-export common types
 pub use error::RepositoryError;
-pub use expense_repository::ExpenseRepository;
+pub use expense_repository::{ExpenseRepository, MonthlyCategoryTotals, MonthlyTotals};
 pub use sqlite::SqliteExpenseRepository;
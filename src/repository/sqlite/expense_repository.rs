@@ -1,113 +1,407 @@
 use std::path::Path;
-use rusqlite::{Connection, params, types::Type};
-use chrono::{NaiveDate, Datelike};
+use rusqlite::{Connection, OpenFlags, Row, params, params_from_iter, types::Type};
+use rusqlite::trace::{TraceEvent, TraceEventCodes};
+use chrono::{NaiveDate, NaiveDateTime, Datelike, Local};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, RoundingStrategy, ToPrimitive};
 
+use crate::models::audit::AuditEntry;
 use crate::models::expense::Expense;
 use crate::models::category::Category;
-use crate::repository::{ExpenseRepository, RepositoryError};
+use crate::models::recurring::RecurringExpense;
+use crate::models::stats::{AmountStats, median};
+use crate::repository::{ExpenseRepository, MonthlyCategoryTotals, MonthlyTotals, RepositoryError};
 use super::schema;
 
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Format of `updated_at`, precise enough to tell apart writes made in quick succession
+const UPDATED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f";
+
+/// Prepared statements kept per connection, enough for every fixed query the repository runs
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
 pub struct SqliteExpenseRepository {
     conn: Connection,
+    audit: bool,
 }
 
 impl SqliteExpenseRepository {
-    /// Create a new SQLite repository with the given database file
+    /// Create a new SQLite repository with the given database file,
+    /// creating its parent directory if needed
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RepositoryError> {
+        if let Some(parent) = path.as_ref().parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        
         let conn = Connection::open(path)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         
         // Initialize schema
         schema::initialize_schema(&conn)?;
         
-        Ok(Self { conn })
+        Ok(Self { conn, audit: false })
+    }
+    
+    /// Open an existing database without write access. The schema is used as is, so a
+    /// database from an older version must have been opened normally once to migrate it.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, RepositoryError> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        
+        Ok(Self { conn, audit: false })
     }
     
     /// Create a new in-memory SQLite repository (useful for testing)
     pub fn new_in_memory() -> Result<Self, RepositoryError> {
         let conn = Connection::open_in_memory()?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         
         // Initialize schema
         schema::initialize_schema(&conn)?;
         
-        Ok(Self { conn })
+        Ok(Self { conn, audit: false })
+    }
+    
+    /// Enable or disable recording of changes in the audit log
+    pub fn with_audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+    
+    /// Log every SQL statement, with its bound parameters, to stderr before it runs
+    pub fn with_explain(self, enabled: bool) -> Self {
+        if enabled {
+            self.conn.trace_v2(TraceEventCodes::SQLITE_TRACE_STMT, Some(log_statement));
+        }
+        self
+    }
+    
+    /// Record the fields that differ between the old and new state of an expense
+    fn record_changes(&self, expense_id: i64, operation: &str, old: Option<&Expense>, new: Option<&Expense>) -> Result<(), RepositoryError> {
+        if !self.audit {
+            return Ok(());
+        }
+        
+        let changed_at = Local::now().naive_local().format(TIMESTAMP_FORMAT).to_string();
+        
+        for ((field, old_value), (_, new_value)) in Self::audit_fields(old).into_iter().zip(Self::audit_fields(new)) {
+            if old_value == new_value {
+                continue;
+            }
+            
+            self.conn.prepare_cached(
+                "INSERT INTO audit_log (expense_id, operation, field, old_value, new_value, changed_at) 
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+            )?.execute(params![expense_id, operation, field, old_value, new_value, changed_at])?;
+        }
+        
+        Ok(())
+    }
+    
+    fn audit_fields(expense: Option<&Expense>) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("amount", expense.map(|e| e.amount().normalize().to_string())),
+            ("category", expense.map(|e| e.category().name().to_string())),
+            ("date", expense.map(|e| e.date().to_string())),
+            ("description", expense.map(|e| e.description().to_string())),
+            ("currency", expense.and_then(|e| e.currency().map(String::from))),
+            ("secondary_category", expense.and_then(|e| e.secondary_category().map(String::from))),
+            ("account", expense.and_then(|e| e.account().map(String::from))),
+        ]
+    }
+    
+    /// Build an expense from a row selected with the standard column order:
+    /// id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at
+    fn expense_from_row(row: &Row) -> rusqlite::Result<Expense> {
+        let id = row.get(0)?;
+        let amount = amount_column(row, 1)?;
+        let category_name: String = row.get(2)?;
+        let category_description: Option<String> = row.get(3)?;
+        let date_str: String = row.get(4)?;
+        let description: String = row.get(5)?;
+        let currency: Option<String> = row.get(6)?;
+        let secondary_category: Option<String> = row.get(7)?;
+        let account: Option<String> = row.get(8)?;
+        let updated_at: Option<String> = row.get(9)?;
+        
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .map_err(|_| rusqlite::Error::InvalidColumnType(4, "Invalid date format".to_string(), Type::Text))?;
+        
+        let category = Category::new(
+            &category_name, 
+            category_description.as_deref()
+        ).map_err(|_| rusqlite::Error::InvalidColumnType(2, "Invalid category".to_string(), Type::Text))?;
+        
+        let mut expense = Expense::new(amount, category, date, description).with_id(id);
+        expense.set_currency(currency);
+        expense.set_secondary_category(secondary_category);
+        expense.set_account(account);
+        expense.set_updated_at(updated_at.and_then(|s| NaiveDateTime::parse_from_str(&s, UPDATED_AT_FORMAT).ok()));
+        
+        Ok(expense)
+    }
+}
+
+impl SqliteExpenseRepository {
+    /// Whether `rebuild_monthly_rollups` has set up the rollup cache in this database
+    fn has_monthly_rollups(&self) -> Result<bool, RepositoryError> {
+        let found: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'monthly_rollups'",
+            [],
+            |row| row.get(0),
+        )?;
+        
+        Ok(found > 0)
+    }
+    
+    /// The `YYYY-MM` bounds of a range covering whole months, if rollups can answer for it
+    fn rollup_months(&self, start: NaiveDate, end: NaiveDate) -> Result<Option<(String, String)>, RepositoryError> {
+        let month_aligned = start.day() == 1 && end.succ_opt().is_some_and(|next| next.day() == 1);
+        
+        if !month_aligned || start > end || !self.has_monthly_rollups()? {
+            return Ok(None);
+        }
+        
+        Ok(Some((start.format("%Y-%m").to_string(), end.format("%Y-%m").to_string())))
+    }
+    
+    fn rollup_category_totals(&self, first_month: &str, last_month: &str) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT category, SUM(total_cents) 
+             FROM monthly_rollups 
+             WHERE month >= ?1 AND month <= ?2 
+             GROUP BY category"
+        )?;
+        
+        let totals = stmt.query_map(params![first_month, last_month], |row| {
+            let category: String = row.get(0)?;
+            let cents: i64 = row.get(1)?;
+            Ok((category, from_cents(cents)))
+        })?.collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(totals)
+    }
+    
+    fn rollup_monthly_totals(&self, first_month: &str, last_month: &str) -> Result<MonthlyTotals, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT CAST(substr(month, 1, 4) AS INTEGER), CAST(substr(month, 6, 2) AS INTEGER), SUM(total_cents) 
+             FROM monthly_rollups 
+             WHERE month >= ?1 AND month <= ?2 
+             GROUP BY month 
+             ORDER BY month"
+        )?;
+        
+        let totals = stmt.query_map(params![first_month, last_month], |row| {
+            let year: i32 = row.get(0)?;
+            let month: u32 = row.get(1)?;
+            let cents: i64 = row.get(2)?;
+            Ok(((year, month), from_cents(cents)))
+        })?.collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(totals)
+    }
+    
+    fn live_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT category, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY category"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let category: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                Ok((category, total))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn live_monthly_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<MonthlyTotals, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT CAST(strftime('%Y', date) AS INTEGER), CAST(strftime('%m', date) AS INTEGER), SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY 1, 2 
+             ORDER BY 1, 2"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let year: i32 = row.get(0)?;
+                let month: u32 = row.get(1)?;
+                let total = from_cents(row.get(2)?);
+                Ok(((year, month), total))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+}
+
+/// Read an amount column, stored as REAL, as a decimal
+fn amount_column(row: &Row, index: usize) -> rusqlite::Result<Decimal> {
+    let amount: f64 = row.get(index)?;
+    
+    Decimal::from_f64(amount)
+        .ok_or_else(|| rusqlite::Error::InvalidColumnType(index, "Invalid amount".to_string(), Type::Real))
+}
+
+/// A total the SQL summed in whole cents, which keeps the REAL amounts from drifting
+fn from_cents(cents: i64) -> Decimal {
+    Decimal::new(cents, 2)
+}
+
+/// Timestamp for a write to a row last written at `previous`, always later than it
+/// so the change is visible to optimistic concurrency checks even within one clock tick
+fn next_updated_at(previous: Option<NaiveDateTime>) -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    
+    match previous {
+        Some(previous) if now <= previous => previous + chrono::Duration::microseconds(1),
+        _ => now,
+    }
+}
+
+/// Trace callback printing the expanded SQL of each executed statement
+fn log_statement(event: TraceEvent<'_>) {
+    if let TraceEvent::Stmt(stmt, sql) = event {
+        let sql = stmt.expanded_sql().unwrap_or_else(|| sql.to_string());
+        eprintln!("[sql] {}", sql.split_whitespace().collect::<Vec<_>>().join(" "));
     }
 }
 
 impl ExpenseRepository for SqliteExpenseRepository {
     fn save(&self, expense: &mut Expense) -> Result<(), RepositoryError> {
         if expense.id().is_none() {
+            let updated_at = next_updated_at(None);
+            
             // Insert new expense
-            let result = self.conn.execute(
-                "INSERT INTO expenses (amount, category, category_description, date, description) 
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![
-                    expense.amount(),
-                    expense.category().name(),
-                    expense.category().description(),
-                    expense.date().to_string(),
-                    expense.description(),
-                ],
-            )?;
+            let result = self.conn.prepare_cached(
+                "INSERT INTO expenses (amount, category, category_description, date, description, currency, secondary_category, account, updated_at) 
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+            )?.execute(params![
+                expense.amount().to_f64(),
+                expense.category().name(),
+                expense.category().description(),
+                expense.date().to_string(),
+                expense.description(),
+                expense.currency(),
+                expense.secondary_category(),
+                expense.account(),
+                updated_at.format(UPDATED_AT_FORMAT).to_string(),
+            ])?;
             
             if result > 0 {
                 // Get the last inserted ID
                 let id = self.conn.last_insert_rowid();
                 expense.set_id(id);
+                expense.set_updated_at(Some(updated_at));
+                self.record_changes(id, "insert", None, Some(expense))?;
             }
         } else {
-            // Update existing expense
-            self.conn.execute(
+            let id = expense.id().unwrap();
+            let previous = if self.audit { self.get_by_id(id)? } else { None };
+            
+            let updated_at = next_updated_at(expense.updated_at());
+            
+            // Update existing expense, unless its row was written since it was loaded
+            let affected = self.conn.prepare_cached(
                 "UPDATE expenses SET 
                  amount = ?1, 
                  category = ?2, 
                  category_description = ?3,
                  date = ?4, 
-                 description = ?5 
-                 WHERE id = ?6",
-                params![
-                    expense.amount(),
-                    expense.category().name(),
-                    expense.category().description(),
-                    expense.date().to_string(),
-                    expense.description(),
-                    expense.id().unwrap(),
-                ],
-            )?;
+                 description = ?5,
+                 currency = ?6,
+                 secondary_category = ?7,
+                 account = ?8,
+                 updated_at = ?10 
+                 WHERE id = ?9 AND updated_at IS ?11"
+            )?.execute(params![
+                expense.amount().to_f64(),
+                expense.category().name(),
+                expense.category().description(),
+                expense.date().to_string(),
+                expense.description(),
+                expense.currency(),
+                expense.secondary_category(),
+                expense.account(),
+                id,
+                updated_at.format(UPDATED_AT_FORMAT).to_string(),
+                expense.updated_at().map(|t| t.format(UPDATED_AT_FORMAT).to_string()),
+            ])?;
+            
+            if affected == 0 {
+                let exists: bool = self.conn.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM expenses WHERE id = ?1)",
+                    params![id],
+                    |row| row.get(0),
+                )?;
+                
+                if exists {
+                    return Err(RepositoryError::Conflict(format!(
+                        "Expense {} was changed by someone else since it was loaded; reload it and try again", id
+                    )));
+                }
+                
+                return Ok(());
+            }
+            
+            expense.set_updated_at(Some(updated_at));
+            
+            self.record_changes(id, "update", previous.as_ref(), Some(expense))?;
         }
         
         Ok(())
     }
     
+    fn save_batch(&self, expenses: &mut [Expense]) -> Result<(), RepositoryError> {
+        let tx = self.conn.unchecked_transaction()?;
+        let before: Vec<_> = expenses.iter().map(|e| (e.id(), e.updated_at())).collect();
+        
+        let result = expenses.iter_mut()
+            .try_for_each(|expense| self.save(expense))
+            .and_then(|()| Ok(tx.commit()?));
+        
+        // The rows were rolled back, so the IDs and timestamps handed out no longer exist
+        if result.is_err() {
+            for (expense, (id, updated_at)) in expenses.iter_mut().zip(before) {
+                match id {
+                    Some(id) => expense.set_id(id),
+                    None => expense.clear_id(),
+                }
+                expense.set_updated_at(updated_at);
+            }
+        }
+        
+        result
+    }
+    
     fn get_by_id(&self, id: i64) -> Result<Option<Expense>, RepositoryError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, amount, category, category_description, date, description 
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
-             WHERE id = ?1"
+             WHERE id = ?1 AND deleted_at IS NULL"
         )?;
         
-        let expense_result = stmt.query_row(
-            params![id],
-            |row| {
-                let id = row.get(0)?;
-                let amount = row.get(1)?;
-                let category_name: String = row.get(2)?;
-                let category_description: Option<String> = row.get(3)?;
-                let date_str: String = row.get(4)?;
-                let description: String = row.get(5)?;
-                
-                let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(4, "Invalid date format".to_string(), Type::Text))?;
-                
-                let category = Category::new(
-                    &category_name, 
-                    category_description.as_deref()
-                ).map_err(|_| rusqlite::Error::InvalidColumnType(2, "Invalid category".to_string(), Type::Text))?;
-                
-                let expense = Expense::new(amount, category, date, description).with_id(id);
-                
-                Ok(expense)
-            },
-        );
+        let expense_result = stmt.query_row(params![id], Self::expense_from_row);
         
         match expense_result {
             Ok(expense) => Ok(Some(expense)),
@@ -116,33 +410,29 @@ impl ExpenseRepository for SqliteExpenseRepository {
         }
     }
     
+    fn exists(&self, amount: Decimal, category: &str, date: NaiveDate, description: &str) -> Result<bool, RepositoryError> {
+        let cents = (amount * Decimal::ONE_HUNDRED).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero).to_i64();
+        
+        let exists = self.conn.prepare_cached(
+            "SELECT EXISTS(
+                 SELECT 1 FROM expenses 
+                 WHERE CAST(ROUND(amount * 100) AS INTEGER) = ?1 AND category = ?2 AND date = ?3 
+                   AND description = ?4 AND deleted_at IS NULL
+             )"
+        )?.query_row(params![cents, category, date.to_string(), description], |row| row.get(0))?;
+        
+        Ok(exists)
+    }
+    
     fn get_all(&self) -> Result<Vec<Expense>, RepositoryError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, amount, category, category_description, date, description 
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
+             WHERE deleted_at IS NULL 
              ORDER BY date DESC"
         )?;
         
-        let expense_iter = stmt.query_map([], |row| {
-            let id = row.get(0)?;
-            let amount = row.get(1)?;
-            let category_name: String = row.get(2)?;
-            let category_description: Option<String> = row.get(3)?;
-            let date_str: String = row.get(4)?;
-            let description: String = row.get(5)?;
-            
-            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .map_err(|_| rusqlite::Error::InvalidColumnType(4, "Invalid date format".to_string(), Type::Text))?;
-            
-            let category = Category::new(
-                &category_name, 
-                category_description.as_deref()
-            ).map_err(|_| rusqlite::Error::InvalidColumnType(2, "Invalid category".to_string(), Type::Text))?;
-            
-            let expense = Expense::new(amount, category, date, description).with_id(id);
-            
-            Ok(expense)
-        })?;
+        let expense_iter = stmt.query_map([], Self::expense_from_row)?;
         
         let mut expenses = Vec::new();
         for expense_result in expense_iter {
@@ -152,34 +442,18 @@ impl ExpenseRepository for SqliteExpenseRepository {
         Ok(expenses)
     }
     
-    fn get_by_category(&self, category_name: &str) -> Result<Vec<Expense>, RepositoryError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, amount, category, category_description, date, description 
+    fn get_paged(&self, limit: usize, offset: usize) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
-             WHERE category = ?1 
-             ORDER BY date DESC"
+             WHERE deleted_at IS NULL 
+             ORDER BY date DESC, id DESC 
+             LIMIT ?1 OFFSET ?2"
         )?;
         
-        let expense_iter = stmt.query_map(params![category_name], |row| {
-            let id = row.get(0)?;
-            let amount = row.get(1)?;
-            let category_name: String = row.get(2)?;
-            let category_description: Option<String> = row.get(3)?;
-            let date_str: String = row.get(4)?;
-            let description: String = row.get(5)?;
-            
-            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .map_err(|_| rusqlite::Error::InvalidColumnType(4, "Invalid date format".to_string(), Type::Text))?;
-            
-            let category = Category::new(
-                &category_name, 
-                category_description.as_deref()
-            ).map_err(|_| rusqlite::Error::InvalidColumnType(2, "Invalid category".to_string(), Type::Text))?;
-            
-            let expense = Expense::new(amount, category, date, description).with_id(id);
-            
-            Ok(expense)
-        })?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+        let expense_iter = stmt.query_map(params![limit, offset], Self::expense_from_row)?;
         
         let mut expenses = Vec::new();
         for expense_result in expense_iter {
@@ -189,34 +463,25 @@ impl ExpenseRepository for SqliteExpenseRepository {
         Ok(expenses)
     }
     
-    fn get_by_date_range(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Expense>, RepositoryError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, amount, category, category_description, date, description 
+    fn count(&self) -> Result<usize, RepositoryError> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM expenses WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        
+        Ok(count as usize)
+    }
+    
+    fn get_added_after(&self, after_id: i64) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
-             WHERE date >= ?1 AND date <= ?2 
-             ORDER BY date DESC"
+             WHERE id > ?1 AND deleted_at IS NULL 
+             ORDER BY id"
         )?;
         
-        let expense_iter = stmt.query_map(params![start.to_string(), end.to_string()], |row| {
-            let id = row.get(0)?;
-            let amount = row.get(1)?;
-            let category_name: String = row.get(2)?;
-            let category_description: Option<String> = row.get(3)?;
-            let date_str: String = row.get(4)?;
-            let description: String = row.get(5)?;
-            
-            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .map_err(|_| rusqlite::Error::InvalidColumnType(4, "Invalid date format".to_string(), Type::Text))?;
-            
-            let category = Category::new(
-                &category_name, 
-                category_description.as_deref()
-            ).map_err(|_| rusqlite::Error::InvalidColumnType(2, "Invalid category".to_string(), Type::Text))?;
-            
-            let expense = Expense::new(amount, category, date, description).with_id(id);
-            
-            Ok(expense)
-        })?;
+        let expense_iter = stmt.query_map(params![after_id], Self::expense_from_row)?;
         
         let mut expenses = Vec::new();
         for expense_result in expense_iter {
@@ -226,145 +491,1161 @@ impl ExpenseRepository for SqliteExpenseRepository {
         Ok(expenses)
     }
     
-    fn delete(&self, id: i64) -> Result<bool, RepositoryError> {
-        let affected = self.conn.execute("DELETE FROM expenses WHERE id = ?1", params![id])?;
-        Ok(affected > 0)
+    fn get_recent(&self, limit: usize) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE deleted_at IS NULL 
+             ORDER BY date DESC, id DESC 
+             LIMIT ?1"
+        )?;
+        
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let expense_iter = stmt.query_map(params![limit], Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
+        
+        Ok(expenses)
     }
     
-    fn get_category_total(&self, category_name: &str, start: NaiveDate, end: NaiveDate) -> Result<f64, RepositoryError> {
-        let total: f64 = self.conn.query_row(
-            "SELECT COALESCE(SUM(amount), 0.0) 
+    fn get_adjacent(&self, id: i64) -> Result<(Option<Expense>, Option<Expense>), RepositoryError> {
+        let date = self.get_by_id_required(id)?.date().to_string();
+        
+        let neighbour = |sql: &str| -> Result<Option<Expense>, RepositoryError> {
+            match self.conn.query_row(sql, params![date, id], Self::expense_from_row) {
+                Ok(expense) => Ok(Some(expense)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(RepositoryError::DatabaseError(e)),
+            }
+        };
+        
+        let previous = neighbour(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
-             WHERE category = ?1 AND date >= ?2 AND date <= ?3",
-            params![category_name, start.to_string(), end.to_string()],
-            |row| row.get(0)
+             WHERE (date < ?1 OR (date = ?1 AND id < ?2)) AND deleted_at IS NULL 
+             ORDER BY date DESC, id DESC 
+             LIMIT 1"
+        )?;
+        let next = neighbour(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE (date > ?1 OR (date = ?1 AND id > ?2)) AND deleted_at IS NULL 
+             ORDER BY date, id 
+             LIMIT 1"
         )?;
         
-        Ok(total)
+        Ok((previous, next))
     }
     
-    fn get_monthly_category_averages(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, f64)>, RepositoryError> {
-        // Calculate number of months in the date range
-        let months = (end.year() * 12 + end.month() as i32) - (start.year() * 12 + start.month() as i32) + 1;
+    fn get_future_dated(&self, today: NaiveDate) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE date > ?1 AND deleted_at IS NULL 
+             ORDER BY date, id"
+        )?;
         
-        if months <= 0 {
-            return Ok(Vec::new());
+        let expense_iter = stmt.query_map(params![today.to_string()], Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
         }
         
-        // Get total per category
-        let mut stmt = self.conn.prepare(
-            "SELECT category, SUM(amount) 
+        Ok(expenses)
+    }
+    
+    fn get_by_category(&self, category_name: &str) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
              FROM expenses 
-             WHERE date >= ?1 AND date <= ?2 
-             GROUP BY category"
+             WHERE category = ?1 AND deleted_at IS NULL 
+             ORDER BY date DESC"
         )?;
         
-        let rows = stmt.query_map(
-            params![start.to_string(), end.to_string()],
-            |row| {
-                let category: String = row.get(0)?;
-                let total: f64 = row.get(1)?;
-                Ok((category, total))
-            },
-        )?;
+        let expense_iter = stmt.query_map(params![category_name], Self::expense_from_row)?;
         
-        let mut averages = Vec::new();
-        for result in rows {
-            let (category, total) = result?;
-            let monthly_avg = total / (months as f64);
-            averages.push((category, monthly_avg));
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
         }
         
-        Ok(averages)
+        Ok(expenses)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::NaiveDate;
     
-    fn create_test_repository() -> SqliteExpenseRepository {
-        SqliteExpenseRepository::new_in_memory().unwrap()
+    fn get_distinct_categories(&self) -> Result<Vec<String>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached("SELECT DISTINCT category FROM expenses ORDER BY category")?;
+        
+        let categories = stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        
+        Ok(categories)
     }
     
-    fn create_test_expense(amount: f64, category_name: &str, date_str: &str, description: &str) -> Expense {
-        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
-        let category = Category::new(category_name, None).unwrap();
-        Expense::new(amount, category, date, description.to_string())
+    fn get_by_account(&self, account: &str) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE account = ?1 AND deleted_at IS NULL 
+             ORDER BY date DESC"
+        )?;
+        
+        let expense_iter = stmt.query_map(params![account], Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
+        
+        Ok(expenses)
     }
     
-    #[test]
-    fn test_save_and_get_expense() {
-        let repo = create_test_repository();
-        let mut expense = create_test_expense(42.50, "Food", "2025-04-11", "Weekly shopping");
-        
-        // Save the expense - should assign an ID
-        repo.save(&mut expense).unwrap();
+    fn get_by_description_pattern(&self, pattern: &str) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE description GLOB ?1 AND deleted_at IS NULL 
+             ORDER BY date DESC"
+        )?;
         
-        // Verify ID was assigned
-        assert!(expense.id().is_some());
+        let expense_iter = stmt.query_map(params![pattern], Self::expense_from_row)?;
         
-        // Fetch the expense by ID
-        let fetched = repo.get_by_id(expense.id().unwrap()).unwrap().unwrap();
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
         
-        // Verify fetched data matches original
-        assert_eq!(fetched.id(), expense.id());
-        assert_eq!(fetched.amount(), 42.50);
-        assert_eq!(fetched.category().name(), "Food");
-        assert_eq!(fetched.date().to_string(), "2025-04-11");
-        assert_eq!(fetched.description(), "Weekly shopping");
+        Ok(expenses)
     }
     
-    #[test]
-    fn test_update_expense() {
-        let repo = create_test_repository();
-        let mut expense = create_test_expense(42.50, "Food", "2025-04-11", "Weekly shopping");
+    fn get_by_categories(&self, category_names: &[String]) -> Result<Vec<Expense>, RepositoryError> {
+        if category_names.is_empty() {
+            return Ok(Vec::new());
+        }
         
-        // Save the expense - should assign an ID
-        repo.save(&mut expense).unwrap();
-        let id = expense.id().unwrap();
+        let placeholders = vec!["?"; category_names.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE category IN ({}) AND deleted_at IS NULL 
+             ORDER BY date DESC",
+            placeholders
+        ))?;
         
-        // Update the expense
+        let expense_iter = stmt.query_map(params_from_iter(category_names), Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
+        
+        Ok(expenses)
+    }
+    
+    fn get_by_date_range(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             ORDER BY date DESC"
+        )?;
+        
+        let expense_iter = stmt.query_map(params![start.to_string(), end.to_string()], Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
+        
+        Ok(expenses)
+    }
+    
+    fn delete(&self, id: i64) -> Result<bool, RepositoryError> {
+        let previous = if self.audit { self.get_by_id(id)? } else { None };
+        let deleted_at = Local::now().naive_local().format(TIMESTAMP_FORMAT).to_string();
+        
+        let affected = self.conn.execute(
+            "UPDATE expenses SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, deleted_at],
+        )?;
+        
+        if affected > 0 {
+            self.record_changes(id, "delete", previous.as_ref(), None)?;
+        }
+        
+        Ok(affected > 0)
+    }
+    
+    fn get_deleted(&self) -> Result<Vec<Expense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+             FROM expenses 
+             WHERE deleted_at IS NOT NULL 
+             ORDER BY deleted_at DESC, id DESC"
+        )?;
+        
+        let expense_iter = stmt.query_map([], Self::expense_from_row)?;
+        
+        let mut expenses = Vec::new();
+        for expense_result in expense_iter {
+            expenses.push(expense_result?);
+        }
+        
+        Ok(expenses)
+    }
+    
+    fn restore_deleted(&self, id: i64) -> Result<bool, RepositoryError> {
+        let affected = self.conn.execute(
+            "UPDATE expenses SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?;
+        
+        if affected > 0 && self.audit {
+            let restored = self.get_by_id(id)?;
+            self.record_changes(id, "restore", None, restored.as_ref())?;
+        }
+        
+        Ok(affected > 0)
+    }
+    
+    fn purge(&self, id: i64) -> Result<bool, RepositoryError> {
+        let previous = if self.audit { self.get_by_id(id)? } else { None };
+        
+        let affected = self.conn.execute("DELETE FROM expenses WHERE id = ?1", params![id])?;
+        
+        if affected > 0 {
+            self.record_changes(id, "purge", previous.as_ref(), None)?;
+        }
+        
+        Ok(affected > 0)
+    }
+    
+    fn empty_trash(&self) -> Result<usize, RepositoryError> {
+        let trashed = if self.audit { self.get_deleted()? } else { Vec::new() };
+        
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = tx.execute("DELETE FROM expenses WHERE deleted_at IS NOT NULL", [])?;
+        
+        // Each purged row is recorded as `purge` would record it
+        for expense in &trashed {
+            if let Some(id) = expense.id() {
+                self.record_changes(id, "purge", Some(expense), None)?;
+            }
+        }
+        
+        tx.commit()?;
+        
+        Ok(removed)
+    }
+    
+    fn get_total(&self, start: NaiveDate, end: NaiveDate) -> Result<Decimal, RepositoryError> {
+        let cents: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(CAST(ROUND(amount * 100) AS INTEGER)), 0) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL",
+            params![start.to_string(), end.to_string()],
+            |row| row.get(0)
+        )?;
+        
+        Ok(from_cents(cents))
+    }
+    
+    fn get_category_total(&self, category_name: &str, start: NaiveDate, end: NaiveDate) -> Result<Decimal, RepositoryError> {
+        let cents: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(CAST(ROUND(amount * 100) AS INTEGER)), 0) 
+             FROM expenses 
+             WHERE category = ?1 AND date >= ?2 AND date <= ?3 AND deleted_at IS NULL",
+            params![category_name, start.to_string(), end.to_string()],
+            |row| row.get(0)
+        )?;
+        
+        Ok(from_cents(cents))
+    }
+    
+    fn get_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        match self.rollup_months(start, end)? {
+            Some((first_month, last_month)) => self.rollup_category_totals(&first_month, &last_month),
+            None => self.live_category_totals(start, end),
+        }
+    }
+    
+    fn get_category_transaction_stats(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal, usize)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT category, SUM(CAST(ROUND(amount * 100) AS INTEGER)), COUNT(*) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY category"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let category: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                let count: i64 = row.get(2)?;
+                Ok((category, total, count as usize))
+            },
+        )?;
+        
+        let mut stats = Vec::new();
+        for result in rows {
+            stats.push(result?);
+        }
+        
+        Ok(stats)
+    }
+    
+    fn get_category_amount_stats(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, AmountStats)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT category, MIN(amount), MAX(amount) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY category"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let category: String = row.get(0)?;
+                let min = amount_column(row, 1)?;
+                let max = amount_column(row, 2)?;
+                Ok((category, min, max))
+            },
+        )?;
+        
+        let mut ranges = Vec::new();
+        for result in rows {
+            ranges.push(result?);
+        }
+        
+        // SQLite has no median aggregate, so fetch each category's amounts in order
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT amount 
+             FROM expenses 
+             WHERE category = ?1 AND date >= ?2 AND date <= ?3 AND deleted_at IS NULL 
+             ORDER BY amount"
+        )?;
+        
+        let mut stats = Vec::new();
+        for (category, min, max) in ranges {
+            let amounts = stmt.query_map(
+                params![category, start.to_string(), end.to_string()],
+                |row| amount_column(row, 0),
+            )?.collect::<Result<Vec<_>, _>>()?;
+            
+            let median = median(&amounts).unwrap_or(min);
+            stats.push((category, AmountStats { min, max, median }));
+        }
+        
+        Ok(stats)
+    }
+    
+    fn get_secondary_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT secondary_category, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE secondary_category IS NOT NULL AND date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY secondary_category"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let category: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                Ok((category, total))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn get_account_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT account, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE account IS NOT NULL AND date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY account"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let account: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                Ok((account, total))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn get_monthly_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<MonthlyTotals, RepositoryError> {
+        match self.rollup_months(start, end)? {
+            Some((first_month, last_month)) => self.rollup_monthly_totals(&first_month, &last_month),
+            None => self.live_monthly_totals(start, end),
+        }
+    }
+    
+    fn get_monthly_category_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<MonthlyCategoryTotals, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT CAST(strftime('%Y', date) AS INTEGER), CAST(strftime('%m', date) AS INTEGER), category, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY 1, 2, 3 
+             ORDER BY 1, 2, 3"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let year: i32 = row.get(0)?;
+                let month: u32 = row.get(1)?;
+                let category: String = row.get(2)?;
+                let total = from_cents(row.get(3)?);
+                Ok(((year, month), category, total))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn get_day_of_month_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(u32, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT CAST(strftime('%d', date) AS INTEGER), SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY 1 
+             ORDER BY 1"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| Ok((row.get(0)?, from_cents(row.get(1)?))),
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn get_monthly_category_averages(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(String, Decimal)>, RepositoryError> {
+        // Calculate number of months in the date range
+        let months = (end.year() * 12 + end.month() as i32) - (start.year() * 12 + start.month() as i32) + 1;
+        
+        if months <= 0 {
+            return Ok(Vec::new());
+        }
+        
+        // Get total per category
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT category, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY category"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let category: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                Ok((category, total))
+            },
+        )?;
+        
+        let mut averages = Vec::new();
+        for result in rows {
+            let (category, total) = result?;
+            let monthly_avg = total / Decimal::from(months);
+            averages.push((category, monthly_avg));
+        }
+        
+        Ok(averages)
+    }
+    
+    fn get_description_totals(&self, start: NaiveDate, end: NaiveDate, normalize: bool) -> Result<Vec<(String, Decimal, usize)>, RepositoryError> {
+        let key = if normalize { "LOWER(TRIM(description))" } else { "description" };
+        
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {key}, SUM(CAST(ROUND(amount * 100) AS INTEGER)), COUNT(*) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY {key} 
+             ORDER BY SUM(CAST(ROUND(amount * 100) AS INTEGER)) DESC"
+        ))?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let description: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                let count: i64 = row.get(2)?;
+                Ok((description, total, count as usize))
+            },
+        )?;
+        
+        let mut totals = Vec::new();
+        for result in rows {
+            totals.push(result?);
+        }
+        
+        Ok(totals)
+    }
+    
+    fn get_date_histogram(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(NaiveDate, Decimal)>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, SUM(CAST(ROUND(amount * 100) AS INTEGER)) 
+             FROM expenses 
+             WHERE date >= ?1 AND date <= ?2 AND deleted_at IS NULL 
+             GROUP BY date"
+        )?;
+        
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string()],
+            |row| {
+                let date: String = row.get(0)?;
+                let total = from_cents(row.get(1)?);
+                Ok((date, total))
+            },
+        )?;
+        
+        let mut daily_totals = std::collections::HashMap::new();
+        for result in rows {
+            let (date, total) = result?;
+            daily_totals.insert(date, total);
+        }
+        
+        // Fill in the days without expenses
+        let histogram = start.iter_days()
+            .take_while(|date| *date <= end)
+            .map(|date| {
+                let total = daily_totals.get(&date.to_string()).copied().unwrap_or(Decimal::ZERO);
+                (date, total)
+            })
+            .collect();
+        
+        Ok(histogram)
+    }
+    
+    fn rebuild_monthly_rollups(&self) -> Result<usize, RepositoryError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        schema::create_monthly_rollups(&tx)?;
+        tx.execute("DELETE FROM monthly_rollups", [])?;
+        let rebuilt = tx.execute(
+            "INSERT INTO monthly_rollups (month, category, total_cents, count) 
+             SELECT substr(date, 1, 7), category, SUM(CAST(ROUND(amount * 100) AS INTEGER)), COUNT(*) 
+             FROM expenses 
+             WHERE deleted_at IS NULL 
+             GROUP BY 1, 2",
+            [],
+        )?;
+        
+        tx.commit()?;
+        
+        Ok(rebuilt)
+    }
+    
+    fn get_history(&self, expense_id: i64) -> Result<Vec<AuditEntry>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT expense_id, operation, field, old_value, new_value, changed_at 
+             FROM audit_log 
+             WHERE expense_id = ?1 
+             ORDER BY id"
+        )?;
+        
+        let rows = stmt.query_map(params![expense_id], |row| {
+            let changed_at_str: String = row.get(5)?;
+            let changed_at = NaiveDateTime::parse_from_str(&changed_at_str, TIMESTAMP_FORMAT)
+                .map_err(|_| rusqlite::Error::InvalidColumnType(5, "Invalid timestamp format".to_string(), Type::Text))?;
+            
+            Ok(AuditEntry {
+                expense_id: row.get(0)?,
+                operation: row.get(1)?,
+                field: row.get(2)?,
+                old_value: row.get(3)?,
+                new_value: row.get(4)?,
+                changed_at,
+            })
+        })?;
+        
+        let mut history = Vec::new();
+        for entry in rows {
+            history.push(entry?);
+        }
+        
+        Ok(history)
+    }
+    
+    fn save_recurring(&self, recurring: &mut RecurringExpense) -> Result<(), RepositoryError> {
+        match recurring.id {
+            None => {
+                self.conn.execute(
+                    "INSERT INTO recurring (amount, category, day_of_month, description, last_applied) 
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![recurring.amount.to_f64(), recurring.category, recurring.day_of_month, recurring.description, recurring.last_applied],
+                )?;
+                
+                recurring.id = Some(self.conn.last_insert_rowid());
+            },
+            Some(id) => {
+                let affected = self.conn.execute(
+                    "UPDATE recurring 
+                     SET amount = ?1, category = ?2, day_of_month = ?3, description = ?4, last_applied = ?5 
+                     WHERE id = ?6",
+                    params![recurring.amount.to_f64(), recurring.category, recurring.day_of_month, recurring.description, recurring.last_applied, id],
+                )?;
+                
+                if affected == 0 {
+                    return Err(RepositoryError::NotFound(format!("No recurring expense found with ID {}", id)));
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    fn get_recurring(&self) -> Result<Vec<RecurringExpense>, RepositoryError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, amount, category, day_of_month, description, last_applied 
+             FROM recurring 
+             ORDER BY day_of_month, id"
+        )?;
+        
+        let rows = stmt.query_map([], |row| {
+            Ok(RecurringExpense {
+                id: row.get(0)?,
+                amount: amount_column(row, 1)?,
+                category: row.get(2)?,
+                day_of_month: row.get(3)?,
+                description: row.get(4)?,
+                last_applied: row.get(5)?,
+            })
+        })?;
+        
+        let mut recurring = Vec::new();
+        for template in rows {
+            recurring.push(template?);
+        }
+        
+        Ok(recurring)
+    }
+    
+    fn delete_recurring(&self, id: i64) -> Result<bool, RepositoryError> {
+        let affected = self.conn.execute("DELETE FROM recurring WHERE id = ?1", params![id])?;
+        
+        Ok(affected > 0)
+    }
+    
+    fn apply_recurring(&self, recurring_id: i64, month: &str, expense: &mut Expense) -> Result<bool, RepositoryError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        // Claiming the month first means a second run finds nothing left to apply
+        let claimed = self.conn.execute(
+            "UPDATE recurring SET last_applied = ?2 
+             WHERE id = ?1 AND (last_applied IS NULL OR last_applied < ?2)",
+            params![recurring_id, month],
+        )?;
+        
+        if claimed == 0 {
+            return Ok(false);
+        }
+        
+        self.save(expense)?;
+        tx.commit()?;
+        
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+    
+    fn create_test_repository() -> SqliteExpenseRepository {
+        SqliteExpenseRepository::new_in_memory().unwrap()
+    }
+    
+    fn create_test_expense(amount: Decimal, category_name: &str, date_str: &str, description: &str) -> Expense {
+        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
+        let category = Category::new(category_name, None).unwrap();
+        Expense::new(amount, category, date, description.to_string())
+    }
+    
+    #[test]
+    fn test_save_and_get_expense() {
+        let repo = create_test_repository();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        
+        // Save the expense - should assign an ID
+        repo.save(&mut expense).unwrap();
+        
+        // Verify ID was assigned
+        assert!(expense.id().is_some());
+        
+        // Fetch the expense by ID
+        let fetched = repo.get_by_id(expense.id().unwrap()).unwrap().unwrap();
+        
+        // Verify fetched data matches original
+        assert_eq!(fetched.id(), expense.id());
+        assert_eq!(fetched.amount(), dec!(42.50));
+        assert_eq!(fetched.category().name(), "Food");
+        assert_eq!(fetched.date().to_string(), "2025-04-11");
+        assert_eq!(fetched.description(), "Weekly shopping");
+    }
+    
+    #[test]
+    fn test_new_creates_missing_parent_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("deeper").join("expenses.db");
+        
+        let repo = SqliteExpenseRepository::new(&path).unwrap();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        repo.save(&mut expense).unwrap();
+        
+        assert!(path.exists());
+    }
+    
+    #[test]
+    fn test_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("expenses.db");
+        
+        let repo = SqliteExpenseRepository::new(&path).unwrap();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        repo.save(&mut expense).unwrap();
+        drop(repo);
+        
+        let repo = SqliteExpenseRepository::open_read_only(&path).unwrap();
+        assert_eq!(repo.get_all().unwrap().len(), 1);
+        
+        let mut expense = create_test_expense(dec!(1.00), "Food", "2025-04-11", "Gum");
+        assert!(repo.save(&mut expense).is_err());
+        
+        assert!(SqliteExpenseRepository::open_read_only(dir.path().join("missing.db")).is_err());
+    }
+    
+    #[test]
+    fn test_save_expense_currency() {
+        let repo = create_test_repository();
+        let mut expense = create_test_expense(dec!(9.99), "Subscriptions", "2025-04-11", "Streaming");
+        expense.set_currency(Some("USD".to_string()));
+        
+        repo.save(&mut expense).unwrap();
+        
+        let fetched = repo.get_by_id(expense.id().unwrap()).unwrap().unwrap();
+        assert_eq!(fetched.currency(), Some("USD"));
+    }
+    
+    #[test]
+    fn test_schema_upgrade_adds_currency_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE expenses (
+                id INTEGER PRIMARY KEY,
+                amount REAL NOT NULL,
+                category TEXT NOT NULL,
+                category_description TEXT,
+                date TEXT NOT NULL,
+                description TEXT NOT NULL
+            )",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO expenses (amount, category, date, description) VALUES (5.0, 'Food', '2025-04-11', 'Old row')",
+            [],
+        ).unwrap();
+        
+        schema::initialize_schema(&conn).unwrap();
+        let repo = SqliteExpenseRepository { conn, audit: false };
+        
+        let expenses = repo.get_all().unwrap();
+        assert_eq!(expenses.len(), 1);
+        assert_eq!(expenses[0].currency(), None);
+    }
+    
+    #[test]
+    fn test_save_batch() {
+        let repo = create_test_repository();
+        let mut expenses = vec![
+            create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping"),
+            create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Monthly rent"),
+        ];
+        
+        repo.save_batch(&mut expenses).unwrap();
+        
+        assert!(expenses.iter().all(|e| e.id().is_some()));
+        assert_eq!(repo.get_all().unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_save_all_assigns_distinct_ids() {
+        let repo = create_test_repository();
+        let mut expenses: Vec<_> = (1..=100)
+            .map(|n| create_test_expense(Decimal::from(n), "Food", "2025-04-11", "Groceries"))
+            .collect();
+        
+        repo.save_all(&mut expenses).unwrap();
+        
+        let ids: std::collections::HashSet<_> = expenses.iter().filter_map(Expense::id).collect();
+        assert_eq!(ids.len(), 100);
+        assert_eq!(repo.count().unwrap(), 100);
+    }
+    
+    #[test]
+    fn test_save_batch_rolls_back_on_failure() {
+        let repo = create_test_repository();
+        let mut saved = create_test_expense(dec!(9.99), "Fun", "2025-04-02", "Cinema");
+        repo.save(&mut saved).unwrap();
+        let stale = saved.clone();
+        saved.set_amount(dec!(12.50)).unwrap();
+        repo.save(&mut saved).unwrap();
+        
+        let mut expenses = vec![
+            create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping"),
+            create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Monthly rent"),
+            // Saving a copy that was changed since it was loaded conflicts
+            stale.clone(),
+        ];
+        
+        assert!(matches!(repo.save_batch(&mut expenses), Err(RepositoryError::Conflict(_))));
+        
+        assert_eq!(expenses.iter().map(Expense::id).collect::<Vec<_>>(), vec![None, None, stale.id()]);
+        assert_eq!(expenses[2].updated_at(), stale.updated_at());
+        assert_eq!(repo.count().unwrap(), 1);
+    }
+    
+    #[test]
+    fn test_exists_compares_amounts_in_cents() {
+        let repo = create_test_repository();
+        let date = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        
+        let mut expense = create_test_expense(dec!(0.1) + dec!(0.2), "Food", "2025-04-11", "Snack");
+        repo.save(&mut expense).unwrap();
+        
+        // 0.1 + 0.2 isn't exactly 0.3 as a float, but is the same number of cents
+        assert!(repo.exists(dec!(0.3), "Food", date, "Snack").unwrap());
+        assert!(!repo.exists(dec!(0.31), "Food", date, "Snack").unwrap());
+        assert!(!repo.exists(dec!(0.29), "Food", date, "Snack").unwrap());
+        
+        assert!(!repo.exists(dec!(0.3), "Drinks", date, "Snack").unwrap());
+        assert!(!repo.exists(dec!(0.3), "Food", date.succ_opt().unwrap(), "Snack").unwrap());
+        assert!(!repo.exists(dec!(0.3), "Food", date, "Snacks").unwrap());
+        
+        repo.delete(expense.id().unwrap()).unwrap();
+        assert!(!repo.exists(dec!(0.3), "Food", date, "Snack").unwrap());
+    }
+    
+    #[test]
+    fn test_update_expense() {
+        let repo = create_test_repository();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        
+        // Save the expense - should assign an ID
+        repo.save(&mut expense).unwrap();
+        let id = expense.id().unwrap();
+        
+        // Update the expense
         let category = Category::new("Groceries", Some("Supermarket")).unwrap();
         expense.set_category(category);
-        expense.set_amount(55.75).unwrap();
+        expense.set_amount(dec!(55.75)).unwrap();
+        
+        // Save the updated expense
+        repo.save(&mut expense).unwrap();
+        
+        // Fetch the expense by ID
+        let fetched = repo.get_by_id(id).unwrap().unwrap();
+        
+        // Verify updated data
+        assert_eq!(fetched.amount(), dec!(55.75));
+        assert_eq!(fetched.category().name(), "Groceries");
+        assert_eq!(fetched.category().description(), Some("Supermarket"));
+    }
+    
+    #[test]
+    fn test_stale_update_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("expenses.db");
+        let ours = SqliteExpenseRepository::new(&path).unwrap();
+        let theirs = SqliteExpenseRepository::new(&path).unwrap();
+        
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        ours.save(&mut expense).unwrap();
+        let id = expense.id().unwrap();
+        
+        // Another process loads and edits the same expense first
+        let mut their_copy = theirs.get_by_id(id).unwrap().unwrap();
+        their_copy.set_description("Weekly shopping and snacks".to_string());
+        theirs.save(&mut their_copy).unwrap();
+        
+        expense.set_amount(dec!(50.0)).unwrap();
+        let result = ours.save(&mut expense);
+        assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+        
+        let stored = ours.get_by_id(id).unwrap().unwrap();
+        assert_eq!(stored.amount(), dec!(42.50));
+        assert_eq!(stored.description(), "Weekly shopping and snacks");
+        
+        // Reloading picks up their change and lets ours through
+        let mut reloaded = stored;
+        reloaded.set_amount(dec!(50.0)).unwrap();
+        ours.save(&mut reloaded).unwrap();
+        assert_eq!(theirs.get_by_id(id).unwrap().unwrap().amount(), dec!(50.0));
+    }
+    
+    #[test]
+    fn test_get_by_category() {
+        let repo = create_test_repository();
+        
+        // Create and save expenses with different categories
+        let mut food_expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        let mut rent_expense = create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Monthly rent");
+        let mut utility_expense = create_test_expense(dec!(85.75), "Utilities", "2025-04-05", "Electricity");
+        
+        repo.save(&mut food_expense).unwrap();
+        repo.save(&mut rent_expense).unwrap();
+        repo.save(&mut utility_expense).unwrap();
+        
+        // Get expenses by category
+        let food_expenses = repo.get_by_category("Food").unwrap();
+        let housing_expenses = repo.get_by_category("Housing").unwrap();
+        
+        // Verify category filtering
+        assert_eq!(food_expenses.len(), 1);
+        assert_eq!(food_expenses[0].amount(), dec!(42.50));
+        
+        assert_eq!(housing_expenses.len(), 1);
+        assert_eq!(housing_expenses[0].amount(), dec!(1200.00));
+    }
+    
+    #[test]
+    fn test_account_round_trip_and_totals() {
+        let repo = create_test_repository();
+        
+        let mut checking = create_test_expense(dec!(40.00), "Food", "2025-04-03", "Groceries");
+        checking.set_account(Some("Checking".to_string()));
+        let mut card = create_test_expense(dec!(25.00), "Food", "2025-04-04", "Takeaway");
+        card.set_account(Some("Credit card".to_string()));
+        let mut more_card = create_test_expense(dec!(15.00), "Transport", "2025-04-05", "Bus pass");
+        more_card.set_account(Some("Credit card".to_string()));
+        let mut cash = create_test_expense(dec!(3.00), "Food", "2025-04-05", "Coffee");
+        
+        for expense in [&mut checking, &mut card, &mut more_card, &mut cash] {
+            repo.save(expense).unwrap();
+        }
+        
+        assert_eq!(repo.get_by_id(card.id().unwrap()).unwrap().unwrap().account(), Some("Credit card"));
+        assert_eq!(repo.get_by_account("Credit card").unwrap().len(), 2);
+        assert!(repo.get_by_account("Savings").unwrap().is_empty());
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        let mut totals = repo.get_account_totals(start, end).unwrap();
+        totals.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(totals, vec![("Checking".to_string(), dec!(40.00)), ("Credit card".to_string(), dec!(40.00))]);
+    }
+    
+    #[test]
+    fn test_date_range_uses_index() {
+        let repo = create_test_repository();
+        
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let mut expenses: Vec<Expense> = (0..3000)
+            .map(|day| {
+                let date = start + chrono::Duration::days(day % 1500);
+                create_test_expense(Decimal::from(day + 1), ["Food", "Rent", "Fun"][day as usize % 3], &date.to_string(), "Bulk")
+            })
+            .collect();
+        repo.save_batch(&mut expenses).unwrap();
+        
+        let from = NaiveDate::from_ymd_opt(2021, 3, 15).unwrap();
+        let to = NaiveDate::from_ymd_opt(2021, 6, 30).unwrap();
+        let mut expected: Vec<_> = expenses.iter()
+            .filter(|e| *e.date() >= from && *e.date() <= to)
+            .filter_map(Expense::id)
+            .collect();
+        let mut found: Vec<_> = repo.get_by_date_range(from, to).unwrap().iter().filter_map(Expense::id).collect();
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+        assert_eq!(found.len(), 216);
+        
+        let plan: String = repo.conn.query_row(
+            "EXPLAIN QUERY PLAN SELECT id FROM expenses WHERE date >= ?1 AND date <= ?2",
+            params![from.to_string(), to.to_string()],
+            |row| row.get(3),
+        ).unwrap();
+        assert!(plan.contains("idx_expenses_date"), "{}", plan);
+    }
+    
+    // Timing only; run with `cargo test bulk_insert_and_read -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bulk_insert_and_read_10k() {
+        let repo = create_test_repository();
+        let start = std::time::Instant::now();
+        
+        for day in 0..10_000 {
+            let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap() + chrono::Duration::days(day % 1500);
+            let mut expense = create_test_expense(Decimal::from(day + 1), "Food", &date.to_string(), "Bulk");
+            repo.save(&mut expense).unwrap();
+        }
+        let inserted = start.elapsed();
+        
+        for id in 1..=10_000 {
+            assert!(repo.get_by_id(id).unwrap().is_some());
+        }
+        let read = start.elapsed() - inserted;
         
-        // Save the updated expense
-        repo.save(&mut expense).unwrap();
+        // The same reads, preparing the statement every time as before
+        let uncached = std::time::Instant::now();
+        for id in 1..=10_000i64 {
+            let mut stmt = repo.conn.prepare(
+                "SELECT id, amount, category, category_description, date, description, currency, secondary_category, account, updated_at 
+                 FROM expenses 
+                 WHERE id = ?1 AND deleted_at IS NULL"
+            ).unwrap();
+            stmt.query_row(params![id], SqliteExpenseRepository::expense_from_row).unwrap();
+        }
         
-        // Fetch the expense by ID
-        let fetched = repo.get_by_id(id).unwrap().unwrap();
+        println!("10k inserts: {:?}, 10k cached reads: {:?}, 10k uncached reads: {:?}", inserted, read, uncached.elapsed());
+    }
+    
+    #[test]
+    fn test_get_recent() {
+        let repo = create_test_repository();
         
-        // Verify updated data
-        assert_eq!(fetched.amount(), 55.75);
-        assert_eq!(fetched.category().name(), "Groceries");
-        assert_eq!(fetched.category().description(), Some("Supermarket"));
+        for (amount, date) in [(dec!(1.0), "2025-04-10"), (dec!(2.0), "2025-04-11"), (dec!(3.0), "2025-04-09"), (dec!(4.0), "2025-04-11")] {
+            let mut expense = create_test_expense(amount, "Food", date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        // Same-day expenses come newest ID first
+        let amounts: Vec<Decimal> = repo.get_recent(3).unwrap().iter().map(|e| e.amount()).collect();
+        assert_eq!(amounts, vec![dec!(4.0), dec!(2.0), dec!(1.0)]);
+        
+        assert_eq!(repo.get_recent(10).unwrap().len(), 4);
+        assert!(repo.get_recent(0).unwrap().is_empty());
     }
     
     #[test]
-    fn test_get_by_category() {
+    fn test_get_paged() {
         let repo = create_test_repository();
         
-        // Create and save expenses with different categories
-        let mut food_expense = create_test_expense(42.50, "Food", "2025-04-11", "Weekly shopping");
-        let mut rent_expense = create_test_expense(1200.00, "Housing", "2025-04-01", "Monthly rent");
-        let mut utility_expense = create_test_expense(85.75, "Utilities", "2025-04-05", "Electricity");
+        for (amount, date) in [(dec!(1.0), "2025-04-10"), (dec!(2.0), "2025-04-11"), (dec!(3.0), "2025-04-09"), (dec!(4.0), "2025-04-11")] {
+            let mut expense = create_test_expense(amount, "Food", date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        let trashed = repo.get_recent(1).unwrap()[0].id().unwrap();
+        repo.delete(trashed).unwrap();
+        
+        let page = |limit, offset| repo.get_paged(limit, offset).unwrap().iter().map(|e| e.amount()).collect::<Vec<_>>();
+        assert_eq!(page(2, 0), vec![dec!(2.0), dec!(1.0)]);
+        assert_eq!(page(2, 2), vec![dec!(3.0)]);
+        assert!(page(2, 4).is_empty());
+        assert_eq!(repo.count().unwrap(), 3);
+    }
+    
+    #[test]
+    fn test_get_future_dated() {
+        let repo = create_test_repository();
+        
+        for (amount, date) in [(dec!(1.0), "2025-04-11"), (dec!(2.0), "2052-04-15"), (dec!(3.0), "2025-04-12"), (dec!(4.0), "2024-01-01")] {
+            let mut expense = create_test_expense(amount, "Food", date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let today = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+        let amounts: Vec<Decimal> = repo.get_future_dated(today).unwrap().iter().map(|e| e.amount()).collect();
+        assert_eq!(amounts, vec![dec!(3.0), dec!(2.0)]);
+    }
+    
+    #[test]
+    fn test_get_by_description_pattern() {
+        let repo = create_test_repository();
+        
+        for (amount, description) in [(dec!(3.50), "coffee beans"), (dec!(2.80), "Coffee to go"), (dec!(18.00), "uber home"), (dec!(12.00), "Uber to airport"), (dec!(4.00), "bus")] {
+            let mut expense = create_test_expense(amount, "Misc", "2025-04-11", description);
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let descriptions = |pattern: &str| {
+            let mut found: Vec<String> = repo.get_by_description_pattern(pattern).unwrap()
+                .iter().map(|e| e.description().to_string()).collect();
+            found.sort();
+            found
+        };
+        
+        // Glob matching is case-sensitive and anchored to the whole description
+        assert_eq!(descriptions("coffee*"), vec!["coffee beans"]);
+        assert_eq!(descriptions("*ber*"), vec!["Uber to airport", "uber home"]);
+        assert_eq!(descriptions("bu?"), vec!["bus"]);
+        assert_eq!(descriptions("?ber *"), vec!["Uber to airport", "uber home"]);
+        assert!(descriptions("bus?").is_empty());
+        assert!(descriptions("coffee").is_empty());
+    }
+    
+    #[test]
+    fn test_get_added_after() {
+        let repo = create_test_repository();
+        
+        let mut first = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        let mut second = create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Monthly rent");
+        let mut third = create_test_expense(dec!(85.75), "Utilities", "2025-04-05", "Electricity");
+        
+        repo.save(&mut first).unwrap();
+        repo.save(&mut second).unwrap();
+        repo.save(&mut third).unwrap();
+        
+        let added = repo.get_added_after(first.id().unwrap()).unwrap();
+        
+        let ids: Vec<_> = added.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec![second.id(), third.id()]);
+        assert!(repo.get_added_after(third.id().unwrap()).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_get_by_categories() {
+        let repo = create_test_repository();
+        
+        let mut food_expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        let mut rent_expense = create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Monthly rent");
+        let mut utility_expense = create_test_expense(dec!(85.75), "Utilities", "2025-04-05", "Electricity");
         
         repo.save(&mut food_expense).unwrap();
         repo.save(&mut rent_expense).unwrap();
         repo.save(&mut utility_expense).unwrap();
         
-        // Get expenses by category
-        let food_expenses = repo.get_by_category("Food").unwrap();
-        let housing_expenses = repo.get_by_category("Housing").unwrap();
+        let expenses = repo.get_by_categories(&["Food".to_string(), "Utilities".to_string()]).unwrap();
         
-        // Verify category filtering
-        assert_eq!(food_expenses.len(), 1);
-        assert_eq!(food_expenses[0].amount(), 42.50);
+        assert_eq!(expenses.len(), 2);
+        assert!(expenses.iter().all(|e| e.category().name() != "Housing"));
         
-        assert_eq!(housing_expenses.len(), 1);
-        assert_eq!(housing_expenses[0].amount(), 1200.00);
+        assert!(repo.get_by_categories(&[]).unwrap().is_empty());
     }
     
     #[test]
@@ -372,9 +1653,9 @@ mod tests {
         let repo = create_test_repository();
         
         // Create and save expenses with different dates
-        let mut expense1 = create_test_expense(42.50, "Food", "2025-03-15", "March shopping");
-        let mut expense2 = create_test_expense(55.75, "Food", "2025-04-05", "April shopping");
-        let mut expense3 = create_test_expense(60.25, "Food", "2025-04-20", "Late April shopping");
+        let mut expense1 = create_test_expense(dec!(42.50), "Food", "2025-03-15", "March shopping");
+        let mut expense2 = create_test_expense(dec!(55.75), "Food", "2025-04-05", "April shopping");
+        let mut expense3 = create_test_expense(dec!(60.25), "Food", "2025-04-20", "Late April shopping");
         
         repo.save(&mut expense1).unwrap();
         repo.save(&mut expense2).unwrap();
@@ -396,10 +1677,31 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_get_by_month() {
+        let repo = create_test_repository();
+        
+        let mut expense1 = create_test_expense(dec!(42.50), "Food", "2025-01-31", "January");
+        let mut expense2 = create_test_expense(dec!(55.75), "Food", "2025-02-01", "February start");
+        let mut expense3 = create_test_expense(dec!(60.25), "Food", "2025-02-28", "February end");
+        let mut expense4 = create_test_expense(dec!(10.00), "Food", "2025-03-01", "March");
+        
+        repo.save(&mut expense1).unwrap();
+        repo.save(&mut expense2).unwrap();
+        repo.save(&mut expense3).unwrap();
+        repo.save(&mut expense4).unwrap();
+        
+        let february = repo.get_by_month(2025, 2).unwrap();
+        assert_eq!(february.len(), 2);
+        assert!(february.iter().all(|e| e.description().starts_with("February")));
+        
+        assert!(matches!(repo.get_by_month(2025, 13), Err(RepositoryError::InvalidOperation(_))));
+    }
+    
     #[test]
     fn test_delete_expense() {
         let repo = create_test_repository();
-        let mut expense = create_test_expense(42.50, "Food", "2025-04-11", "Weekly shopping");
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
         
         // Save the expense
         repo.save(&mut expense).unwrap();
@@ -418,15 +1720,136 @@ mod tests {
         assert!(!deleted);
     }
     
+    #[test]
+    fn test_trash_restore_and_empty() {
+        let repo = create_test_repository();
+        let mut kept = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        let mut trashed = create_test_expense(dec!(12.00), "Food", "2025-04-12", "Lunch");
+        let mut purged = create_test_expense(dec!(8.00), "Food", "2025-04-13", "Snack");
+        repo.save(&mut kept).unwrap();
+        repo.save(&mut trashed).unwrap();
+        repo.save(&mut purged).unwrap();
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        // Deleted expenses leave lists and totals but stay in the trash
+        repo.delete(trashed.id().unwrap()).unwrap();
+        assert_eq!(repo.get_all().unwrap().len(), 2);
+        assert_eq!(repo.get_total(start, end).unwrap(), dec!(50.50));
+        assert_eq!(repo.get_deleted().unwrap()[0].id(), trashed.id());
+        assert!(!repo.delete(trashed.id().unwrap()).unwrap());
+        
+        // Restoring brings it back
+        assert!(repo.restore_deleted(trashed.id().unwrap()).unwrap());
+        assert!(!repo.restore_deleted(kept.id().unwrap()).unwrap());
+        assert!(repo.get_by_id(trashed.id().unwrap()).unwrap().is_some());
+        assert!(repo.get_deleted().unwrap().is_empty());
+        
+        // Purging skips the trash, emptying the trash removes rows for good
+        assert!(repo.purge(purged.id().unwrap()).unwrap());
+        repo.delete(trashed.id().unwrap()).unwrap();
+        assert_eq!(repo.empty_trash().unwrap(), 1);
+        assert!(repo.get_deleted().unwrap().is_empty());
+        assert!(!repo.restore_deleted(trashed.id().unwrap()).unwrap());
+        assert_eq!(repo.get_all().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_audit_history() {
+        let repo = create_test_repository().with_audit(true);
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        
+        repo.save(&mut expense).unwrap();
+        let id = expense.id().unwrap();
+        
+        expense.set_amount(dec!(45.00)).unwrap();
+        repo.save(&mut expense).unwrap();
+        repo.delete(id).unwrap();
+        
+        let history = repo.get_history(id).unwrap();
+        let operations: Vec<_> = history.iter().map(|e| e.operation.as_str()).collect();
+        
+        // Insert records every set field, update only the changed amount
+        assert_eq!(operations.iter().filter(|op| **op == "insert").count(), 4);
+        assert_eq!(operations.iter().filter(|op| **op == "update").count(), 1);
+        assert!(operations.contains(&"delete"));
+        
+        let update = history.iter().find(|e| e.operation == "update").unwrap();
+        assert_eq!(update.field, "amount");
+        assert_eq!(update.old_value.as_deref(), Some("42.5"));
+        assert_eq!(update.new_value.as_deref(), Some("45"));
+        
+        // Emptying the trash records the removal of every row in it
+        assert_eq!(repo.empty_trash().unwrap(), 1);
+        let purged: Vec<_> = repo.get_history(id).unwrap().into_iter().filter(|e| e.operation == "purge").collect();
+        assert!(!purged.is_empty());
+        assert!(purged.iter().all(|e| e.new_value.is_none()));
+    }
+    
+    #[test]
+    fn test_recurring_templates() {
+        let repo = create_test_repository();
+        
+        let mut rent = RecurringExpense::new(dec!(950), "Housing".to_string(), 1, "Rent".to_string());
+        let mut music = RecurringExpense::new(dec!(10.99), "Hobbies".to_string(), 15, "Music".to_string());
+        repo.save_recurring(&mut music).unwrap();
+        repo.save_recurring(&mut rent).unwrap();
+        
+        let templates = repo.get_recurring().unwrap();
+        assert_eq!(templates, vec![rent.clone(), music.clone()]);
+        
+        // A month is applied once; the expense is only saved the first time
+        let mut expense = create_test_expense(dec!(950), "Housing", "2025-04-01", "Rent");
+        assert!(repo.apply_recurring(rent.id.unwrap(), "2025-04", &mut expense).unwrap());
+        let mut again = create_test_expense(dec!(950), "Housing", "2025-04-01", "Rent");
+        assert!(!repo.apply_recurring(rent.id.unwrap(), "2025-04", &mut again).unwrap());
+        assert!(again.id().is_none());
+        assert!(!repo.apply_recurring(rent.id.unwrap(), "2025-03", &mut again).unwrap());
+        assert_eq!(repo.get_all().unwrap().len(), 1);
+        assert_eq!(repo.get_recurring().unwrap()[0].last_applied.as_deref(), Some("2025-04"));
+        
+        assert!(repo.delete_recurring(music.id.unwrap()).unwrap());
+        assert!(!repo.delete_recurring(music.id.unwrap()).unwrap());
+        assert!(matches!(repo.save_recurring(&mut music), Err(RepositoryError::NotFound(_))));
+        assert_eq!(repo.get_recurring().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_no_history_without_audit() {
+        let repo = create_test_repository();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        
+        repo.save(&mut expense).unwrap();
+        
+        assert!(repo.get_history(expense.id().unwrap()).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_required_lookups_report_not_found() {
+        let repo = create_test_repository();
+        let mut expense = create_test_expense(dec!(42.50), "Food", "2025-04-11", "Weekly shopping");
+        repo.save(&mut expense).unwrap();
+        let id = expense.id().unwrap();
+        
+        assert_eq!(repo.get_by_id_required(id).unwrap().id(), Some(id));
+        repo.delete_required(id).unwrap();
+        
+        let result = repo.get_by_id_required(id);
+        assert!(matches!(result, Err(RepositoryError::NotFound(_))));
+        assert_eq!(result.unwrap_err().to_string(), format!("Entity not found: No expense found with ID {}", id));
+        
+        assert!(matches!(repo.delete_required(id), Err(RepositoryError::NotFound(_))));
+    }
+    
     #[test]
     fn test_get_category_total() {
         let repo = create_test_repository();
         
         // Create and save multiple expenses in the same category
-        let mut expense1 = create_test_expense(42.50, "Food", "2025-04-05", "Week 1");
-        let mut expense2 = create_test_expense(38.25, "Food", "2025-04-12", "Week 2");
-        let mut expense3 = create_test_expense(45.00, "Food", "2025-04-19", "Week 3");
-        let mut expense4 = create_test_expense(39.75, "Food", "2025-04-26", "Week 4");
+        let mut expense1 = create_test_expense(dec!(42.50), "Food", "2025-04-05", "Week 1");
+        let mut expense2 = create_test_expense(dec!(38.25), "Food", "2025-04-12", "Week 2");
+        let mut expense3 = create_test_expense(dec!(45.00), "Food", "2025-04-19", "Week 3");
+        let mut expense4 = create_test_expense(dec!(39.75), "Food", "2025-04-26", "Week 4");
         
         repo.save(&mut expense1).unwrap();
         repo.save(&mut expense2).unwrap();
@@ -440,7 +1863,354 @@ mod tests {
         let total = repo.get_category_total("Food", start, end).unwrap();
         
         // Should be the sum of all food expenses
-        assert_eq!(total, 42.50 + 38.25 + 45.00 + 39.75);
+        assert_eq!(total, dec!(42.50) + dec!(38.25) + dec!(45.00) + dec!(39.75));
+    }
+    
+    #[test]
+    fn test_get_total() {
+        let repo = create_test_repository();
+        
+        let mut food = create_test_expense(dec!(42.50), "Food", "2025-04-05", "Groceries");
+        let mut rent = create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Rent");
+        let mut earlier = create_test_expense(dec!(10.00), "Food", "2025-03-31", "Snack");
+        
+        repo.save(&mut food).unwrap();
+        repo.save(&mut rent).unwrap();
+        repo.save(&mut earlier).unwrap();
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        assert_eq!(repo.get_total(start, end).unwrap(), dec!(42.50) + dec!(1200.00));
+        
+        // An empty range totals zero
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        assert_eq!(repo.get_total(start, end).unwrap(), dec!(0.0));
+    }
+    
+    #[test]
+    fn test_get_category_totals() {
+        let repo = create_test_repository();
+        
+        let mut expense1 = create_test_expense(dec!(42.50), "Food", "2025-04-05", "Week 1");
+        let mut expense2 = create_test_expense(dec!(38.25), "Food", "2025-04-12", "Week 2");
+        let mut expense3 = create_test_expense(dec!(1200.00), "Housing", "2025-04-01", "Rent");
+        let mut expense4 = create_test_expense(dec!(99.00), "Food", "2025-05-02", "Outside range");
+        
+        repo.save(&mut expense1).unwrap();
+        repo.save(&mut expense2).unwrap();
+        repo.save(&mut expense3).unwrap();
+        repo.save(&mut expense4).unwrap();
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        let totals: std::collections::HashMap<_, _> = repo.get_category_totals(start, end)
+            .unwrap()
+            .into_iter()
+            .collect();
+        
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals["Food"], dec!(42.50) + dec!(38.25));
+        assert_eq!(totals["Housing"], dec!(1200.00));
+    }
+    
+    #[test]
+    fn test_get_monthly_totals() {
+        let repo = create_test_repository();
+        
+        for (amount, date) in [(dec!(10.0), "2025-03-31"), (dec!(20.0), "2025-01-15"), (dec!(5.0), "2025-01-02"), (dec!(99.0), "2025-04-01"), (dec!(7.0), "2024-12-31")] {
+            let mut expense = create_test_expense(amount, "Food", date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let start = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        
+        assert_eq!(repo.get_monthly_totals(start, end).unwrap(), vec![
+            ((2024, 12), dec!(7.0)),
+            ((2025, 1), dec!(25.0)),
+            ((2025, 3), dec!(10.0)),
+        ]);
+    }
+    
+    #[test]
+    fn test_get_adjacent() {
+        let repo = create_test_repository();
+        
+        // Saved out of date order, and two on the same day, which are ordered by ID
+        let mut ids = Vec::new();
+        for date in ["2025-04-10", "2025-04-01", "2025-04-05", "2025-04-05"] {
+            let mut expense = create_test_expense(dec!(10.0), "Food", date, date);
+            repo.save(&mut expense).unwrap();
+            ids.push(expense.id().unwrap());
+        }
+        let id_of = |expense: Option<Expense>| expense.and_then(|e| e.id());
+        
+        // First by date: no previous
+        let (previous, next) = repo.get_adjacent(ids[1]).unwrap();
+        assert_eq!(id_of(previous), None);
+        assert_eq!(id_of(next), Some(ids[2]));
+        
+        let (previous, next) = repo.get_adjacent(ids[2]).unwrap();
+        assert_eq!(id_of(previous), Some(ids[1]));
+        assert_eq!(id_of(next), Some(ids[3]));
+        
+        // Last by date: no next
+        let (previous, next) = repo.get_adjacent(ids[0]).unwrap();
+        assert_eq!(id_of(previous), Some(ids[3]));
+        assert_eq!(id_of(next), None);
+        
+        // Trashed expenses are skipped over
+        repo.delete(ids[3]).unwrap();
+        let (previous, _) = repo.get_adjacent(ids[0]).unwrap();
+        assert_eq!(id_of(previous), Some(ids[2]));
+        
+        assert!(matches!(repo.get_adjacent(999), Err(RepositoryError::NotFound(_))));
+    }
+    
+    #[test]
+    fn test_get_category_transaction_stats() {
+        let repo = create_test_repository();
+        
+        let expenses = [(dec!(600.0), "Rent", "2025-04-01"), (dec!(10.0), "Food", "2025-04-02"), (dec!(15.5), "Food", "2025-04-09"), (dec!(4.5), "Food", "2025-04-20"), (dec!(99.0), "Food", "2025-05-01")];
+        for (amount, category, date) in expenses {
+            let mut expense = create_test_expense(amount, category, date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        let mut stats = repo.get_category_transaction_stats(start, end).unwrap();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        
+        assert_eq!(stats, vec![
+            ("Food".to_string(), dec!(30.0), 3),
+            ("Rent".to_string(), dec!(600.0), 1),
+        ]);
+        
+        // Every expense in the range is counted exactly once
+        let count: usize = stats.iter().map(|(_, _, count)| count).sum();
+        assert_eq!(count, repo.get_by_date_range(start, end).unwrap().len());
+    }
+    
+    #[test]
+    fn test_monthly_rollups_match_live_totals_after_mutations() {
+        let repo = create_test_repository();
+        
+        for (amount, category, date) in [(dec!(10.0), "Food", "2025-01-05"), (dec!(0.1), "Food", "2025-01-06"), (dec!(600.0), "Rent", "2025-02-01")] {
+            repo.save(&mut create_test_expense(amount, category, date, "Before")).unwrap();
+        }
+        assert_eq!(repo.rebuild_monthly_rollups().unwrap(), 2);
+        
+        // Deterministic pseudo-random sequence, so failures are reproducible
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        let categories = ["Food", "Rent", "Hobbies"];
+        
+        for _ in 0..300 {
+            let ids: Vec<i64> = repo.conn.prepare("SELECT id FROM expenses").unwrap()
+                .query_map([], |row| row.get(0)).unwrap()
+                .collect::<Result<_, _>>().unwrap();
+            let id = if ids.is_empty() { 0 } else { ids[next(ids.len() as u64) as usize] };
+            let amount = Decimal::new(next(10_000) as i64 + 1, 2);
+            let category = categories[next(3) as usize];
+            let date = format!("2025-{:02}-{:02}", next(3) + 1, next(28) + 1);
+            
+            match next(6) {
+                0 | 1 => repo.save(&mut create_test_expense(amount, category, &date, "Random")).unwrap(),
+                2 => if let Some(mut expense) = repo.get_by_id(id).unwrap() {
+                    expense.set_amount(amount).unwrap();
+                    expense.set_category(Category::new(category, None).unwrap());
+                    expense.set_date(NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap()).unwrap();
+                    repo.save(&mut expense).unwrap();
+                },
+                3 => { repo.delete(id).unwrap(); },
+                4 => { repo.restore_deleted(id).unwrap(); },
+                _ => { repo.purge(id).unwrap(); },
+            }
+        }
+        
+        let sorted = |mut totals: Vec<(String, Decimal)>| {
+            totals.sort();
+            totals
+        };
+        
+        for (start, end) in [("2025-01-01", "2025-01-31"), ("2025-02-01", "2025-03-31"), ("2024-12-01", "2025-12-31")] {
+            let start = NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap();
+            let end = NaiveDate::parse_from_str(end, "%Y-%m-%d").unwrap();
+            
+            assert!(repo.rollup_months(start, end).unwrap().is_some());
+            assert_eq!(sorted(repo.get_category_totals(start, end).unwrap()), sorted(repo.live_category_totals(start, end).unwrap()));
+            assert_eq!(repo.get_monthly_totals(start, end).unwrap(), repo.live_monthly_totals(start, end).unwrap());
+        }
+        
+        // Ranges that don't cover whole months are still aggregated live
+        let start = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        assert!(repo.rollup_months(start, end).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_get_monthly_category_totals() {
+        let repo = create_test_repository();
+        
+        for (amount, category, date) in [(dec!(10.0), "Food", "2025-01-05"), (dec!(5.5), "Food", "2025-01-20"), (dec!(600.0), "Rent", "2025-01-01"), (dec!(7.0), "Food", "2025-03-02"), (dec!(1.0), "Food", "2025-04-01")] {
+            repo.save(&mut create_test_expense(amount, category, date, "Shopping")).unwrap();
+        }
+        
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        
+        assert_eq!(repo.get_monthly_category_totals(start, end).unwrap(), vec![
+            ((2025, 1), "Food".to_string(), dec!(15.5)),
+            ((2025, 1), "Rent".to_string(), dec!(600.0)),
+            ((2025, 3), "Food".to_string(), dec!(7.0)),
+        ]);
+    }
+    
+    #[test]
+    fn test_get_distinct_categories() {
+        let repo = create_test_repository();
+        
+        for (amount, category) in [(dec!(10.0), "Food"), (dec!(20.0), "Rent"), (dec!(5.0), "Food"), (dec!(7.0), "Hobbies")] {
+            let mut expense = create_test_expense(amount, category, "2025-04-01", "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        // A trashed expense still keeps its category in use
+        let hobby = repo.get_by_category("Hobbies").unwrap()[0].id().unwrap();
+        repo.delete(hobby).unwrap();
+        
+        assert_eq!(repo.get_distinct_categories().unwrap(), vec!["Food", "Hobbies", "Rent"]);
+    }
+    
+    #[test]
+    fn test_get_category_amount_stats() {
+        let repo = create_test_repository();
+        
+        let expenses = [(dec!(600.0), "Rent", "2025-04-01"), (dec!(10.0), "Food", "2025-04-02"), (dec!(15.5), "Food", "2025-04-09"), (dec!(4.5), "Food", "2025-04-20"), (dec!(2.0), "Food", "2025-04-25"), (dec!(99.0), "Food", "2025-05-01")];
+        for (amount, category, date) in expenses {
+            let mut expense = create_test_expense(amount, category, date, "Shopping");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        let mut stats = repo.get_category_amount_stats(start, end).unwrap();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        
+        assert_eq!(stats, vec![
+            ("Food".to_string(), AmountStats { min: dec!(2.0), max: dec!(15.5), median: dec!(7.25) }),
+            ("Rent".to_string(), AmountStats { min: dec!(600.0), max: dec!(600.0), median: dec!(600.0) }),
+        ]);
+        
+        // A range without expenses has no stats rather than zeros
+        let empty = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert!(repo.get_category_amount_stats(empty, empty).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_get_day_of_month_totals() {
+        let repo = create_test_repository();
+        
+        for (amount, date) in [(dec!(800.0), "2025-01-01"), (dec!(800.0), "2025-02-01"), (dec!(12.0), "2025-01-15"), (dec!(3.0), "2025-01-31"), (dec!(50.0), "2025-03-01")] {
+            let mut expense = create_test_expense(amount, "Housing", date, "Rent");
+            repo.save(&mut expense).unwrap();
+        }
+        
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 2, 28).unwrap();
+        
+        assert_eq!(repo.get_day_of_month_totals(start, end).unwrap(), vec![
+            (1, dec!(1600.0)),
+            (15, dec!(12.0)),
+            (31, dec!(3.0)),
+        ]);
+    }
+    
+    #[test]
+    fn test_get_secondary_category_totals() {
+        let repo = create_test_repository();
+        
+        let mut lunch = create_test_expense(dec!(18.00), "Food", "2025-04-03", "Client lunch");
+        lunch.set_secondary_category(Some("Work".to_string()));
+        let mut taxi = create_test_expense(dec!(32.00), "Transport", "2025-04-03", "Taxi to client");
+        taxi.set_secondary_category(Some("Work".to_string()));
+        let mut groceries = create_test_expense(dec!(60.00), "Food", "2025-04-05", "Groceries");
+        
+        repo.save(&mut lunch).unwrap();
+        repo.save(&mut taxi).unwrap();
+        repo.save(&mut groceries).unwrap();
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        assert_eq!(repo.get_by_id(lunch.id().unwrap()).unwrap().unwrap().secondary_category(), Some("Work"));
+        assert_eq!(repo.get_secondary_category_totals(start, end).unwrap(), vec![("Work".to_string(), dec!(50.00))]);
+        
+        // The primary totals are unaffected by the secondary category
+        assert_eq!(repo.get_category_total("Food", start, end).unwrap(), dec!(78.00));
+        assert_eq!(repo.get_total(start, end).unwrap(), dec!(110.00));
+    }
+    
+    #[test]
+    fn test_get_description_totals() {
+        let repo = create_test_repository();
+        
+        let mut expense1 = create_test_expense(dec!(3.50), "Food", "2025-04-01", "Corner Cafe");
+        let mut expense2 = create_test_expense(dec!(4.00), "Food", "2025-04-02", "corner cafe ");
+        let mut expense3 = create_test_expense(dec!(5.00), "Food", "2025-04-03", "Corner Cafe");
+        let mut expense4 = create_test_expense(dec!(20.00), "Transport", "2025-04-04", "Taxi");
+        
+        repo.save(&mut expense1).unwrap();
+        repo.save(&mut expense2).unwrap();
+        repo.save(&mut expense3).unwrap();
+        repo.save(&mut expense4).unwrap();
+        
+        let start = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        
+        let exact = repo.get_description_totals(start, end, false).unwrap();
+        assert_eq!(exact.len(), 3);
+        assert_eq!(exact[0], ("Taxi".to_string(), dec!(20.00), 1));
+        assert_eq!(exact[1], ("Corner Cafe".to_string(), dec!(8.50), 2));
+        
+        let normalized = repo.get_description_totals(start, end, true).unwrap();
+        assert_eq!(normalized.len(), 2);
+        assert_eq!(normalized[1], ("corner cafe".to_string(), dec!(12.50), 3));
+    }
+    
+    #[test]
+    fn test_get_date_histogram() {
+        let repo = create_test_repository();
+        
+        let mut expense1 = create_test_expense(dec!(10.00), "Food", "2025-04-01", "Breakfast");
+        let mut expense2 = create_test_expense(dec!(5.50), "Food", "2025-04-01", "Coffee");
+        let mut expense3 = create_test_expense(dec!(20.00), "Transport", "2025-04-04", "Taxi");
+        
+        repo.save(&mut expense1).unwrap();
+        repo.save(&mut expense2).unwrap();
+        repo.save(&mut expense3).unwrap();
+        
+        let start = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 4, 4).unwrap();
+        
+        let histogram = repo.get_date_histogram(start, end).unwrap();
+        let totals: Vec<Decimal> = histogram.iter().map(|(_, total)| *total).collect();
+        
+        // Every day in the range is present, including the zero-spend ones
+        assert_eq!(histogram.len(), 5);
+        assert_eq!(histogram[0].0, start);
+        assert_eq!(histogram[4].0, end);
+        assert_eq!(totals, vec![dec!(0.0), dec!(15.50), dec!(0.0), dec!(0.0), dec!(20.00)]);
     }
     
     #[test]
@@ -448,10 +2218,10 @@ mod tests {
         let repo = create_test_repository();
         
         // Create expenses across different months and categories
-        let mut expense1 = create_test_expense(100.00, "Food", "2025-03-15", "March food");
-        let mut expense2 = create_test_expense(200.00, "Food", "2025-04-15", "April food");
-        let mut expense3 = create_test_expense(300.00, "Housing", "2025-03-01", "March rent");
-        let mut expense4 = create_test_expense(300.00, "Housing", "2025-04-01", "April rent");
+        let mut expense1 = create_test_expense(dec!(100.00), "Food", "2025-03-15", "March food");
+        let mut expense2 = create_test_expense(dec!(200.00), "Food", "2025-04-15", "April food");
+        let mut expense3 = create_test_expense(dec!(300.00), "Housing", "2025-03-01", "March rent");
+        let mut expense4 = create_test_expense(dec!(300.00), "Housing", "2025-04-01", "April rent");
         
         repo.save(&mut expense1).unwrap();
         repo.save(&mut expense2).unwrap();
@@ -472,10 +2242,10 @@ mod tests {
         
         // Check food average: (100 + 200) / 2 months = 150
         assert!(avg_map.contains_key("Food"));
-        assert!((avg_map["Food"] - 150.0).abs() < 0.001);
+        assert!((avg_map["Food"] - dec!(150.0)).abs() < dec!(0.001));
         
         // Check housing average: (300 + 300) / 2 months = 300
         assert!(avg_map.contains_key("Housing"));
-        assert!((avg_map["Housing"] - 300.0).abs() < 0.001);
+        assert!((avg_map["Housing"] - dec!(300.0)).abs() < dec!(0.001));
     }
 }
@@ -9,10 +9,113 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
             category TEXT NOT NULL,
             category_description TEXT,
             date TEXT NOT NULL,
-            description TEXT NOT NULL
+            description TEXT NOT NULL,
+            currency TEXT,
+            deleted_at TEXT,
+            secondary_category TEXT,
+            account TEXT,
+            updated_at TEXT
         )",
         [],
     )?;
     
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            expense_id INTEGER NOT NULL,
+            operation TEXT NOT NULL,
+            field TEXT NOT NULL,
+            old_value TEXT,
+            new_value TEXT,
+            changed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recurring (
+            id INTEGER PRIMARY KEY,
+            amount REAL NOT NULL,
+            category TEXT NOT NULL,
+            day_of_month INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            last_applied TEXT
+        )",
+        [],
+    )?;
+    
+    // Bring databases created by older versions up to date
+    add_column_if_missing(conn, "expenses", "currency", "TEXT")?;
+    add_column_if_missing(conn, "expenses", "deleted_at", "TEXT")?;
+    add_column_if_missing(conn, "expenses", "secondary_category", "TEXT")?;
+    add_column_if_missing(conn, "expenses", "account", "TEXT")?;
+    add_column_if_missing(conn, "expenses", "updated_at", "TEXT")?;
+    
+    // Dates are stored as YYYY-MM-DD, so they sort as text and range queries can use the index
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_date ON expenses(date)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_category ON expenses(category)", [])?;
+    
+    Ok(())
+}
+
+/// Create the `monthly_rollups` table of spending outside the trash per (YYYY-MM, category),
+/// in whole cents, and the triggers that keep it in step with every insert, update, trash and purge.
+/// Databases without the table are aggregated from the expenses on every query.
+pub fn create_monthly_rollups(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS monthly_rollups (
+            month TEXT NOT NULL,
+            category TEXT NOT NULL,
+            total_cents INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY (month, category)
+        );
+        
+        CREATE TRIGGER IF NOT EXISTS monthly_rollups_insert AFTER INSERT ON expenses
+        WHEN NEW.deleted_at IS NULL
+        BEGIN
+            INSERT INTO monthly_rollups (month, category, total_cents, count)
+            VALUES (substr(NEW.date, 1, 7), NEW.category, CAST(ROUND(NEW.amount * 100) AS INTEGER), 1)
+            ON CONFLICT (month, category) DO UPDATE
+            SET total_cents = total_cents + excluded.total_cents, count = count + 1;
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS monthly_rollups_delete AFTER DELETE ON expenses
+        WHEN OLD.deleted_at IS NULL
+        BEGIN
+            UPDATE monthly_rollups
+            SET total_cents = total_cents - CAST(ROUND(OLD.amount * 100) AS INTEGER), count = count - 1
+            WHERE month = substr(OLD.date, 1, 7) AND category = OLD.category;
+            DELETE FROM monthly_rollups
+            WHERE month = substr(OLD.date, 1, 7) AND category = OLD.category AND count <= 0;
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS monthly_rollups_update AFTER UPDATE OF amount, category, date, deleted_at ON expenses
+        BEGIN
+            UPDATE monthly_rollups
+            SET total_cents = total_cents - CAST(ROUND(OLD.amount * 100) AS INTEGER), count = count - 1
+            WHERE month = substr(OLD.date, 1, 7) AND category = OLD.category AND OLD.deleted_at IS NULL;
+            DELETE FROM monthly_rollups
+            WHERE month = substr(OLD.date, 1, 7) AND category = OLD.category AND count <= 0;
+            INSERT INTO monthly_rollups (month, category, total_cents, count)
+            SELECT substr(NEW.date, 1, 7), NEW.category, CAST(ROUND(NEW.amount * 100) AS INTEGER), 1
+            WHERE NEW.deleted_at IS NULL
+            ON CONFLICT (month, category) DO UPDATE
+            SET total_cents = total_cents + excluded.total_cents, count = count + 1;
+        END;"
+    )
+}
+
+/// Add a column to an existing table unless it is already present
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    
+    if columns.any(|name| name.as_deref() == Ok(column)) {
+        return Ok(());
+    }
+    
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    
     Ok(())
 }
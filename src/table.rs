@@ -0,0 +1,234 @@
+use serde::{Serialize, Deserialize};
+
+/// How tables in list and summary output are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableStyle {
+    /// Fixed-width columns with `-` separator lines
+    #[default]
+    Plain,
+    /// Columns enclosed in `+---+` borders
+    AsciiBox,
+    /// GitHub-flavored markdown table
+    Markdown,
+    /// Columns sized to their content without separator lines
+    Compact,
+}
+
+/// A simple text table rendered in one of the supported styles
+pub struct Table {
+    headers: Vec<String>,
+    widths: Vec<usize>,
+    rows: Vec<Vec<String>>,
+    rule_width: Option<usize>,
+    plain_header: bool,
+}
+
+impl Table {
+    /// Create a table from `(header, minimum width)` column definitions.
+    /// The minimum widths are only used by the plain style.
+    pub fn new(columns: &[(&str, usize)]) -> Self {
+        Self {
+            headers: columns.iter().map(|(header, _)| header.to_string()).collect(),
+            widths: columns.iter().map(|(_, width)| *width).collect(),
+            rows: Vec::new(),
+            rule_width: None,
+            plain_header: true,
+        }
+    }
+    
+    /// Use a fixed length for the separator lines of the plain style
+    pub fn with_rule_width(mut self, width: usize) -> Self {
+        self.rule_width = Some(width);
+        self
+    }
+    
+    /// Omit the header row (and its separator) in the plain style
+    pub fn without_plain_header(mut self) -> Self {
+        self.plain_header = false;
+        self
+    }
+    
+    pub fn add_row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+    
+    /// Render the table as lines of text without a trailing newline
+    pub fn render(&self, style: TableStyle) -> String {
+        let lines = match style {
+            TableStyle::Plain => self.render_plain(),
+            TableStyle::AsciiBox => self.render_ascii_box(),
+            TableStyle::Markdown => self.render_markdown(),
+            TableStyle::Compact => self.render_compact(),
+        };
+        
+        lines.join("\n")
+    }
+    
+    fn render_plain(&self) -> Vec<String> {
+        let rule = "-".repeat(self.rule_width.unwrap_or_else(|| self.widths.iter().sum::<usize>() + self.widths.len()));
+        let format_row = |cells: &[String]| {
+            cells.iter()
+                .zip(&self.widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        
+        let mut lines = Vec::new();
+        if self.plain_header {
+            lines.push(format_row(&self.headers));
+            lines.push(rule.clone());
+        }
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        lines.push(rule);
+        
+        lines
+    }
+    
+    fn render_ascii_box(&self) -> Vec<String> {
+        let widths = self.content_widths();
+        let border = format!("+{}+", widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>().join("+"));
+        let format_row = |cells: &[String]| {
+            format!("| {} |", Self::pad_cells(cells, &widths).join(" | "))
+        };
+        
+        let mut lines = vec![border.clone(), format_row(&self.headers), border.clone()];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        lines.push(border);
+        
+        lines
+    }
+    
+    fn render_markdown(&self) -> Vec<String> {
+        let escape = |cells: &[String]| -> Vec<String> {
+            cells.iter().map(|cell| cell.replace('|', "\\|")).collect()
+        };
+        let headers = escape(&self.headers);
+        let rows: Vec<_> = self.rows.iter().map(|row| escape(row)).collect();
+        
+        let widths = Self::widths_of(&headers, &rows).into_iter().map(|w| w.max(3)).collect::<Vec<_>>();
+        let format_row = |cells: &[String]| {
+            format!("| {} |", Self::pad_cells(cells, &widths).join(" | "))
+        };
+        
+        let mut lines = vec![
+            format_row(&headers),
+            format!("|{}|", widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>().join("|")),
+        ];
+        lines.extend(rows.iter().map(|row| format_row(row)));
+        
+        lines
+    }
+    
+    fn render_compact(&self) -> Vec<String> {
+        let widths = self.content_widths();
+        let format_row = |cells: &[String]| {
+            Self::pad_cells(cells, &widths).join("  ").trim_end().to_string()
+        };
+        
+        let mut lines = vec![format_row(&self.headers)];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        
+        lines
+    }
+    
+    fn content_widths(&self) -> Vec<usize> {
+        Self::widths_of(&self.headers, &self.rows)
+    }
+    
+    fn widths_of(headers: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        
+        widths
+    }
+    
+    fn pad_cells(cells: &[String], widths: &[usize]) -> Vec<String> {
+        cells.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_table() -> Table {
+        let mut table = Table::new(&[("ID", 5), ("Category", 10), ("Amount", 8)]);
+        table.add_row(vec!["1".to_string(), "Food".to_string(), "42.50".to_string()]);
+        table.add_row(vec!["12".to_string(), "Transport".to_string(), "3.20".to_string()]);
+        table
+    }
+    
+    #[test]
+    fn render_plain_matches_fixed_width_layout() {
+        let rendered = sample_table().render(TableStyle::Plain);
+        let expected = [
+            format!("{:<5} {:<10} {:<8}", "ID", "Category", "Amount"),
+            "-".repeat(26),
+            format!("{:<5} {:<10} {:<8}", "1", "Food", "42.50"),
+            format!("{:<5} {:<10} {:<8}", "12", "Transport", "3.20"),
+            "-".repeat(26),
+        ].join("\n");
+        
+        assert_eq!(rendered, expected);
+    }
+    
+    #[test]
+    fn render_plain_without_header() {
+        let rendered = sample_table().with_rule_width(10).without_plain_header().render(TableStyle::Plain);
+        let lines: Vec<_> = rendered.lines().collect();
+        
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("1 "));
+        assert_eq!(lines[2], "-".repeat(10));
+    }
+    
+    #[test]
+    fn render_ascii_box() {
+        let rendered = sample_table().render(TableStyle::AsciiBox);
+        
+        assert_eq!(rendered, "\
++----+-----------+--------+
+| ID | Category  | Amount |
++----+-----------+--------+
+| 1  | Food      | 42.50  |
+| 12 | Transport | 3.20   |
++----+-----------+--------+");
+    }
+    
+    #[test]
+    fn render_markdown_escapes_pipes() {
+        let mut table = Table::new(&[("Description", 0)]);
+        table.add_row(vec!["a|b".to_string()]);
+        
+        assert_eq!(table.render(TableStyle::Markdown), "\
+| Description |
+|-------------|
+| a\\|b        |");
+    }
+    
+    #[test]
+    fn render_compact() {
+        let rendered = sample_table().render(TableStyle::Compact);
+        
+        assert_eq!(rendered, "\
+ID  Category   Amount
+1   Food       42.50
+12  Transport  3.20");
+    }
+    
+    #[test]
+    fn deserialize_table_style() {
+        let style: TableStyle = serde_yaml::from_str("ascii-box").unwrap();
+        assert_eq!(style, TableStyle::AsciiBox);
+    }
+}